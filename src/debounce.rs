@@ -0,0 +1,92 @@
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+/// Coalesces bursts of file-change notifications into at most one refresh
+/// per window, so a log written in quick bursts isn't re-parsed per write.
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    window: Duration,
+    pending: bool,
+    last_refresh: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: false,
+            last_refresh: None,
+        }
+    }
+
+    /// Record that something changed.
+    pub fn notify(&mut self) {
+        self.pending = true;
+    }
+
+    /// Take every notification waiting in `rx`; returns how many there were.
+    pub fn drain(&mut self, rx: &Receiver<()>) -> usize {
+        let count = rx.try_iter().count();
+        if count > 0 {
+            self.notify();
+        }
+        count
+    }
+
+    /// Whether a change is waiting and the last refresh is a window ago.
+    pub fn ready(&self, now: Instant) -> bool {
+        self.pending
+            && self
+                .last_refresh
+                .is_none_or(|last| now.duration_since(last) >= self.window)
+    }
+
+    /// Record a refresh (for whatever reason); it covers every change so far.
+    pub fn refreshed(&mut self, now: Instant) {
+        self.pending = false;
+        self.last_refresh = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    const WINDOW: Duration = Duration::from_millis(250);
+
+    #[test]
+    fn test_burst_refreshes_once_per_window() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        assert!(!debouncer.ready(start));
+
+        // The first change refreshes straight away.
+        debouncer.notify();
+        assert!(debouncer.ready(start));
+        debouncer.refreshed(start);
+
+        // A burst right after waits for the window, then refreshes once.
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..50 {
+            tx.send(()).unwrap();
+        }
+        assert_eq!(debouncer.drain(&rx), 50);
+        assert_eq!(debouncer.drain(&rx), 0);
+        assert!(!debouncer.ready(start + Duration::from_millis(100)));
+        let later = start + WINDOW;
+        assert!(debouncer.ready(later));
+        debouncer.refreshed(later);
+        assert!(!debouncer.ready(later + WINDOW));
+    }
+
+    #[test]
+    fn test_other_refreshes_cover_pending_changes() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(WINDOW);
+        debouncer.notify();
+        // A timed poll already picked the change up.
+        debouncer.refreshed(start);
+        assert!(!debouncer.ready(start + WINDOW * 2));
+    }
+}
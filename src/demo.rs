@@ -0,0 +1,165 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::log_parser::FLAME_ELEMENTIUM_ID;
+
+/// A secondary drop picked up once per run, so the log has more than FE.
+pub const DEMO_DROP_ID: &str = "5028";
+
+/// Flame Elementium in the bag when the generated log starts.
+pub const DEMO_START_FE: u32 = 1000;
+
+/// Zone the generated runs take place in (tier 200).
+const DEMO_MAP: &str = "/Game/Art/Maps/01SD/XZ_YuJinZhiXiBiNanSuo200/XZ_YuJinZhiXiBiNanSuo200";
+
+/// Zone visited between runs.
+const DEMO_HIDEOUT: &str = "/Game/Art/Maps/00YJ/Hideout/Hideout";
+
+/// Pickups each run's FE is split across.
+const PICKUPS_PER_RUN: u32 = 3;
+
+/// Writes UE_game.log lines with the timestamps the game puts on them.
+struct LogWriter {
+    text: String,
+    time: DateTime<Utc>,
+    frame: u32,
+}
+
+impl LogWriter {
+    fn line(&mut self, body: &str) {
+        self.text.push_str(&format!(
+            "[{}][{:>3}]GameLog: Display: [Game] {}\n",
+            self.time.format("%Y.%m.%d-%H.%M.%S:%3f"),
+            self.frame % 1000,
+            body
+        ));
+        self.frame += 7;
+    }
+
+    fn wait(&mut self, secs: i64) {
+        self.time += Duration::seconds(secs);
+    }
+
+    fn enter(&mut self, zone: &str) {
+        self.line(&format!(
+            "SceneLevelMgr@ OpenMainWorld END! InMainLevelPath = {}",
+            zone
+        ));
+    }
+
+    fn pickup(&mut self, slot: u32, id: &str, num: u32) {
+        self.line("ItemChange@ ProtoName=PickItems start");
+        self.line(&format!(
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = {} ConfigBaseId = {} Num = {}",
+            slot, id, num
+        ));
+        self.line("ItemChange@ ProtoName=PickItems end");
+    }
+}
+
+/// A synthetic `UE_game.log`: a game launch and an inventory sort, then
+/// `runs` map runs that each pick up `fe_per_run` Flame Elementium (in a
+/// few pickups) and one [`DEMO_DROP_ID`], with a hideout visit after each.
+///
+/// Parsing it gives a delta of `runs * fe_per_run` FE and `runs` of the
+/// secondary drop. Runs are five minutes apart, starting at `start`.
+pub fn generate_log(runs: u32, fe_per_run: u32, start: DateTime<Utc>) -> String {
+    let mut log = LogWriter {
+        text: format!("Log file open, {}\n", start.format("%m/%d/%y %H:%M:%S")),
+        time: start,
+        frame: 0,
+    };
+    log.enter(DEMO_HIDEOUT);
+    log.wait(30);
+    log.line("ItemChange@ ProtoName=ResetItemsLayout start");
+    log.line("ItemChange@ ProtoName=ResetItemsLayout end");
+    log.line(&format!(
+        "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = {} Num = {}",
+        FLAME_ELEMENTIUM_ID, DEMO_START_FE
+    ));
+    log.line(&format!(
+        "BagMgr@:InitBagData PageId = 102 SlotId = 1 ConfigBaseId = {} Num = 0",
+        DEMO_DROP_ID
+    ));
+
+    let mut fe = DEMO_START_FE;
+    for run in 1..=runs {
+        log.wait(20);
+        log.enter(DEMO_MAP);
+        for pickup in 0..PICKUPS_PER_RUN {
+            log.wait(60);
+            fe += fe_per_run / PICKUPS_PER_RUN;
+            if pickup == PICKUPS_PER_RUN - 1 {
+                fe += fe_per_run % PICKUPS_PER_RUN;
+            }
+            log.pickup(0, FLAME_ELEMENTIUM_ID, fe);
+        }
+        log.wait(50);
+        log.pickup(1, DEMO_DROP_ID, run);
+        log.wait(50);
+        log.enter(DEMO_HIDEOUT);
+    }
+    log.text
+}
+
+/// [`generate_log`] starting at a fixed time, for reproducible output.
+pub fn demo_log(runs: u32, fe_per_run: u32) -> String {
+    generate_log(
+        runs,
+        fe_per_run,
+        Utc.with_ymd_and_hms(2024, 8, 21, 8, 0, 0).unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::{parse_line, parse_log, LogEvent};
+    use crate::log_source::StringLogSource;
+    use crate::settings::Settings;
+
+    #[test]
+    fn test_demo_log_parses_into_expected_deltas() {
+        let log = demo_log(4, 250);
+        let snapshot = parse_log(
+            &mut StringLogSource(log.clone()),
+            &Settings::default().loot_contexts,
+        )
+        .unwrap();
+        let loot = snapshot.loot;
+        assert!(loot.baseline_found);
+        assert_eq!(loot.primary_delta(FLAME_ELEMENTIUM_ID), 1000);
+        let fe = loot
+            .items
+            .iter()
+            .find(|i| i.config_base_id == FLAME_ELEMENTIUM_ID)
+            .unwrap();
+        assert_eq!(fe.current, DEMO_START_FE + 1000);
+        let drop = loot
+            .items
+            .iter()
+            .find(|i| i.config_base_id == DEMO_DROP_ID)
+            .unwrap();
+        assert_eq!(drop.delta, 4);
+        assert_eq!(loot.total_events, 4 * (PICKUPS_PER_RUN as usize + 1));
+        assert_eq!(snapshot.map.as_deref(), Some("Hideout"));
+
+        let maps: Vec<Option<u32>> = log
+            .lines()
+            .filter_map(|line| match parse_line(line) {
+                Some(LogEvent::Map(m)) => Some(m.tier),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(maps.len(), 9);
+        assert_eq!(maps.iter().filter(|t| **t == Some(200)).count(), 4);
+    }
+
+    #[test]
+    fn test_demo_log_remainder_goes_to_last_pickup() {
+        let log = demo_log(1, 100);
+        assert!(log.contains("ConfigBaseId = 100300 Num = 1033\n"));
+        assert!(log.contains("ConfigBaseId = 100300 Num = 1066\n"));
+        assert!(log.contains("ConfigBaseId = 100300 Num = 1100\n"));
+        assert!(log.starts_with("Log file open, 08/21/24 08:00:00\n"));
+    }
+}
@@ -1,142 +1,118 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Local, Utc};
 use eframe::egui;
-
-use crate::log_parser::{self, ItemDelta, LogEvent, LootSummary, FLAME_ELEMENTIUM_ID};
+use egui_plot::{Bar, BarChart, Plot, PlotPoint, Text};
+use tracing::warn;
+
+use crate::debounce::Debouncer;
+use crate::log_parser::{self, ItemDelta};
+use crate::models;
+use crate::records::RecordKind;
+use crate::scoring::{self, ScoreMode};
+use crate::settings;
+use crate::stats;
 use crate::storage;
+use crate::tracker::{LogChange, Tracker, POLL_INTERVAL};
+use crate::viewmodel::{
+    self, Action, ActionEffect, BulkAction, ItemAction, ItemDisplay, LiveRate, NumberLocale,
+    StatKind, Tab, TargetStatus, TrackerView,
+};
 
-/// Interval between log re-parses.
-const POLL_INTERVAL: Duration = Duration::from_secs(3);
-
-// ── Per-map run tracking ──────────────────────────────────────────────
+/// Shortest gap between log re-parses triggered by the file watcher.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
 
-#[derive(Debug, Clone)]
-struct MapRun {
-    map_name: String,
-    start: Instant,
-    end: Option<Instant>,
-    loot_gained: HashMap<String, i64>,
-}
+/// Saved sessions listed on the Runs tab, newest first.
+const SAVED_SESSIONS_SHOWN: usize = 10;
 
-impl MapRun {
-    fn duration_secs(&self) -> f64 {
-        let end = self.end.unwrap_or_else(Instant::now);
-        (end - self.start).as_secs_f64()
-    }
+// ── Application state ─────────────────────────────────────────────────
 
-    fn total_items(&self) -> i64 {
-        self.loot_gained.values().sum()
-    }
+/// The "Set price" window for one item.
+struct PriceEditor {
+    id: String,
+    name: String,
+    price: f64,
 }
 
-// ── Session ───────────────────────────────────────────────────────────
-
-#[derive(Debug, Clone)]
-struct TrackerSession {
-    start: Instant,
-    start_wall: DateTime<Utc>,
-    cumulative_loot: HashMap<String, i64>,
-    runs: Vec<MapRun>,
+/// Free-text settings as typed, before they are checked.
+struct SettingsDraft {
+    primary_currency: String,
+    thousands_separator: String,
+    decimal_separator: String,
+    log_url: String,
+    loot_contexts: String,
 }
 
-impl TrackerSession {
-    fn new() -> Self {
+impl SettingsDraft {
+    fn from(settings: &settings::Settings) -> Self {
         Self {
-            start: Instant::now(),
-            start_wall: Utc::now(),
-            cumulative_loot: HashMap::new(),
-            runs: Vec::new(),
-        }
-    }
-
-    fn elapsed_secs(&self) -> f64 {
-        self.start.elapsed().as_secs_f64()
-    }
-
-    fn total_items(&self) -> i64 {
-        self.cumulative_loot.values().sum()
-    }
-
-    /// Total Flame Elementium gained during this session.
-    fn flame_elementium(&self) -> i64 {
-        self.cumulative_loot
-            .get(FLAME_ELEMENTIUM_ID)
-            .copied()
-            .unwrap_or(0)
-    }
-
-    /// Flame Elementium gained per hour during this session.
-    fn flame_elementium_per_hour(&self) -> f64 {
-        let secs = self.elapsed_secs();
-        if secs < 1.0 {
-            return 0.0;
+            primary_currency: settings.primary_currency.clone(),
+            thousands_separator: settings.thousands_separator.to_string(),
+            decimal_separator: settings.number_locale().decimal.to_string(),
+            log_url: settings.log_url.clone().unwrap_or_default(),
+            loot_contexts: settings.loot_contexts.join(", "),
         }
-        self.flame_elementium() as f64 / secs * 3600.0
     }
 }
 
-// ── Application state ─────────────────────────────────────────────────
-
 pub struct TrackerApp {
-    // Log file
-    log_path: Option<PathBuf>,
-    log_status: String,
+    // Log parsing and session state
+    tracker: Tracker,
 
     // Polling
     last_poll: Instant,
 
-    // Current parsed data
-    loot: Option<LootSummary>,
-    inventory: Vec<log_parser::BagEvent>,
-    current_map: Option<String>,
-
-    // Session
-    session: Option<TrackerSession>,
-
-    // Previous loot state for delta tracking
-    prev_loot: HashMap<String, i64>,
+    // Primary currency sampled at each poll, for the last-60s rate
+    live_rate: LiveRate,
 
     // UI tab
     active_tab: Tab,
 
-    // File watcher channel
+    // Problem found in sessions.json at startup, shown until dismissed
+    data_warning: Option<String>,
+
+    // Last sort time when the sort reminder was dismissed; a later sort
+    // going stale brings it back
+    sort_reminder_dismissed: Option<Option<DateTime<Utc>>>,
+
+    // Text being typed into the Settings tab, applied when the field loses focus
+    settings_draft: SettingsDraft,
+    settings_error: Option<String>,
+
+    // Item whose hand-set price is being edited
+    price_editor: Option<PriceEditor>,
+
+    // Rows ticked in the loot and inventory tables (id → name), for the bulk-action bar
+    selection: BTreeMap<String, String>,
+
+    // File watcher channel, and bursts of its events coalesced
     _watcher: Option<notify::RecommendedWatcher>,
     watch_rx: Option<mpsc::Receiver<()>>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Tab {
-    FlameElementium,
-    Items,
-    Inventory,
-    Runs,
+    watch_debounce: Debouncer,
 }
 
 impl TrackerApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let log_path = storage::detect_game_log();
-        let log_status = match &log_path {
-            Some(p) => format!("Log found: {}", p.display()),
-            None => "UE_game.log not found – start Torchlight Infinite with logging enabled"
-                .to_string(),
-        };
-
+        let tracker = Tracker::new();
         let mut app = Self {
-            log_path,
-            log_status,
+            active_tab: tracker.settings.gui_tab,
+            settings_draft: SettingsDraft::from(&tracker.settings),
+            settings_error: None,
+            price_editor: None,
+            selection: BTreeMap::new(),
+            tracker,
             last_poll: Instant::now() - POLL_INTERVAL, // trigger immediate first poll
-            loot: None,
-            inventory: Vec::new(),
-            current_map: None,
-            session: None,
-            prev_loot: HashMap::new(),
-            active_tab: Tab::FlameElementium,
+            live_rate: LiveRate::default(),
+            data_warning: storage::data_file_path()
+                .and_then(|path| storage::load_sessions(&path))
+                .ok()
+                .and_then(|sessions| models::multiple_active_warning(&sessions)),
+            sort_reminder_dismissed: None,
             _watcher: None,
             watch_rx: None,
+            watch_debounce: Debouncer::new(WATCH_DEBOUNCE),
         };
 
         // Set up file watcher if log exists
@@ -151,17 +127,23 @@ impl TrackerApp {
     fn setup_watcher(&mut self) {
         use notify::{RecursiveMode, Watcher};
 
-        if let Some(ref log_path) = self.log_path {
+        if let Some(ref log_path) = self.tracker.log_path {
             let (tx, rx) = mpsc::channel();
             let sender = tx;
             let mut watcher =
-                notify::recommended_watcher(move |_res: Result<notify::Event, notify::Error>| {
+                notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
+                    if let Err(e) = res {
+                        warn!(error = %e, "file watcher error");
+                    }
                     let _ = sender.send(());
                 })
+                .map_err(|e| warn!(error = %e, "failed to create file watcher; polling only"))
                 .ok();
 
             if let Some(ref mut w) = watcher {
-                let _ = w.watch(log_path, RecursiveMode::NonRecursive);
+                if let Err(e) = w.watch(log_path, RecursiveMode::NonRecursive) {
+                    warn!(path = %log_path.display(), error = %e, "failed to watch game log");
+                }
             }
 
             self._watcher = watcher;
@@ -170,111 +152,83 @@ impl TrackerApp {
     }
 
     fn poll_log(&mut self) {
-        if self.log_path.is_none() {
-            // Try to detect again
-            self.log_path = storage::detect_game_log();
-            if let Some(ref p) = self.log_path {
-                self.log_status = format!("Log found: {}", p.display());
-                self.setup_watcher();
+        match self.tracker.poll() {
+            change if change.rearm() => self.setup_watcher(),
+            LogChange::Lost => {
+                self._watcher = None;
+                self.watch_rx = None;
             }
+            _ => {}
         }
-
-        if let Some(ref path) = self.log_path {
-            let path = path.clone();
-            // Parse loot
-            match log_parser::parse_loot_from_log(&path) {
-                Ok(summary) => {
-                    // Track deltas for session
-                    if let Some(ref mut session) = self.session {
-                        let new_loot: HashMap<String, i64> = summary
-                            .items
-                            .iter()
-                            .map(|i| (i.config_base_id.clone(), i.delta))
-                            .collect();
-
-                        // Compute session-relative deltas
-                        for (cid, &new_delta) in &new_loot {
-                            let prev = self.prev_loot.get(cid).copied().unwrap_or(0);
-                            let diff = new_delta - prev;
-                            if diff != 0 {
-                                *session.cumulative_loot.entry(cid.clone()).or_insert(0) += diff;
-                            }
-                        }
-                        self.prev_loot = new_loot;
-                    }
-
-                    self.loot = Some(summary);
-                }
-                Err(e) => {
-                    self.log_status = format!("Error parsing log: {}", e);
-                }
-            }
-
-            // Parse inventory
-            if let Ok(inv) = log_parser::parse_inventory_from_log(&path) {
-                self.inventory = inv;
-            }
-
-            // Detect current map from log
-            self.detect_map(&path);
-        }
-
         self.last_poll = Instant::now();
+        self.watch_debounce.refreshed(self.last_poll);
+        self.live_rate.record(
+            self.tracker.session.as_ref(),
+            &self.tracker.settings.primary_currency,
+            Utc::now(),
+        );
     }
 
-    fn detect_map(&mut self, path: &std::path::Path) {
-        if let Ok(contents) = std::fs::read_to_string(path) {
-            // Find last map event
-            for line in contents.lines().rev() {
-                if let Some(LogEvent::Map(m)) = log_parser::parse_line(line) {
-                    // Extract readable map name from path
-                    let name = m
-                        .zone_path
-                        .rsplit('/')
-                        .next()
-                        .unwrap_or(&m.zone_path)
-                        .to_string();
-                    self.current_map = Some(name);
-                    return;
-                }
-            }
-        }
+    /// Display values for the current frame.
+    fn view(&self) -> TrackerView {
+        TrackerView::from(
+            self.tracker.current_map.as_deref(),
+            self.tracker.session.as_ref(),
+            self.tracker.loot.as_ref(),
+            &self.tracker.settings,
+            Utc::now(),
+        )
     }
 
-    fn start_session(&mut self) {
-        let mut session = TrackerSession::new();
-
-        // Snapshot current loot state
-        if let Some(ref loot) = self.loot {
-            self.prev_loot = loot
-                .items
-                .iter()
-                .map(|i| (i.config_base_id.clone(), i.delta))
-                .collect();
+    /// Switch tabs, remembering the choice for the next launch.
+    fn select_tab(&mut self, tab: Tab) {
+        if tab != self.active_tab {
+            self.active_tab = tab;
+            self.tracker.settings.gui_tab = tab;
+            self.tracker.save_settings();
         }
-        session.cumulative_loot.clear();
-
-        self.session = Some(session);
     }
 
-    fn stop_session(&mut self) {
-        self.session = None;
-        self.prev_loot.clear();
+    /// Apply keyboard shortcuts shared with the TUI (ignored while typing).
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let keys = [
+            (egui::Key::S, 's'),
+            (egui::Key::Num1, '1'),
+            (egui::Key::Num2, '2'),
+            (egui::Key::Num3, '3'),
+            (egui::Key::Num4, '4'),
+            (egui::Key::Num5, '5'),
+            (egui::Key::Tab, '\t'),
+        ];
+        for (key, ch) in keys {
+            if !ctx.input(|i| i.key_pressed(key)) {
+                continue;
+            }
+            match viewmodel::action_for_key(ch) {
+                Some(Action::ToggleSession) => self.tracker.toggle_session(),
+                Some(Action::SelectTab(tab)) => self.select_tab(tab),
+                Some(Action::NextTab) => self.select_tab(self.active_tab.next()),
+                Some(Action::Quit) | None => {}
+            }
+        }
     }
 }
 
 impl eframe::App for TrackerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check for file watcher notifications
-        let mut should_poll = self.last_poll.elapsed() >= POLL_INTERVAL;
         if let Some(ref rx) = self.watch_rx {
-            if rx.try_recv().is_ok() {
-                should_poll = true;
-            }
+            self.watch_debounce.drain(rx);
         }
+        let should_poll = self.last_poll.elapsed() >= self.tracker.settings.poll_interval()
+            || self.watch_debounce.ready(Instant::now());
         if should_poll {
             self.poll_log();
         }
+        self.handle_shortcuts(ctx);
 
         // Request repaint periodically for live timer updates
         ctx.request_repaint_after(Duration::from_secs(1));
@@ -323,7 +277,7 @@ impl eframe::App for TrackerApp {
                         .color(egui::Color32::from_gray(120)),
                 );
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    let (icon, color) = if self.log_path.is_some() {
+                    let (icon, color) = if self.tracker.has_log() {
                         ("● LOG OK", egui::Color32::from_gray(200))
                     } else {
                         ("○ NO LOG", egui::Color32::from_gray(100))
@@ -334,12 +288,103 @@ impl eframe::App for TrackerApp {
             ui.add_space(4.0);
         });
 
+        if let Some(alert) = self.view().pace_alert {
+            egui::TopBottomPanel::top("pace_alert").show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("⚠ {}", alert))
+                        .size(12.0)
+                        .color(egui::Color32::from_rgb(230, 90, 70))
+                        .strong(),
+                );
+            });
+        }
+
+        if let Some(alert) = self.tracker.bag_alert() {
+            egui::TopBottomPanel::top("bag_alert").show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("⚠ {}", alert))
+                        .size(12.0)
+                        .color(egui::Color32::from_rgb(230, 160, 60))
+                        .strong(),
+                );
+            });
+        }
+
+        if let Some(loot) = self.tracker.loot.as_ref() {
+            if let Some(warning) = loot.anomalies.warning() {
+                egui::TopBottomPanel::top("parse_anomalies").show(ctx, |ui| {
+                    ui.label(
+                        egui::RichText::new(format!("⚠ {}", warning))
+                            .size(12.0)
+                            .color(egui::Color32::from_rgb(230, 90, 70))
+                            .strong(),
+                    );
+                    ui.collapsing("Sample lines", |ui| {
+                        for line in &loot.anomalies.samples {
+                            ui.add(
+                                egui::Label::new(egui::RichText::new(line).monospace().size(11.0))
+                                    .selectable(true),
+                            );
+                        }
+                    });
+                });
+            }
+            if let Some(warning) = loot.item_db_warning() {
+                egui::TopBottomPanel::top("item_db_warning").show(ctx, |ui| {
+                    ui.label(
+                        egui::RichText::new(format!("⚠ {}", warning))
+                            .size(12.0)
+                            .color(egui::Color32::from_rgb(230, 160, 60))
+                            .strong(),
+                    );
+                });
+            }
+        }
+
+        let last_sort = self.tracker.loot.as_ref().map(|l| l.last_sort_at);
+        let reminder = viewmodel::sort_reminder(
+            self.tracker.loot.as_ref(),
+            &self.tracker.settings,
+            Utc::now(),
+        )
+        .filter(|_| self.sort_reminder_dismissed != last_sort);
+        if let Some(reminder) = reminder {
+            egui::TopBottomPanel::top("sort_reminder").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("⟳ {}", reminder))
+                            .size(13.0)
+                            .color(egui::Color32::from_rgb(90, 170, 230))
+                            .strong(),
+                    );
+                    if ui.small_button("Dismiss").clicked() {
+                        self.sort_reminder_dismissed = last_sort;
+                    }
+                });
+            });
+        }
+
+        if let Some(warning) = self.data_warning.clone() {
+            egui::TopBottomPanel::top("data_warning").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("⚠ {}", warning))
+                            .size(12.0)
+                            .color(egui::Color32::from_rgb(230, 160, 60)),
+                    );
+                    if ui.small_button("Dismiss").clicked() {
+                        self.data_warning = None;
+                    }
+                });
+            });
+        }
+
         // ── Bottom panel: status bar ──────────────────────────────────
         egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
             ui.add_space(2.0);
             ui.horizontal(|ui| {
                 ui.label(
-                    egui::RichText::new(&self.log_status)
+                    egui::RichText::new(&self.tracker.log_status)
                         .size(11.0)
                         .color(egui::Color32::from_gray(100)),
                 );
@@ -358,13 +403,8 @@ impl eframe::App for TrackerApp {
 
             // Tabs
             ui.horizontal(|ui| {
-                let tabs = [
-                    (Tab::FlameElementium, "Flame Elementium"),
-                    (Tab::Items, "Items"),
-                    (Tab::Inventory, "Inventory"),
-                    (Tab::Runs, "Runs"),
-                ];
-                for (tab, label) in tabs {
+                for tab in Tab::ALL {
+                    let label = tab.label();
                     let selected = self.active_tab == tab;
                     let text = if selected {
                         egui::RichText::new(label)
@@ -377,7 +417,7 @@ impl eframe::App for TrackerApp {
                             .color(egui::Color32::from_gray(120))
                     };
                     if ui.selectable_label(selected, text).clicked() {
-                        self.active_tab = tab;
+                        self.select_tab(tab);
                     }
                     ui.add_space(4.0);
                 }
@@ -387,11 +427,22 @@ impl eframe::App for TrackerApp {
 
             match self.active_tab {
                 Tab::FlameElementium => self.draw_fe_tab(ui),
-                Tab::Items => self.draw_loot_tab(ui),
-                Tab::Inventory => self.draw_inventory_tab(ui),
+                Tab::Items => {
+                    if let Some(action) = self.draw_loot_tab(ui) {
+                        self.apply_row_action(ui.ctx(), action);
+                    }
+                }
+                Tab::Inventory => {
+                    if let Some(action) = self.draw_inventory_tab(ui) {
+                        self.apply_row_action(ui.ctx(), action);
+                    }
+                }
                 Tab::Runs => self.draw_runs_tab(ui),
+                Tab::Settings => self.draw_settings_tab(ui),
             }
         });
+
+        self.draw_price_editor(ctx);
     }
 }
 
@@ -399,7 +450,7 @@ impl TrackerApp {
     fn draw_session_bar(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             // Session control
-            if self.session.is_some() {
+            if self.tracker.session.is_some() {
                 if ui
                     .button(
                         egui::RichText::new("■ Stop Session")
@@ -408,7 +459,7 @@ impl TrackerApp {
                     )
                     .clicked()
                 {
-                    self.stop_session();
+                    self.tracker.stop_session();
                 }
             } else if ui
                 .button(
@@ -418,48 +469,27 @@ impl TrackerApp {
                 )
                 .clicked()
             {
-                self.start_session();
+                self.tracker.start_session();
             }
 
             ui.add_space(16.0);
 
             // Stats boxes
-            let map_display = self
-                .current_map
-                .as_deref()
-                .unwrap_or("-");
-
-            self.draw_stat(ui, "MAP", map_display);
-
-            if let Some(ref session) = self.session {
-                let elapsed = session.elapsed_secs();
-                let mins = (elapsed / 60.0).floor() as u64;
-                let secs = (elapsed % 60.0).floor() as u64;
-                let time_str = format!("{:02}:{:02}", mins, secs);
-                self.draw_stat(ui, "TIME", &time_str);
-
-                let fe = session.flame_elementium();
-                self.draw_stat(ui, "FE", &fe.to_string());
-
-                let fe_per_hour = session.flame_elementium_per_hour();
-                self.draw_stat(ui, "FE/HR", &format!("{:.0}", fe_per_hour));
-
-                let total = session.total_items();
-                self.draw_stat(ui, "ITEMS", &total.to_string());
-
-                let runs = session.runs.len();
-                self.draw_stat(ui, "RUNS", &runs.to_string());
-            } else {
-                self.draw_stat(ui, "TIME", "--:--");
-                self.draw_stat(ui, "FE", "-");
-                self.draw_stat(ui, "FE/HR", "-");
-                self.draw_stat(ui, "ITEMS", "-");
-                self.draw_stat(ui, "RUNS", "-");
+            let settings = &self.tracker.settings;
+            for stat in &self.view().stats(
+                &settings.stat_boxes,
+                &settings.number_locale(),
+                settings.abbreviate_numbers,
+            ) {
+                self.draw_stat(ui, stat);
             }
         });
     }
 
-    fn draw_stat(&self, ui: &mut egui::Ui, label: &str, value: &str) {
+    fn draw_stat(&self, ui: &mut egui::Ui, stat: &viewmodel::Stat) {
+        let color = stat
+            .target
+            .map_or(egui::Color32::WHITE, |t| target_color(t.status));
         egui::Frame::new()
             .fill(egui::Color32::from_gray(18))
             .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(40)))
@@ -468,59 +498,87 @@ impl TrackerApp {
             .show(ui, |ui| {
                 ui.vertical(|ui| {
                     ui.label(
-                        egui::RichText::new(label)
+                        egui::RichText::new(&stat.label)
                             .size(10.0)
                             .color(egui::Color32::from_gray(100)),
                     );
-                    ui.label(
-                        egui::RichText::new(value)
-                            .size(16.0)
-                            .color(egui::Color32::WHITE)
-                            .strong(),
-                    );
+                    ui.horizontal(|ui| {
+                        let value = ui.label(
+                            egui::RichText::new(&stat.value)
+                                .size(16.0)
+                                .color(color)
+                                .strong(),
+                        );
+                        if stat.value != stat.full {
+                            value.on_hover_text(&stat.full);
+                        }
+                        if let Some(target) = stat.target {
+                            ui.label(
+                                egui::RichText::new(target.delta_label())
+                                    .size(10.0)
+                                    .color(color),
+                            );
+                        }
+                    });
                 });
             });
         ui.add_space(4.0);
     }
 
     fn draw_fe_tab(&self, ui: &mut egui::Ui) {
-        if let Some(ref session) = self.session {
-            let fe = session.flame_elementium();
-            let fe_hr = session.flame_elementium_per_hour();
-            let elapsed = session.elapsed_secs();
-            let mins = (elapsed / 60.0).floor() as u64;
-            let secs = (elapsed % 60.0).floor() as u64;
-
+        let view = self.view();
+        let sep = &self.tracker.settings.number_locale();
+        if view.active {
             ui.add_space(8.0);
 
             // Large FE display
             ui.vertical_centered(|ui| {
                 ui.label(
-                    egui::RichText::new("🔥 FLAME ELEMENTIUM")
+                    egui::RichText::new(format!("🔥 {}", view.currency_name.to_uppercase()))
                         .size(18.0)
                         .color(egui::Color32::WHITE)
                         .strong(),
                 );
                 ui.add_space(8.0);
                 ui.label(
-                    egui::RichText::new(format!("{}", fe))
+                    egui::RichText::new(viewmodel::format_count(view.primary, sep))
                         .size(48.0)
                         .color(egui::Color32::WHITE)
                         .strong(),
                 );
                 ui.add_space(4.0);
                 ui.label(
-                    egui::RichText::new(format!("{:.0} FE / hour", fe_hr))
-                        .size(20.0)
-                        .color(egui::Color32::from_gray(180)),
+                    egui::RichText::new(format!(
+                        "{} {} / hour",
+                        viewmodel::format_count(view.primary_per_hour.round() as i64, sep),
+                        view.currency
+                    ))
+                    .size(20.0)
+                    .color(egui::Color32::from_gray(180)),
+                );
+                let live = self
+                    .tracker
+                    .session
+                    .as_ref()
+                    .and_then(|session| self.live_rate.per_hour(session, Utc::now()));
+                ui.label(
+                    egui::RichText::new(format!(
+                        "last 60s: {} {} / hour",
+                        live.map_or_else(
+                            || "-".to_string(),
+                            |rate| viewmodel::format_count(rate.round() as i64, sep)
+                        ),
+                        view.currency
+                    ))
+                    .size(13.0)
+                    .color(egui::Color32::from_gray(140)),
                 );
                 ui.add_space(12.0);
                 ui.label(
                     egui::RichText::new(format!(
-                        "Session time: {:02}:{:02}  •  Total items: {}",
-                        mins,
-                        secs,
-                        session.total_items()
+                        "Session time: {}  •  Total items: {}",
+                        view.time,
+                        viewmodel::format_count(view.items, sep)
                     ))
                     .size(13.0)
                     .color(egui::Color32::from_gray(120)),
@@ -528,135 +586,201 @@ impl TrackerApp {
             });
         } else {
             // No session – show FE from log if available
-            if let Some(ref loot) = self.loot {
-                let fe_delta = loot.flame_elementium_delta();
+            if self.tracker.loot.is_some() {
                 ui.add_space(8.0);
                 ui.vertical_centered(|ui| {
                     ui.label(
-                        egui::RichText::new("🔥 FLAME ELEMENTIUM")
+                        egui::RichText::new(format!("🔥 {}", view.currency_name.to_uppercase()))
                             .size(18.0)
                             .color(egui::Color32::WHITE)
                             .strong(),
                     );
                     ui.add_space(8.0);
                     ui.label(
-                        egui::RichText::new(format!("{}", fe_delta))
+                        egui::RichText::new(viewmodel::format_count(view.primary, sep))
                             .size(48.0)
                             .color(egui::Color32::WHITE)
                             .strong(),
                     );
                     ui.add_space(4.0);
                     ui.label(
-                        egui::RichText::new("from log (start a session to track FE/hour)")
-                            .size(13.0)
-                            .color(egui::Color32::from_gray(100)),
+                        egui::RichText::new(format!(
+                            "from log (start a session to track {}/hour)",
+                            view.currency
+                        ))
+                        .size(13.0)
+                        .color(egui::Color32::from_gray(100)),
                     );
                 });
             } else {
                 ui.label(
-                    egui::RichText::new(
-                        "Start a session to track Flame Elementium. Sort inventory in-game to sync baseline.",
-                    )
+                    egui::RichText::new(format!(
+                        "Start a session to track {}. Sort inventory in-game to sync baseline.",
+                        view.currency_name
+                    ))
                     .size(13.0)
                     .color(egui::Color32::from_gray(100)),
                 );
             }
+            if let Some(last) = &self.tracker.last_session {
+                ui.add_space(12.0);
+                ui.vertical_centered(|ui| {
+                    ui.label(
+                        egui::RichText::new(viewmodel::last_session_line(
+                            last,
+                            &view.currency,
+                            view.rate_precision,
+                            sep,
+                        ))
+                        .size(13.0)
+                        .color(egui::Color32::from_gray(140)),
+                    );
+                });
+            }
         }
     }
 
-    fn draw_loot_tab(&self, ui: &mut egui::Ui) {
+    /// Returns what the user asked to do with a loot row, if anything.
+    fn draw_loot_tab(&self, ui: &mut egui::Ui) -> Option<RowAction> {
+        let mut action = self.draw_bulk_bar(ui);
         // Show session loot if active, otherwise show log loot
-        if let Some(ref session) = self.session {
+        if let Some(ref session) = self.tracker.session {
             if session.cumulative_loot.is_empty() {
+                let hint = self.tracker.loot.as_ref().and_then(|l| l.hint()).unwrap_or(
+                    "Session active – pick up items in-game. Sort inventory to sync baseline.",
+                );
                 ui.label(
-                    egui::RichText::new(
-                        "Session active – pick up items in-game. Sort inventory to sync baseline.",
-                    )
-                    .size(13.0)
-                    .color(egui::Color32::from_gray(100)),
+                    egui::RichText::new(hint)
+                        .size(13.0)
+                        .color(egui::Color32::from_gray(100)),
                 );
-                return;
+                return None;
             }
 
-            ui.label(
-                egui::RichText::new("Session Loot")
-                    .size(14.0)
-                    .color(egui::Color32::from_gray(160))
-                    .strong(),
-            );
+            let rows = viewmodel::loot_rows(Some(session), None, &self.tracker.settings);
+            let display = self.tracker.settings.item_display;
+            let headers = display.cells("Item", "ID");
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new("Session Loot")
+                        .size(14.0)
+                        .color(egui::Color32::from_gray(160))
+                        .strong(),
+                );
+                let hidden = session
+                    .cumulative_loot
+                    .keys()
+                    .filter(|id| self.tracker.settings.is_ignored(id))
+                    .count();
+                self.draw_hidden_count(ui, hidden);
+                let score = scoring::score_deltas(
+                    session
+                        .cumulative_loot
+                        .iter()
+                        .map(|(id, &n)| (id.as_str(), n)),
+                    &self.tracker.settings.scoring(),
+                );
+                ui.add_space(8.0);
+                ui.label(
+                    egui::RichText::new(viewmodel::score_label(score, &self.tracker.settings))
+                        .size(12.0)
+                        .color(egui::Color32::from_gray(160)),
+                );
+            });
             ui.add_space(4.0);
 
-            let mut items: Vec<_> = session.cumulative_loot.iter().collect();
-            items.sort_by(|a, b| b.1.abs().cmp(&a.1.abs()));
-
             egui::ScrollArea::vertical()
                 .auto_shrink([false; 2])
                 .show(ui, |ui| {
                     egui::Grid::new("session_loot_grid")
-                        .num_columns(3)
+                        .num_columns(headers.len() + 3)
                         .spacing([12.0, 4.0])
                         .striped(true)
                         .show(ui, |ui| {
                             // Header
-                            ui.label(
-                                egui::RichText::new("Item")
-                                    .size(12.0)
-                                    .color(egui::Color32::from_gray(100))
-                                    .strong(),
-                            );
-                            ui.label(
-                                egui::RichText::new("ID")
-                                    .size(12.0)
-                                    .color(egui::Color32::from_gray(100))
-                                    .strong(),
-                            );
-                            ui.label(
-                                egui::RichText::new("Delta")
-                                    .size(12.0)
-                                    .color(egui::Color32::from_gray(100))
-                                    .strong(),
-                            );
-                            ui.end_row();
-
-                            for (cid, &delta) in &items {
-                                let name = log_parser::item_name(cid);
+                            for h in headers
+                                .iter()
+                                .copied()
+                                .chain(["This Run", "Session", "Top Map"])
+                            {
                                 ui.label(
-                                    egui::RichText::new(&name)
-                                        .size(13.0)
-                                        .color(egui::Color32::WHITE),
-                                );
-                                ui.label(
-                                    egui::RichText::new(*cid)
-                                        .size(11.0)
-                                        .color(egui::Color32::from_gray(80)),
+                                    egui::RichText::new(h)
+                                        .size(12.0)
+                                        .color(egui::Color32::from_gray(100))
+                                        .strong(),
                                 );
-                                let (sign, color) = if delta > 0 {
-                                    ("+", egui::Color32::from_gray(220))
-                                } else {
-                                    ("", egui::Color32::from_gray(120))
-                                };
-                                ui.label(
-                                    egui::RichText::new(format!("{}{}", sign, delta))
+                            }
+                            ui.end_row();
+
+                            for row in &rows {
+                                if let Some(a) = item_cells(
+                                    ui,
+                                    &self.tracker.settings,
+                                    &row.name,
+                                    &row.id,
+                                    self.selection.contains_key(&row.id),
+                                ) {
+                                    action = Some(a);
+                                }
+                                for delta in [row.run.unwrap_or(0), row.delta] {
+                                    let color = if delta > 0 {
+                                        egui::Color32::from_gray(220)
+                                    } else {
+                                        egui::Color32::from_gray(120)
+                                    };
+                                    ui.label(
+                                        egui::RichText::new(viewmodel::format_delta(
+                                            delta,
+                                            &self.tracker.settings.number_locale(),
+                                        ))
                                         .size(13.0)
                                         .color(color)
                                         .strong(),
-                                );
+                                    );
+                                }
+                                match &row.top_map {
+                                    Some((map, n)) => {
+                                        ui.label(
+                                            egui::RichText::new(map)
+                                                .size(12.0)
+                                                .color(egui::Color32::from_gray(160)),
+                                        )
+                                        .on_hover_text(
+                                            format!(
+                                                "{} of {} this session",
+                                                viewmodel::format_count(
+                                                    *n,
+                                                    &self.tracker.settings.number_locale()
+                                                ),
+                                                viewmodel::format_count(
+                                                    row.delta,
+                                                    &self.tracker.settings.number_locale()
+                                                )
+                                            ),
+                                        );
+                                    }
+                                    None => {
+                                        ui.label("");
+                                    }
+                                }
                                 ui.end_row();
                             }
                         });
                 });
-        } else if let Some(ref loot) = self.loot {
+        } else if let Some(ref loot) = self.tracker.loot {
             if loot.items.is_empty() {
+                let hint = loot.hint().unwrap_or(
+                    "No loot detected. Sort your inventory in-game to sync, then pick up items.",
+                );
                 ui.label(
-                    egui::RichText::new(
-                        "No loot detected. Sort your inventory in-game to sync, then pick up items.",
-                    )
-                    .size(13.0)
-                    .color(egui::Color32::from_gray(100)),
+                    egui::RichText::new(hint)
+                        .size(13.0)
+                        .color(egui::Color32::from_gray(100)),
                 );
-                return;
+                return None;
             }
 
+            let items = viewmodel::visible_items(loot, &self.tracker.settings);
             ui.horizontal(|ui| {
                 ui.label(
                     egui::RichText::new("Recent Loot from Log")
@@ -670,10 +794,18 @@ impl TrackerApp {
                         .size(12.0)
                         .color(egui::Color32::from_gray(80)),
                 );
+                self.draw_hidden_count(ui, loot.items.len() - items.len());
+                let score = scoring::score(loot, &self.tracker.settings.scoring());
+                ui.add_space(8.0);
+                ui.label(
+                    egui::RichText::new(viewmodel::score_label(score, &self.tracker.settings))
+                        .size(12.0)
+                        .color(egui::Color32::from_gray(160)),
+                );
             });
             ui.add_space(4.0);
 
-            self.draw_loot_table(ui, &loot.items);
+            action = self.draw_loot_table(ui, &items);
         } else {
             ui.label(
                 egui::RichText::new("Waiting for log data...")
@@ -681,19 +813,152 @@ impl TrackerApp {
                     .color(egui::Color32::from_gray(100)),
             );
         }
+        action
+    }
+
+    /// Ignore or (un)pin an item from a loot table, and save the change.
+    fn apply_row_action(&mut self, ctx: &egui::Context, action: RowAction) {
+        match action {
+            RowAction::Item { id, name, action } => {
+                match viewmodel::apply_item_action(&mut self.tracker.settings, &id, &name, action) {
+                    ActionEffect::Copy(text) => ctx.copy_text(text),
+                    ActionEffect::SettingsChanged => self.tracker.save_settings(),
+                    ActionEffect::None => {}
+                }
+            }
+            RowAction::EditPrice { id, name } => {
+                let price = self.tracker.settings.item_prices.get(&id).copied();
+                self.price_editor = Some(PriceEditor {
+                    id,
+                    name,
+                    price: price.unwrap_or(0.0),
+                });
+            }
+            RowAction::ToggleSelect { id, name } => {
+                if self.selection.remove(&id).is_none() {
+                    self.selection.insert(id, name);
+                }
+            }
+            RowAction::Bulk(bulk) => {
+                match viewmodel::apply_bulk_action(
+                    &mut self.tracker.settings,
+                    &self.selection,
+                    bulk,
+                ) {
+                    ActionEffect::Copy(text) => ctx.copy_text(text),
+                    ActionEffect::SettingsChanged => self.tracker.save_settings(),
+                    ActionEffect::None => {}
+                }
+                if bulk != BulkAction::Export {
+                    self.selection.clear();
+                }
+            }
+            RowAction::ClearSelection => self.selection.clear(),
+        }
+    }
+
+    /// The "Set price" window, while an item's price is being edited.
+    fn draw_price_editor(&mut self, ctx: &egui::Context) {
+        let Some(editor) = self.price_editor.as_mut() else {
+            return;
+        };
+        let mut done = None;
+        egui::Window::new(format!("Price of {}", editor.name))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut editor.price)
+                        .range(0.0..=f64::MAX)
+                        .speed(0.1),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        done = Some(Some(ItemAction::SetPrice(Some(editor.price))));
+                    }
+                    if ui.button("Clear").clicked() {
+                        done = Some(Some(ItemAction::SetPrice(None)));
+                    }
+                    if ui.button("Cancel").clicked() {
+                        done = Some(None);
+                    }
+                });
+            });
+        if let Some(action) = done {
+            let editor = self.price_editor.take().expect("editor is open");
+            if let Some(action) = action {
+                self.apply_row_action(
+                    ctx,
+                    RowAction::Item {
+                        id: editor.id,
+                        name: editor.name,
+                        action,
+                    },
+                );
+            }
+        }
+    }
+
+    /// "N selected" and the bulk actions, while any loot or inventory rows
+    /// are ticked.
+    fn draw_bulk_bar(&self, ui: &mut egui::Ui) -> Option<RowAction> {
+        if self.selection.is_empty() {
+            return None;
+        }
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!("{} selected", self.selection.len()))
+                    .size(12.0)
+                    .color(egui::Color32::from_gray(160)),
+            );
+            ui.add_space(8.0);
+            let buttons = [
+                ("Ignore", RowAction::Bulk(BulkAction::Ignore)),
+                ("Pin", RowAction::Bulk(BulkAction::Pin)),
+                ("Copy as CSV", RowAction::Bulk(BulkAction::Export)),
+                ("Clear", RowAction::ClearSelection),
+            ];
+            for (label, row_action) in buttons {
+                if ui.small_button(label).clicked() {
+                    action = Some(row_action);
+                }
+            }
+        });
+        ui.add_space(4.0);
+        action
+    }
+
+    /// Note how many ignored items a loot table leaves out.
+    fn draw_hidden_count(&self, ui: &mut egui::Ui, hidden: usize) {
+        if hidden == 0 {
+            return;
+        }
+        ui.add_space(8.0);
+        ui.label(
+            egui::RichText::new(format!("{} ignored", hidden))
+                .size(12.0)
+                .color(egui::Color32::from_gray(80)),
+        )
+        .on_hover_text("Manage ignored items on the Settings tab");
     }
 
-    fn draw_loot_table(&self, ui: &mut egui::Ui, items: &[ItemDelta]) {
+    /// Returns what the user asked to do with a loot row, if anything.
+    fn draw_loot_table(&self, ui: &mut egui::Ui, items: &[&ItemDelta]) -> Option<RowAction> {
+        let sep = &self.tracker.settings.number_locale();
+        let display = self.tracker.settings.item_display;
+        let headers = display.cells("Item", "ID");
+        let mut action = None;
         egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
             .show(ui, |ui| {
                 egui::Grid::new("loot_grid")
-                    .num_columns(4)
+                    .num_columns(headers.len() + 2)
                     .spacing([12.0, 4.0])
                     .striped(true)
                     .show(ui, |ui| {
                         // Header
-                        for h in ["Item", "ID", "Delta", "Current"] {
+                        for h in headers.iter().copied().chain(["Delta", "Current"]) {
                             ui.label(
                                 egui::RichText::new(h)
                                     .size(12.0)
@@ -704,40 +969,46 @@ impl TrackerApp {
                         ui.end_row();
 
                         for item in items {
-                            ui.label(
-                                egui::RichText::new(&item.item_name)
-                                    .size(13.0)
-                                    .color(egui::Color32::WHITE),
-                            );
-                            ui.label(
-                                egui::RichText::new(&item.config_base_id)
-                                    .size(11.0)
-                                    .color(egui::Color32::from_gray(80)),
-                            );
-                            let (sign, color) = if item.delta > 0 {
-                                ("+", egui::Color32::from_gray(220))
+                            let id = &item.config_base_id;
+                            if let Some(a) = item_cells(
+                                ui,
+                                &self.tracker.settings,
+                                &item.item_name,
+                                id,
+                                self.selection.contains_key(id),
+                            ) {
+                                action = Some(a);
+                            }
+                            let color = if item.delta > 0 {
+                                egui::Color32::from_gray(220)
                             } else {
-                                ("", egui::Color32::from_gray(120))
+                                egui::Color32::from_gray(120)
                             };
                             ui.label(
-                                egui::RichText::new(format!("{}{}", sign, item.delta))
+                                egui::RichText::new(viewmodel::format_delta(item.delta, sep))
                                     .size(13.0)
                                     .color(color)
                                     .strong(),
                             );
                             ui.label(
-                                egui::RichText::new(item.current.to_string())
-                                    .size(12.0)
-                                    .color(egui::Color32::from_gray(140)),
+                                egui::RichText::new(viewmodel::format_count(
+                                    item.current.into(),
+                                    sep,
+                                ))
+                                .size(12.0)
+                                .color(egui::Color32::from_gray(140)),
                             );
                             ui.end_row();
                         }
                     });
             });
+        action
     }
 
-    fn draw_inventory_tab(&self, ui: &mut egui::Ui) {
-        if self.inventory.is_empty() {
+    /// Returns what the user asked to do with an inventory row, if anything.
+    fn draw_inventory_tab(&self, ui: &mut egui::Ui) -> Option<RowAction> {
+        let mut action = self.draw_bulk_bar(ui);
+        if self.tracker.inventory.is_empty() {
             ui.label(
                 egui::RichText::new(
                     "No inventory data. Sort your inventory in-game to populate.",
@@ -745,11 +1016,11 @@ impl TrackerApp {
                 .size(13.0)
                 .color(egui::Color32::from_gray(100)),
             );
-            return;
+            return None;
         }
 
         ui.label(
-            egui::RichText::new(format!("Inventory ({} slots)", self.inventory.len()))
+            egui::RichText::new(format!("Inventory ({} slots)", self.tracker.inventory.len()))
                 .size(14.0)
                 .color(egui::Color32::from_gray(160))
                 .strong(),
@@ -774,12 +1045,21 @@ impl TrackerApp {
                         }
                         ui.end_row();
 
-                        for item in &self.inventory {
-                            ui.label(
-                                egui::RichText::new(&item.item_name)
-                                    .size(13.0)
-                                    .color(egui::Color32::WHITE),
-                            );
+                        for item in &self.tracker.inventory {
+                            let id = &item.config_base_id;
+                            let pinned = self.tracker.settings.pinned_items.contains(id);
+                            let shown =
+                                log_parser::display_name(id, &self.tracker.settings.item_aliases);
+                            ui.horizontal(|ui| {
+                                let selected = self.selection.contains_key(id);
+                                if let Some(a) = select_box(ui, selected, &item.item_name, id) {
+                                    action = Some(a);
+                                }
+                                if let Some(a) = item_label(ui, &shown, &item.item_name, id, pinned)
+                                {
+                                    action = Some(a);
+                                }
+                            });
                             ui.label(
                                 egui::RichText::new(item.page_id.to_string())
                                     .size(12.0)
@@ -791,19 +1071,23 @@ impl TrackerApp {
                                     .color(egui::Color32::from_gray(140)),
                             );
                             ui.label(
-                                egui::RichText::new(item.num.to_string())
-                                    .size(13.0)
-                                    .color(egui::Color32::WHITE)
-                                    .strong(),
+                                egui::RichText::new(viewmodel::format_count(
+                                    item.num.into(),
+                                    &self.tracker.settings.number_locale(),
+                                ))
+                                .size(13.0)
+                                .color(egui::Color32::WHITE)
+                                .strong(),
                             );
                             ui.end_row();
                         }
                     });
             });
+        action
     }
 
-    fn draw_runs_tab(&self, ui: &mut egui::Ui) {
-        if let Some(ref session) = self.session {
+    fn draw_runs_tab(&mut self, ui: &mut egui::Ui) {
+        if let Some(ref session) = self.tracker.session {
             if session.runs.is_empty() {
                 ui.label(
                     egui::RichText::new(
@@ -820,16 +1104,38 @@ impl TrackerApp {
                         .strong(),
                 );
                 ui.add_space(4.0);
+                draw_map_chart(
+                    ui,
+                    &viewmodel::map_rates(session, &self.tracker.settings.primary_currency),
+                    &format!("{} / hour", self.view().currency),
+                );
+                let per_run: Vec<i64> = session
+                    .runs
+                    .iter()
+                    .filter(|r| r.end.is_some())
+                    .map(|r| r.currency(&self.tracker.settings.primary_currency))
+                    .collect();
+                if !per_run.is_empty() {
+                    ui.add_space(8.0);
+                    let size =
+                        i64::try_from(self.tracker.settings.histogram_bucket).unwrap_or(i64::MAX);
+                    draw_histogram(
+                        ui,
+                        &stats::histogram(&per_run, size),
+                        &format!("{} per completed run", self.view().currency),
+                    );
+                }
+                ui.add_space(8.0);
 
                 egui::ScrollArea::vertical()
                     .auto_shrink([false; 2])
                     .show(ui, |ui| {
                         egui::Grid::new("runs_grid")
-                            .num_columns(3)
+                            .num_columns(4)
                             .spacing([12.0, 4.0])
                             .striped(true)
                             .show(ui, |ui| {
-                                for h in ["Map", "Duration", "Items"] {
+                                for h in ["Started", "Map", "Duration", "Items"] {
                                     ui.label(
                                         egui::RichText::new(h)
                                             .size(12.0)
@@ -839,25 +1145,30 @@ impl TrackerApp {
                                 }
                                 ui.end_row();
 
-                                for run in session.runs.iter().rev() {
+                                for run in viewmodel::run_rows(session, &self.tracker.settings) {
+                                    ui.label(
+                                        egui::RichText::new(&run.started)
+                                            .size(12.0)
+                                            .color(egui::Color32::from_gray(160)),
+                                    );
                                     ui.label(
-                                        egui::RichText::new(&run.map_name)
+                                        egui::RichText::new(&run.map)
                                             .size(13.0)
                                             .color(egui::Color32::WHITE),
                                     );
-                                    let secs = run.duration_secs();
-                                    let mins = (secs / 60.0).floor() as u64;
-                                    let s = (secs % 60.0).floor() as u64;
                                     ui.label(
-                                        egui::RichText::new(format!("{}:{:02}", mins, s))
+                                        egui::RichText::new(&run.duration)
                                             .size(12.0)
                                             .color(egui::Color32::from_gray(160)),
                                     );
                                     ui.label(
-                                        egui::RichText::new(run.total_items().to_string())
-                                            .size(13.0)
-                                            .color(egui::Color32::WHITE)
-                                            .strong(),
+                                        egui::RichText::new(viewmodel::format_count(
+                                            run.items,
+                                            &self.tracker.settings.number_locale(),
+                                        ))
+                                        .size(13.0)
+                                        .color(egui::Color32::WHITE)
+                                        .strong(),
                                     );
                                     ui.end_row();
                                 }
@@ -878,18 +1189,20 @@ impl TrackerApp {
             );
             ui.add_space(4.0);
 
-            let elapsed = session.elapsed_secs();
-            let mins = (elapsed / 60.0).floor() as u64;
-            let secs = (elapsed % 60.0).floor() as u64;
+            let view = self.view();
             ui.label(
                 egui::RichText::new(format!(
-                    "Started: {}  |  Duration: {}:{:02}  |  FE: {}  |  FE/hr: {:.0}  |  Total items: {}",
+                    "Started: {}  |  Duration: {}  |  {cur}: {}  |  {cur}/hr: {}  |  Total items: {}",
                     session.start_wall.with_timezone(&Local).format("%H:%M:%S"),
-                    mins,
-                    secs,
-                    session.flame_elementium(),
-                    session.flame_elementium_per_hour(),
-                    session.total_items()
+                    view.time,
+                    view.primary,
+                    viewmodel::format_number(
+                        view.primary_per_hour,
+                        view.rate_precision,
+                        &self.tracker.settings.number_locale()
+                    ),
+                    view.items,
+                    cur = view.currency
                 ))
                 .size(12.0)
                 .color(egui::Color32::from_gray(140)),
@@ -901,9 +1214,784 @@ impl TrackerApp {
                     .color(egui::Color32::from_gray(100)),
             );
         }
+
+        if !self.tracker.records.is_empty() {
+            ui.add_space(12.0);
+            ui.separator();
+            ui.add_space(8.0);
+            self.draw_records(ui);
+        }
+
+        ui.add_space(12.0);
+        ui.separator();
+        ui.add_space(8.0);
+        if self.draw_saved_sessions(ui) {
+            self.tracker.reload_saved_sessions();
+        }
+    }
+
+    /// The newest sessions in the shared sessions file, with a copyable id
+    /// for the CLI and their drop lists. Returns true if Reload was clicked.
+    fn draw_saved_sessions(&self, ui: &mut egui::Ui) -> bool {
+        let mut reload = false;
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new("Saved Sessions")
+                    .size(14.0)
+                    .color(egui::Color32::from_gray(160))
+                    .strong(),
+            );
+            reload = ui
+                .small_button("↻ Reload")
+                .on_hover_text("Re-read the sessions file")
+                .clicked();
+        });
+        ui.add_space(4.0);
+        let sessions = &self.tracker.saved_sessions;
+        if sessions.is_empty() {
+            ui.label(
+                egui::RichText::new("No saved sessions yet.")
+                    .size(12.0)
+                    .color(egui::Color32::from_gray(100)),
+            );
+            return reload;
+        }
+        let numbers = models::map_sequence_numbers(sessions);
+        for session in sessions.iter().rev().take(SAVED_SESSIONS_SHOWN) {
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new(viewmodel::session_id_label(&session.id, false))
+                        .size(12.0)
+                        .monospace()
+                        .color(egui::Color32::from_gray(160)),
+                )
+                .on_hover_text(&session.id);
+                if ui.small_button("📋").on_hover_text("Copy id").clicked() {
+                    ui.ctx().copy_text(session.id.clone());
+                }
+                ui.label(
+                    egui::RichText::new(format!(
+                        "{}  {}{}",
+                        session.display_name(numbers[session.id.as_str()]),
+                        session.start_time.with_timezone(&Local).format("%Y-%m-%d"),
+                        if session.is_active() {
+                            "  (active)"
+                        } else {
+                            ""
+                        }
+                    ))
+                    .size(12.0)
+                    .color(egui::Color32::from_gray(140)),
+                );
+            });
+            if !session.drops.is_empty() {
+                egui::CollapsingHeader::new(format!("Drops ({})", session.drops.len()))
+                    .id_salt(&session.id)
+                    .show(ui, |ui| {
+                        for drop in &session.drops {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{} ×{}  {}",
+                                    drop.name,
+                                    drop.quantity,
+                                    viewmodel::format_number(
+                                        drop.value,
+                                        self.tracker.settings.value_precision,
+                                        &self.tracker.settings.number_locale()
+                                    )
+                                ))
+                                .size(12.0)
+                                .color(egui::Color32::from_gray(140)),
+                            );
+                        }
+                    });
+            }
+        }
+        reload
+    }
+
+    /// Lifetime bests; updated whenever a session stops.
+    fn draw_records(&self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Records")
+                .size(14.0)
+                .color(egui::Color32::from_gray(160))
+                .strong(),
+        );
+        ui.add_space(4.0);
+        egui::Grid::new("records_grid")
+            .num_columns(3)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for kind in RecordKind::ALL {
+                    let Some(record) = self.tracker.records.get(kind) else {
+                        continue;
+                    };
+                    ui.label(
+                        egui::RichText::new(kind.label())
+                            .size(12.0)
+                            .color(egui::Color32::from_gray(140)),
+                    );
+                    ui.label(
+                        egui::RichText::new(
+                            kind.format_value(record.value, &self.tracker.settings.number_locale()),
+                        )
+                        .size(13.0)
+                        .color(egui::Color32::from_rgb(255, 200, 80))
+                        .strong(),
+                    );
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{}  {}",
+                            record.map,
+                            record.at.with_timezone(&Local).format("%Y-%m-%d")
+                        ))
+                        .size(12.0)
+                        .color(egui::Color32::from_gray(120)),
+                    );
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+impl TrackerApp {
+    /// Edit settings in place; every accepted change is saved immediately.
+    fn draw_settings_tab(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        egui::Grid::new("settings_grid")
+            .num_columns(2)
+            .spacing([16.0, 8.0])
+            .show(ui, |ui| {
+                setting_label(ui, "Primary currency");
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.settings_draft.primary_currency)
+                            .desired_width(80.0),
+                    );
+                    if response.lost_focus() {
+                        let id = self.settings_draft.primary_currency.trim().to_string();
+                        if log_parser::item_name(&id).starts_with("Unknown ") {
+                            self.settings_error = Some(format!("Unknown item id {}", id));
+                        } else if id != self.tracker.settings.primary_currency {
+                            self.tracker.settings.primary_currency = id;
+                            changed = true;
+                        }
+                    }
+                    ui.label(
+                        egui::RichText::new(log_parser::item_name(
+                            &self.tracker.settings.primary_currency,
+                        ))
+                        .size(12.0)
+                        .color(egui::Color32::from_gray(120)),
+                    );
+                });
+                ui.end_row();
+
+                setting_label(ui, "Abbreviate stat boxes");
+                changed |= ui
+                    .checkbox(&mut self.tracker.settings.abbreviate_numbers, "")
+                    .on_hover_text("Show large numbers as 123.5k / 1.5m")
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Group digits");
+                changed |= ui
+                    .checkbox(&mut self.tracker.settings.group_digits, "")
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Number style");
+                ui.horizontal(|ui| {
+                    let current = self.tracker.settings.number_locale();
+                    for (name, locale) in NumberLocale::PRESETS {
+                        let sample = viewmodel::format_number(1234.5, 1, &locale);
+                        if ui
+                            .selectable_label(current == locale, name)
+                            .on_hover_text(sample)
+                            .clicked()
+                        {
+                            self.tracker.settings.set_number_locale(locale);
+                            self.settings_draft = SettingsDraft::from(&self.tracker.settings);
+                            changed |= current != locale;
+                        }
+                    }
+                });
+                ui.end_row();
+
+                setting_label(ui, "Thousands separator");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.settings_draft.thousands_separator)
+                        .char_limit(1)
+                        .desired_width(24.0),
+                );
+                if response.lost_focus() {
+                    match self.settings_draft.thousands_separator.chars().next() {
+                        Some(sep)
+                            if settings::is_valid_separator(sep)
+                                && Some(sep) != self.tracker.settings.decimal_separator =>
+                        {
+                            changed |= sep != self.tracker.settings.thousands_separator;
+                            self.tracker.settings.thousands_separator = sep;
+                        }
+                        _ => {
+                            self.settings_error = Some(format!(
+                                "Invalid thousands separator {:?}",
+                                self.settings_draft.thousands_separator
+                            ))
+                        }
+                    }
+                }
+                ui.end_row();
+
+                setting_label(ui, "Decimal separator");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.settings_draft.decimal_separator)
+                        .char_limit(1)
+                        .desired_width(24.0),
+                );
+                if response.lost_focus() {
+                    match self.settings_draft.decimal_separator.chars().next() {
+                        Some(sep)
+                            if settings::is_valid_separator(sep)
+                                && sep != self.tracker.settings.thousands_separator =>
+                        {
+                            changed |= sep != self.tracker.settings.number_locale().decimal;
+                            self.tracker.settings.decimal_separator = Some(sep);
+                        }
+                        _ => {
+                            self.settings_error = Some(format!(
+                                "Invalid decimal separator {:?}",
+                                self.settings_draft.decimal_separator
+                            ))
+                        }
+                    }
+                }
+                ui.end_row();
+
+                setting_label(ui, "Rate decimals");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.rate_precision)
+                            .range(0..=6),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Value decimals");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.value_precision)
+                            .range(0..=6),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Identify items by");
+                ui.horizontal(|ui| {
+                    for mode in ItemDisplay::ALL {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.tracker.settings.item_display,
+                                mode,
+                                mode.label(),
+                            )
+                            .changed();
+                    }
+                });
+                ui.end_row();
+
+                setting_label(ui, "Loot total");
+                ui.horizontal(|ui| {
+                    for mode in ScoreMode::ALL {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.tracker.settings.score_mode,
+                                mode,
+                                mode.key(),
+                            )
+                            .changed();
+                    }
+                });
+                ui.end_row();
+
+                setting_label(ui, "Run histogram bucket");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.histogram_bucket)
+                            .range(1..=u64::MAX)
+                            .speed(10.0),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Recent runs (pace)");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.tracker.settings.recent_runs).range(1..=50))
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Pace alert floor (/hr, 0 = off)");
+                let mut floor = self.tracker.settings.pace_floor.unwrap_or(0.0);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut floor)
+                            .range(0.0..=f64::MAX)
+                            .speed(10.0),
+                    )
+                    .changed()
+                {
+                    self.tracker.settings.pace_floor = (floor > 0.0).then_some(floor);
+                    changed = true;
+                }
+                ui.end_row();
+
+                setting_label(ui, "Target per hour (0 = off)");
+                let mut target = self.tracker.settings.target_per_hour.unwrap_or(0.0);
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut target)
+                            .range(0.0..=f64::MAX)
+                            .speed(10.0),
+                    )
+                    .changed()
+                {
+                    self.tracker.settings.target_per_hour = (target > 0.0).then_some(target);
+                    changed = true;
+                }
+                ui.end_row();
+
+                setting_label(ui, "Pace alert after runs");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.pace_alert_runs)
+                            .range(1..=50),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Warn when a bag page is");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.bag_warn_percent)
+                            .range(1..=100)
+                            .suffix("% full"),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Remind to sort after");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.sort_reminder_mins)
+                            .suffix(" min"),
+                    )
+                    .on_hover_text("0 turns the reminder off")
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Loot contexts");
+                let response = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.settings_draft.loot_contexts)
+                            .hint_text("PickItems")
+                            .desired_width(320.0),
+                    )
+                    .on_hover_text(
+                        "Log ProtoNames whose item changes count as loot, comma-separated",
+                    );
+                if response.lost_focus() {
+                    let contexts: Vec<String> = self
+                        .settings_draft
+                        .loot_contexts
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                    if contexts != self.tracker.settings.loot_contexts {
+                        self.tracker.settings.loot_contexts = contexts;
+                        changed = true;
+                    }
+                }
+                ui.end_row();
+
+                setting_label(ui, "Min session length for stats");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.min_session_secs)
+                            .suffix(" s"),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Poll interval");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.tracker.settings.poll_interval_secs)
+                            .range(1..=300)
+                            .suffix(" s"),
+                    )
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Remote log URL");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.settings_draft.log_url)
+                        .hint_text("local file")
+                        .desired_width(320.0),
+                );
+                if response.lost_focus() {
+                    match settings::parse_log_url(&self.settings_draft.log_url) {
+                        Ok(url) if url != self.tracker.settings.log_url => {
+                            self.tracker.set_log_url(url);
+                            changed = true;
+                        }
+                        Ok(_) => {}
+                        Err(e) => self.settings_error = Some(e),
+                    }
+                }
+                ui.end_row();
+
+                setting_label(ui, "Ignored items");
+                ui.vertical(|ui| {
+                    if self.tracker.settings.ignored_items.is_empty() {
+                        setting_label(ui, "none – right-click an item on the Items tab");
+                    }
+                    let mut unignore = None;
+                    for id in &self.tracker.settings.ignored_items {
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new(log_parser::item_name(id))
+                                    .size(13.0)
+                                    .color(egui::Color32::WHITE),
+                            );
+                            if ui.small_button("Unignore").clicked() {
+                                unignore = Some(id.clone());
+                            }
+                        });
+                    }
+                    if let Some(id) = unignore {
+                        self.tracker.settings.ignored_items.remove(&id);
+                        changed = true;
+                    }
+                });
+                ui.end_row();
+
+                setting_label(ui, "Count ignored items in totals");
+                changed |= ui
+                    .checkbox(&mut self.tracker.settings.count_ignored, "")
+                    .changed();
+                ui.end_row();
+
+                setting_label(ui, "Session bar stats");
+                changed |= draw_stat_boxes(ui, &mut self.tracker.settings.stat_boxes);
+                ui.end_row();
+            });
+
+        if changed {
+            self.settings_error = None;
+            self.tracker.save_settings();
+        }
+        if let Some(ref error) = self.settings_error {
+            ui.add_space(6.0);
+            ui.label(
+                egui::RichText::new(format!("⚠ {}", error))
+                    .size(12.0)
+                    .color(egui::Color32::from_rgb(230, 90, 70)),
+            );
+        }
+    }
+}
+
+/// Green, amber or red for a rate against the FE/hour target.
+fn target_color(status: TargetStatus) -> egui::Color32 {
+    match status {
+        TargetStatus::OnTarget => egui::Color32::from_rgb(90, 200, 110),
+        TargetStatus::Close => egui::Color32::from_rgb(230, 160, 60),
+        TargetStatus::Behind => egui::Color32::from_rgb(230, 90, 70),
     }
 }
 
+/// Checklist of session bar stats: shown ones first, in order, with buttons
+/// to move them. Returns whether the list changed.
+fn draw_stat_boxes(ui: &mut egui::Ui, boxes: &mut Vec<StatKind>) -> bool {
+    let mut remove = None;
+    let mut swap = None;
+    let mut add = None;
+    ui.vertical(|ui| {
+        for (i, kind) in boxes.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut true, kind.title()).changed() {
+                    remove = Some(i);
+                }
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("↑").small())
+                    .clicked()
+                {
+                    swap = Some((i - 1, i));
+                }
+                if ui
+                    .add_enabled(i + 1 < boxes.len(), egui::Button::new("↓").small())
+                    .clicked()
+                {
+                    swap = Some((i, i + 1));
+                }
+            });
+        }
+        for kind in StatKind::ALL.into_iter().filter(|k| !boxes.contains(k)) {
+            if ui.checkbox(&mut false, kind.title()).changed() {
+                add = Some(kind);
+            }
+        }
+    });
+    if let Some(i) = remove {
+        boxes.remove(i);
+    }
+    if let Some((a, b)) = swap {
+        boxes.swap(a, b);
+    }
+    if let Some(kind) = add {
+        boxes.push(kind);
+    }
+    remove.is_some() || swap.is_some() || add.is_some()
+}
+
+/// What the user picked on a loot or inventory row.
+enum RowAction {
+    Item {
+        id: String,
+        name: String,
+        action: ItemAction,
+    },
+    /// Open the "Set price" window for the item.
+    EditPrice {
+        id: String,
+        name: String,
+    },
+    /// Tick or untick the row for the bulk-action bar.
+    ToggleSelect {
+        id: String,
+        name: String,
+    },
+    Bulk(BulkAction),
+    ClearSelection,
+}
+
+/// The checkbox that adds a row to the bulk-action selection.
+fn select_box(ui: &mut egui::Ui, selected: bool, name: &str, id: &str) -> Option<RowAction> {
+    let mut ticked = selected;
+    ui.checkbox(&mut ticked, "")
+        .on_hover_text("Select for bulk actions")
+        .changed()
+        .then(|| RowAction::ToggleSelect {
+            id: id.to_string(),
+            name: name.to_string(),
+        })
+}
+
+/// An item name in a loot table, with a box to select it and a star to pin
+/// it to the top.
+fn item_name_cell(
+    ui: &mut egui::Ui,
+    text: &str,
+    name: &str,
+    id: &str,
+    pinned: bool,
+    selected: bool,
+) -> Option<RowAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        action = select_box(ui, selected, name, id);
+        let (star, color, tip) = if pinned {
+            ("★", egui::Color32::from_rgb(255, 200, 80), "Unpin")
+        } else {
+            ("☆", egui::Color32::from_gray(80), "Pin to the top")
+        };
+        let star = ui
+            .add(
+                egui::Label::new(egui::RichText::new(star).size(13.0).color(color))
+                    .sense(egui::Sense::click()),
+            )
+            .on_hover_text(tip);
+        if star.clicked() {
+            action = Some(RowAction::Item {
+                id: id.to_string(),
+                name: name.to_string(),
+                action: ItemAction::TogglePin,
+            });
+        }
+        if let Some(a) = item_label(ui, text, name, id, pinned) {
+            action = Some(a);
+        }
+    });
+    action
+}
+
+/// The item columns of a loot row under the item display setting: the pin
+/// star and label (the user's alias, if any, else the name or id), then the
+/// id in its own column when names and ids are both shown.
+fn item_cells(
+    ui: &mut egui::Ui,
+    settings: &settings::Settings,
+    name: &str,
+    id: &str,
+    selected: bool,
+) -> Option<RowAction> {
+    let pinned = settings.pinned_items.contains(id);
+    let shown = settings.item_aliases.get(id).map_or(name, String::as_str);
+    let cells = settings.item_display.cells(shown, id);
+    let action = item_name_cell(ui, cells[0], name, id, pinned, selected);
+    for extra in &cells[1..] {
+        ui.label(
+            egui::RichText::new(*extra)
+                .size(11.0)
+                .color(egui::Color32::from_gray(80)),
+        );
+    }
+    action
+}
+
+/// An item's `text` (its name or id) with the right-click menu shared by
+/// loot and inventory rows.
+fn item_label(
+    ui: &mut egui::Ui,
+    text: &str,
+    name: &str,
+    id: &str,
+    pinned: bool,
+) -> Option<RowAction> {
+    let mut action = None;
+    let label = ui.add(
+        egui::Label::new(
+            egui::RichText::new(text)
+                .size(13.0)
+                .color(egui::Color32::WHITE),
+        )
+        .sense(egui::Sense::click()),
+    );
+    // An alias hides the official name; keep it one hover away.
+    let label = if text != name && text != id {
+        label.on_hover_text(name)
+    } else {
+        label
+    };
+    label.context_menu(|ui| {
+        let item = |action| RowAction::Item {
+            id: id.to_string(),
+            name: name.to_string(),
+            action,
+        };
+        let pin = if pinned { "Unpin" } else { "Pin to the top" };
+        let entries = [
+            ("Copy ID", Some(ItemAction::CopyId)),
+            ("Copy name", Some(ItemAction::CopyName)),
+            (pin, Some(ItemAction::TogglePin)),
+            ("Ignore this item", Some(ItemAction::Ignore)),
+            ("Set price…", None),
+        ];
+        for (label, item_action) in entries {
+            if ui.button(label).clicked() {
+                action = Some(match item_action {
+                    Some(a) => item(a),
+                    None => RowAction::EditPrice {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                    },
+                });
+                ui.close_menu();
+            }
+        }
+    });
+    action
+}
+
+fn setting_label(ui: &mut egui::Ui, text: &str) {
+    ui.label(
+        egui::RichText::new(text)
+            .size(12.0)
+            .color(egui::Color32::from_gray(140)),
+    );
+}
+
+/// Bar chart of the average primary-currency rate per map, labelled with each map's run count.
+fn draw_map_chart(ui: &mut egui::Ui, rates: &[viewmodel::MapRate], y_label: &str) {
+    let bars: Vec<Bar> = rates
+        .iter()
+        .enumerate()
+        .map(|(i, rate)| {
+            Bar::new(i as f64, rate.per_hour)
+                .name(&rate.map)
+                .width(0.6)
+                .fill(egui::Color32::from_rgb(230, 120, 40))
+        })
+        .collect();
+    let names: Vec<String> = rates.iter().map(|r| r.map.clone()).collect();
+
+    Plot::new("map_fe_chart")
+        .height(160.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show_grid([false, true])
+        .include_y(0.0)
+        .y_axis_label(y_label)
+        .x_axis_formatter(move |mark, _range| {
+            let i = mark.value.round();
+            if (mark.value - i).abs() > f64::EPSILON || i < 0.0 {
+                return String::new();
+            }
+            names.get(i as usize).cloned().unwrap_or_default()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars));
+            for (i, rate) in rates.iter().enumerate() {
+                let label = if rate.runs == 1 {
+                    "1 run".to_string()
+                } else {
+                    format!("{} runs", rate.runs)
+                };
+                plot_ui.text(
+                    Text::new(
+                        PlotPoint::new(i as f64, rate.per_hour.max(0.0)),
+                        egui::RichText::new(label)
+                            .size(11.0)
+                            .color(egui::Color32::from_gray(200)),
+                    )
+                    .anchor(egui::Align2::CENTER_BOTTOM),
+                );
+            }
+        });
+}
+
+/// Bar chart of how many runs fell in each histogram bucket.
+fn draw_histogram(ui: &mut egui::Ui, buckets: &[stats::HistogramBucket], x_label: &str) {
+    let bars: Vec<Bar> = buckets
+        .iter()
+        .map(|b| {
+            Bar::new(b.start as f64 + b.size as f64 / 2.0, b.runs as f64)
+                .name(format!("{} – {}", b.start, b.start + b.size - 1))
+                .width(b.size as f64 * 0.9)
+                .fill(egui::Color32::from_rgb(90, 150, 230))
+        })
+        .collect();
+
+    Plot::new("run_fe_histogram")
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show_grid([false, true])
+        .include_y(0.0)
+        .x_axis_label(x_label)
+        .y_axis_label("Runs")
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars));
+        });
+}
+
 /// Launch the standalone GUI application.
 pub fn run() -> anyhow::Result<()> {
     let options = eframe::NativeOptions {
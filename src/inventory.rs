@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::log_parser::BagEvent;
+use crate::storage;
+use crate::viewmodel::{self, NumberLocale};
+
+/// One line of an inventory export: a single slot, or every slot of one item
+/// when grouped (`page` and `slot` are then empty).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InventoryRow {
+    pub item_name: String,
+    pub config_base_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u32>,
+    pub quantity: u64,
+    /// How many slots hold the item (1 unless grouped).
+    #[serde(default = "one_slot")]
+    pub slots: usize,
+}
+
+fn one_slot() -> usize {
+    1
+}
+
+/// Export rows for `items`, one per slot or (when `grouped`) one per item
+/// sorted by name.
+pub fn rows(items: &[BagEvent], grouped: bool) -> Vec<InventoryRow> {
+    if !grouped {
+        return items
+            .iter()
+            .map(|item| InventoryRow {
+                item_name: item.item_name.clone(),
+                config_base_id: item.config_base_id.clone(),
+                page: Some(item.page_id),
+                slot: Some(item.slot_id),
+                quantity: item.num.into(),
+                slots: 1,
+            })
+            .collect();
+    }
+    let mut by_id: BTreeMap<&str, InventoryRow> = BTreeMap::new();
+    for item in items {
+        let row = by_id
+            .entry(&item.config_base_id)
+            .or_insert_with(|| InventoryRow {
+                item_name: item.item_name.clone(),
+                config_base_id: item.config_base_id.clone(),
+                page: None,
+                slot: None,
+                quantity: 0,
+                slots: 0,
+            });
+        row.quantity += u64::from(item.num);
+        row.slots += 1;
+    }
+    let mut rows: Vec<InventoryRow> = by_id.into_values().collect();
+    rows.sort_by(|a, b| a.item_name.cmp(&b.item_name));
+    rows
+}
+
+/// Grouped rows for a session's start or end snapshot; `None` when no
+/// inventory has been read (no sort or login in the log yet).
+pub fn session_snapshot(items: &[BagEvent]) -> Option<Vec<InventoryRow>> {
+    (!items.is_empty()).then(|| rows(items, true))
+}
+
+/// Net change of one item between two inventory snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InventoryChange {
+    pub item_name: String,
+    pub config_base_id: String,
+    pub before: u64,
+    pub after: u64,
+    pub delta: i64,
+}
+
+/// Total quantity per item id, with the name it was seen under.
+fn totals(rows: &[InventoryRow]) -> BTreeMap<&str, (&str, u64)> {
+    let mut totals: BTreeMap<&str, (&str, u64)> = BTreeMap::new();
+    for row in rows {
+        let entry = totals
+            .entry(&row.config_base_id)
+            .or_insert((&row.item_name, 0));
+        entry.1 += row.quantity;
+    }
+    totals
+}
+
+/// Per-item changes from `before` to `after` (per-slot or grouped rows
+/// alike), largest change first. Unchanged items are left out.
+pub fn diff(before: &[InventoryRow], after: &[InventoryRow]) -> Vec<InventoryChange> {
+    let before = totals(before);
+    let after = totals(after);
+    let ids: BTreeSet<&str> = before.keys().chain(after.keys()).copied().collect();
+    let mut changes: Vec<InventoryChange> = ids
+        .into_iter()
+        .filter_map(|id| {
+            let (name_before, qty_before) = before.get(id).copied().unwrap_or(("", 0));
+            let (name_after, qty_after) = after.get(id).copied().unwrap_or(("", 0));
+            let delta = qty_after as i64 - qty_before as i64;
+            (delta != 0).then(|| InventoryChange {
+                item_name: if name_after.is_empty() {
+                    name_before
+                } else {
+                    name_after
+                }
+                .to_string(),
+                config_base_id: id.to_string(),
+                before: qty_before,
+                after: qty_after,
+                delta,
+            })
+        })
+        .collect();
+    // Stable: ties stay in id order.
+    changes.sort_by_key(|c| std::cmp::Reverse(c.delta.abs()));
+    changes
+}
+
+/// Plain-text table of `changes` for `inventory-diff`.
+pub fn diff_table(changes: &[InventoryChange], locale: &NumberLocale) -> String {
+    let mut out = format!(
+        "{:<32} {:>10} {:>10} {:>10}\n",
+        "Item", "Before", "After", "Change"
+    );
+    for c in changes {
+        out.push_str(&format!(
+            "{:<32} {:>10} {:>10} {:>10}\n",
+            c.item_name,
+            viewmodel::format_count(c.before as i64, locale),
+            viewmodel::format_count(c.after as i64, locale),
+            viewmodel::format_delta(c.delta, locale)
+        ));
+    }
+    out
+}
+
+/// Read a snapshot written by `inventory --json`.
+pub fn load_rows(path: &Path) -> Result<Vec<InventoryRow>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| storage::path_context(e, "read inventory export", path))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not an inventory JSON export", path.display()))
+}
+
+/// A named inventory state saved with `snapshot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub taken_at: DateTime<Utc>,
+    /// Per-slot rows, as `inventory --json` writes them.
+    pub rows: Vec<InventoryRow>,
+}
+
+/// Where `snapshots.json` lives: next to the sessions file.
+pub fn snapshots_path(data_file: &Path) -> PathBuf {
+    data_file.with_file_name("snapshots.json")
+}
+
+/// Saved snapshots by name; a missing file means none.
+pub fn load_snapshots(path: &Path) -> Result<BTreeMap<String, Snapshot>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a snapshots file", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(storage::path_context(e, "read snapshots file", path).into()),
+    }
+}
+
+pub fn save_snapshots(path: &Path, snapshots: &BTreeMap<String, Snapshot>) -> Result<()> {
+    let json = serde_json::to_string_pretty(snapshots)?;
+    fs::write(path, json).map_err(|e| storage::path_context(e, "save snapshots to", path).into())
+}
+
+/// Rows for a `inventory-diff` argument: the snapshot of that name, or else
+/// an `inventory --json` export at that path.
+pub fn resolve_rows(
+    arg: &str,
+    snapshots: &BTreeMap<String, Snapshot>,
+) -> Result<Vec<InventoryRow>> {
+    if let Some(snapshot) = snapshots.get(arg) {
+        return Ok(snapshot.rows.clone());
+    }
+    let path = Path::new(arg);
+    if !path.exists() {
+        return Err(anyhow!("No snapshot or file named {:?}", arg));
+    }
+    load_rows(path)
+}
+
+/// How full one bag page is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageFullness {
+    pub page: u32,
+    pub occupied: usize,
+    /// Slots on the page, if configured.
+    pub capacity: Option<usize>,
+}
+
+impl PageFullness {
+    /// Occupied share of the page (0.0–1.0), if its capacity is known.
+    pub fn ratio(&self) -> Option<f64> {
+        match self.capacity {
+            Some(0) | None => None,
+            Some(capacity) => Some(self.occupied as f64 / capacity as f64),
+        }
+    }
+}
+
+/// Occupied slots per page, for every page with items or a known capacity,
+/// sorted by page id.
+pub fn page_fullness(items: &[BagEvent], capacities: &BTreeMap<u32, usize>) -> Vec<PageFullness> {
+    let mut slots: BTreeMap<u32, BTreeSet<u32>> = capacities
+        .keys()
+        .map(|&page| (page, BTreeSet::new()))
+        .collect();
+    for item in items {
+        slots.entry(item.page_id).or_default().insert(item.slot_id);
+    }
+    slots
+        .into_iter()
+        .map(|(page, occupied)| PageFullness {
+            page,
+            occupied: occupied.len(),
+            capacity: capacities.get(&page).copied(),
+        })
+        .collect()
+}
+
+/// Warning for the pages at least `percent` full; pages without a known
+/// capacity never warn.
+pub fn fullness_warning(pages: &[PageFullness], percent: u8) -> Option<String> {
+    let full: Vec<String> = pages
+        .iter()
+        .filter_map(|p| {
+            let ratio = p.ratio()?;
+            (ratio * 100.0 >= f64::from(percent)).then(|| {
+                format!(
+                    "bag page {} is {:.0}% full ({}/{})",
+                    p.page,
+                    ratio * 100.0,
+                    p.occupied,
+                    p.capacity.unwrap_or(0)
+                )
+            })
+        })
+        .collect();
+    if full.is_empty() {
+        None
+    } else {
+        Some(format!("Inventory: {}", full.join(", ")))
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// CSV with a header line; grouped rows have no page/slot columns.
+pub fn to_csv(rows: &[InventoryRow], grouped: bool) -> String {
+    let mut out = if grouped {
+        String::from("item_name,config_base_id,quantity,slots\n")
+    } else {
+        String::from("item_name,config_base_id,page,slot,quantity\n")
+    };
+    for row in rows {
+        let name = csv_field(&row.item_name);
+        let id = csv_field(&row.config_base_id);
+        let line = match (grouped, row.page, row.slot) {
+            (false, Some(page), Some(slot)) => {
+                format!("{},{},{},{},{}\n", name, id, page, slot, row.quantity)
+            }
+            _ => format!("{},{},{},{}\n", name, id, row.quantity, row.slots),
+        };
+        out.push_str(&line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(page_id: u32, slot_id: u32, id: &str, name: &str, num: u32) -> BagEvent {
+        BagEvent {
+            page_id,
+            slot_id,
+            config_base_id: id.to_string(),
+            item_name: name.to_string(),
+            num,
+            is_init: true,
+        }
+    }
+
+    fn sample() -> Vec<BagEvent> {
+        vec![
+            slot(102, 0, "100300", "Flame Elementium", 600),
+            slot(102, 1, "100300", "Flame Elementium", 99),
+            slot(103, 4, "6002", "Ember, Refined", 3),
+        ]
+    }
+
+    #[test]
+    fn test_per_slot_csv() {
+        let csv = to_csv(&rows(&sample(), false), false);
+        assert_eq!(
+            csv,
+            "item_name,config_base_id,page,slot,quantity\n\
+             Flame Elementium,100300,102,0,600\n\
+             Flame Elementium,100300,102,1,99\n\
+             \"Ember, Refined\",6002,103,4,3\n"
+        );
+    }
+
+    #[test]
+    fn test_grouped_csv_sums_slots() {
+        let csv = to_csv(&rows(&sample(), true), true);
+        assert_eq!(
+            csv,
+            "item_name,config_base_id,quantity,slots\n\
+             \"Ember, Refined\",6002,3,1\n\
+             Flame Elementium,100300,699,2\n"
+        );
+    }
+
+    #[test]
+    fn test_page_fullness_with_unknown_capacity() {
+        let mut items = sample();
+        // A second event for an occupied slot is still one slot.
+        items.push(slot(102, 1, "100300", "Flame Elementium", 100));
+        let capacities = BTreeMap::from([(102, 4), (104, 10)]);
+        let pages = page_fullness(&items, &capacities);
+        assert_eq!(
+            pages,
+            [
+                PageFullness {
+                    page: 102,
+                    occupied: 2,
+                    capacity: Some(4),
+                },
+                PageFullness {
+                    page: 103,
+                    occupied: 1,
+                    capacity: None,
+                },
+                PageFullness {
+                    page: 104,
+                    occupied: 0,
+                    capacity: Some(10),
+                },
+            ]
+        );
+        assert_eq!(pages[0].ratio(), Some(0.5));
+        assert_eq!(pages[1].ratio(), None);
+        assert_eq!(pages[2].ratio(), Some(0.0));
+    }
+
+    #[test]
+    fn test_fullness_warning_threshold() {
+        let capacities = BTreeMap::from([(102, 2), (103, 4)]);
+        let pages = page_fullness(&sample(), &capacities);
+        assert_eq!(
+            fullness_warning(&pages, 90).as_deref(),
+            Some("Inventory: bag page 102 is 100% full (2/2)")
+        );
+        assert_eq!(
+            fullness_warning(&pages, 25).as_deref(),
+            Some("Inventory: bag page 102 is 100% full (2/2), bag page 103 is 25% full (1/4)")
+        );
+        // Unknown capacity: no warning however many slots are used.
+        let pages = page_fullness(&sample(), &BTreeMap::new());
+        assert_eq!(fullness_warning(&pages, 1), None);
+    }
+
+    fn grouped(id: &str, quantity: u64) -> InventoryRow {
+        InventoryRow {
+            item_name: format!("Item {}", id),
+            config_base_id: id.to_string(),
+            page: None,
+            slot: None,
+            quantity,
+            slots: 1,
+        }
+    }
+
+    #[test]
+    fn test_diff_added_removed_and_changed() {
+        let before = [grouped("1", 10), grouped("2", 5), grouped("3", 7)];
+        let after = [grouped("1", 4), grouped("3", 7), grouped("4", 2)];
+        let changes = diff(&before, &after);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.config_base_id.as_str(), c.before, c.after, c.delta))
+            .collect();
+        assert_eq!(summary, [("1", 10, 4, -6), ("2", 5, 0, -5), ("4", 0, 2, 2)]);
+        assert_eq!(changes[1].item_name, "Item 2");
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_diff_sums_slots() {
+        let before = rows(&sample(), false);
+        let mut items = sample();
+        items.remove(1);
+        let after = rows(&items, true);
+        let changes = diff(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].before, changes[0].after), (699, 600));
+    }
+
+    #[test]
+    fn test_load_rows_reads_json_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inventory.json");
+        let exported = rows(&sample(), false);
+        fs::write(&path, serde_json::to_string(&exported).unwrap()).unwrap();
+        assert_eq!(load_rows(&path).unwrap(), exported);
+
+        fs::write(&path, "item_name,config_base_id\n").unwrap();
+        assert!(load_rows(&path).is_err());
+    }
+
+    #[test]
+    fn test_snapshots_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = snapshots_path(&dir.path().join("sessions.json"));
+        assert!(load_snapshots(&path).unwrap().is_empty());
+
+        let snapshot = Snapshot {
+            taken_at: "2026-10-16T10:00:00Z".parse().unwrap(),
+            rows: rows(&sample(), false),
+        };
+        let mut snapshots = BTreeMap::from([("before craft".to_string(), snapshot.clone())]);
+        save_snapshots(&path, &snapshots).unwrap();
+        let loaded = load_snapshots(&path).unwrap();
+        assert_eq!(loaded, snapshots);
+        assert_eq!(
+            resolve_rows("before craft", &loaded).unwrap(),
+            snapshot.rows
+        );
+        assert!(resolve_rows("missing", &loaded).is_err());
+
+        snapshots.remove("before craft");
+        save_snapshots(&path, &snapshots).unwrap();
+        assert!(load_snapshots(&path).unwrap().is_empty());
+
+        let unwritable = dir.path().join("missing").join("snapshots.json");
+        let err = save_snapshots(&unwritable, &snapshots).unwrap_err();
+        assert!(err.to_string().starts_with(&format!(
+            "Failed to save snapshots to {}: ",
+            unwritable.display()
+        )));
+    }
+
+    #[test]
+    fn test_csv_field_escapes_quotes() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            to_csv(&[], true),
+            "item_name,config_base_id,quantity,slots\n"
+        );
+    }
+}
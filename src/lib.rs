@@ -0,0 +1,42 @@
+//! Log parsing, session storage and display logic behind `tli-tracker`.
+//!
+//! The binary's GUI, TUI and CLI are built on these modules; other tools can
+//! use them to read Torchlight: Infinite's `UE_game.log` too.
+//!
+//! ```
+//! use tli_tracker::{parse_line, LogEvent, FLAME_ELEMENTIUM_ID};
+//!
+//! let line = "[2024.08.21-08.36.47:367][930]GameLog: Display: [Game] \
+//!     BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 671";
+//! match parse_line(line) {
+//!     Some(LogEvent::Bag(bag)) => {
+//!         assert_eq!(bag.config_base_id, FLAME_ELEMENTIUM_ID);
+//!         assert_eq!(bag.item_name, "Flame Elementium");
+//!         assert_eq!(bag.num, 671);
+//!     }
+//!     other => panic!("unexpected event: {:?}", other),
+//! }
+//! ```
+
+pub mod debounce;
+pub mod demo;
+pub mod inventory;
+pub mod log_parser;
+pub mod log_source;
+pub mod models;
+pub mod records;
+pub mod scoring;
+pub mod settings;
+pub mod stats;
+pub mod storage;
+pub mod tracker;
+pub mod viewmodel;
+
+pub use log_parser::{
+    item_name, parse_line, parse_log, parse_loot_from_log, BagEvent, CustomEvent, ItemDelta,
+    LineMatchers, LogEvent, LootSummary, FLAME_ELEMENTIUM_ID,
+};
+pub use log_source::{FileLogSource, HttpLogSource, LogEncoding, LogSource, TailedLog};
+pub use models::{DropItem, RunRecord, Session};
+pub use settings::Settings;
+pub use storage::{load_sessions, save_sessions};
@@ -1,9 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io;
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::Path;
 
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::log_source::{FileLogSource, LogEncoding, LogSource};
+use crate::storage;
 
 /// ConfigBaseId for Flame Elementium – the primary tracked resource.
 pub const FLAME_ELEMENTIUM_ID: &str = "100300";
@@ -16,15 +21,97 @@ static ITEMS_JSON: &str = include_str!("items.json");
 fn item_db() -> &'static HashMap<String, String> {
     use std::sync::OnceLock;
     static DB: OnceLock<HashMap<String, String>> = OnceLock::new();
-    DB.get_or_init(|| serde_json::from_str(ITEMS_JSON).unwrap_or_default())
+    DB.get_or_init(|| {
+        serde_json::from_str(ITEMS_JSON).unwrap_or_else(|e| {
+            warn!(error = %e, "embedded item database is invalid; item names unavailable");
+            HashMap::new()
+        })
+    })
+}
+
+/// Embedded alias table: old ConfigBaseId → current ConfigBaseId, for items
+/// the game remapped between patches.
+static ALIASES_JSON: &str = include_str!("aliases.json");
+
+fn alias_db() -> &'static HashMap<String, String> {
+    use std::sync::OnceLock;
+    static DB: OnceLock<HashMap<String, String>> = OnceLock::new();
+    DB.get_or_init(|| {
+        serde_json::from_str(ALIASES_JSON).unwrap_or_else(|e| {
+            warn!(error = %e, "embedded alias table is invalid; item aliases unavailable");
+            HashMap::new()
+        })
+    })
+}
+
+/// Follow `aliases` from `id` to the newest id (chains allowed, cycles cut).
+pub fn resolve_alias<'a>(id: &'a str, aliases: &'a HashMap<String, String>) -> &'a str {
+    let mut current = id;
+    for _ in 0..aliases.len() {
+        match aliases.get(current) {
+            Some(next) if next != current => current = next,
+            _ => break,
+        }
+    }
+    current
+}
+
+fn lookup_name(
+    config_base_id: &str,
+    items: &HashMap<String, String>,
+    aliases: &HashMap<String, String>,
+) -> Option<String> {
+    items
+        .get(config_base_id)
+        .or_else(|| items.get(resolve_alias(config_base_id, aliases)))
+        .cloned()
 }
 
 /// Resolve a ConfigBaseId to the English item name (or "Unknown <id>").
+///
+/// Ids missing from the item database are looked up again through the
+/// alias table before giving up.
 pub fn item_name(config_base_id: &str) -> String {
-    item_db()
+    lookup_name(config_base_id, item_db(), alias_db())
+        .unwrap_or_else(|| format!("Unknown {}", config_base_id))
+}
+
+/// The name to show for a ConfigBaseId: the user's own alias for it if
+/// they set one (see `Settings::item_aliases`), else [`item_name`].
+pub fn display_name(config_base_id: &str, aliases: &BTreeMap<String, String>) -> String {
+    aliases
         .get(config_base_id)
         .cloned()
-        .unwrap_or_else(|| format!("Unknown {}", config_base_id))
+        .unwrap_or_else(|| item_name(config_base_id))
+}
+
+/// The English name of a ConfigBaseId, if the item database (or an alias)
+/// knows it.
+pub fn known_item_name(config_base_id: &str) -> Option<String> {
+    lookup_name(config_base_id, item_db(), alias_db())
+}
+
+/// The real name for a stored `"Unknown <id>"` drop name, if the id can now
+/// be resolved (directly or via an alias).
+pub fn resolve_unknown_name(name: &str) -> Option<String> {
+    rename_unknown(name, item_db(), alias_db())
+}
+
+fn rename_unknown(
+    name: &str,
+    items: &HashMap<String, String>,
+    aliases: &HashMap<String, String>,
+) -> Option<String> {
+    let id = name.strip_prefix("Unknown ")?.trim();
+    lookup_name(id, items, aliases)
+}
+
+/// Every known item name, sorted and de-duplicated.
+pub fn item_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = item_db().values().map(String::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
 }
 
 // ── Parsed event types ────────────────────────────────────────────────
@@ -54,6 +141,15 @@ pub struct ContextMarker {
 #[derive(Debug, Clone, Serialize)]
 pub struct MapEvent {
     pub zone_path: String,
+    /// Map tier/level from the zone path's numeric suffix, if it has one.
+    pub tier: Option<u32>,
+}
+
+impl MapEvent {
+    /// The last segment of the zone path, e.g. `XZ_YuJinZhiXiBiNanSuo200`.
+    pub fn name(&self) -> &str {
+        self.zone_path.rsplit('/').next().unwrap_or(&self.zone_path)
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -63,6 +159,104 @@ pub enum LogEvent {
     BagRemove(BagRemoveEvent),
     Context(ContextMarker),
     Map(MapEvent),
+    /// Produced by a matcher registered with [`LineMatchers`].
+    Custom(CustomEvent),
+}
+
+/// What a custom line matcher reports: any JSON value. Objects appear in
+/// the event stream next to `"type": "Custom"` (so should not have a `type`
+/// key of their own); other values are wrapped as `{"value": ...}`.
+pub type CustomEvent = serde_json::Value;
+
+type Matcher = Box<dyn Fn(&str) -> Option<CustomEvent> + Send + Sync>;
+
+/// The built-in parser plus extra matchers for lines it doesn't know.
+///
+/// Matchers only see lines no built-in event matched, and run in the order
+/// they were registered; the first to return `Some` wins.
+///
+/// ```
+/// use tli_tracker::log_parser::{LineMatchers, LogEvent};
+/// use serde_json::json;
+///
+/// let mut matchers = LineMatchers::new();
+/// matchers.register(|line| {
+///     let level = line.split("PlayerLevelUp Level = ").nth(1)?;
+///     Some(json!({ "level_up": level.trim().parse::<u32>().ok()? }))
+/// });
+///
+/// let event = matchers.parse_line("GameLog: Display: [Game] PlayerLevelUp Level = 87");
+/// assert!(matches!(event, Some(LogEvent::Custom(v)) if v["level_up"] == 87));
+/// ```
+#[derive(Default)]
+pub struct LineMatchers {
+    matchers: Vec<Matcher>,
+}
+
+impl LineMatchers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a matcher, tried after those registered before it.
+    pub fn register(
+        &mut self,
+        matcher: impl Fn(&str) -> Option<CustomEvent> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.matchers.push(Box::new(matcher));
+        self
+    }
+
+    /// Like [`parse_line`], falling back to the registered matchers.
+    pub fn parse_line(&self, line: &str) -> Option<LogEvent> {
+        parse_line(line).or_else(|| {
+            let value = self.matchers.iter().find_map(|m| m(line))?;
+            Some(LogEvent::Custom(match value {
+                serde_json::Value::Object(_) => value,
+                other => serde_json::json!({ "value": other }),
+            }))
+        })
+    }
+
+    /// Like [`read_events_since`], including custom events.
+    pub fn read_events_since(&self, log_path: &Path, since: u64) -> io::Result<EventBatch> {
+        let read = || -> io::Result<(u64, Vec<u8>, LogEncoding)> {
+            let encoding = LogEncoding::of_file(log_path)?;
+            let mut file = fs::File::open(log_path)?;
+            let start = if since > file.metadata()?.len() {
+                0
+            } else {
+                since
+            };
+            file.seek(SeekFrom::Start(start))?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            Ok((start, buf, encoding))
+        };
+        let (start, buf, encoding) = read().map_err(|e| {
+            warn!(path = %log_path.display(), error = %e, "failed to read game log");
+            storage::path_context(e, "read game log", log_path)
+        })?;
+        let complete = encoding.complete_lines(&buf);
+        let events = encoding
+            .decode(&buf[..complete])
+            .0
+            .lines()
+            .filter_map(|line| self.parse_line(line))
+            .collect();
+        Ok(EventBatch {
+            events,
+            offset: start + complete as u64,
+        })
+    }
+}
+
+/// Events parsed from a log after some byte offset, with the offset to
+/// continue from.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventBatch {
+    pub events: Vec<LogEvent>,
+    pub offset: u64,
 }
 
 // ── Inventory state (delta tracking) ──────────────────────────────────
@@ -73,6 +267,7 @@ pub struct ItemDelta {
     pub config_base_id: String,
     pub item_name: String,
     pub delta: i64,
+    /// Total stack size across all slots, replayed from the whole log.
     pub current: u32,
 }
 
@@ -81,17 +276,128 @@ pub struct ItemDelta {
 pub struct LootSummary {
     pub items: Vec<ItemDelta>,
     pub total_events: usize,
+    /// An inventory sort was found to measure deltas from.
+    pub baseline_found: bool,
+    /// The log has any bag events at all (false for a fresh, empty log).
+    pub events_seen: bool,
+    /// When the last inventory sort happened, if its log line has a timestamp.
+    pub last_sort_at: Option<DateTime<Utc>>,
+    /// Inventory sorts in the log; a change between polls means deltas are
+    /// now measured from a new baseline.
+    pub sorts_seen: usize,
+    /// Bag lines that could not be parsed.
+    pub anomalies: ParseAnomalies,
+    /// The last game-start line in the log; a different one between polls
+    /// means the game was relaunched and deltas start from its login
+    /// inventory.
+    pub game_start: Option<String>,
 }
 
+/// How many sample lines `ParseAnomalies` keeps.
+const ANOMALY_SAMPLES: usize = 3;
+
+/// Lines that look like bag events but didn't parse, e.g. after the game
+/// changed its log format. Their loot is lost, so the user should know.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParseAnomalies {
+    pub count: usize,
+    /// The first few offending lines, for a bug report.
+    pub samples: Vec<String>,
+}
+
+impl ParseAnomalies {
+    fn record(&mut self, line: &str) {
+        self.count += 1;
+        if self.samples.len() < ANOMALY_SAMPLES {
+            self.samples.push(line.to_string());
+        }
+    }
+
+    /// Warning for the user, if any line failed.
+    pub fn warning(&self) -> Option<String> {
+        match self.count {
+            0 => None,
+            1 => Some("1 bag line couldn't be parsed — please report this.".to_string()),
+            n => Some(format!(
+                "{} bag lines couldn't be parsed — please report this.",
+                n
+            )),
+        }
+    }
+}
+
+/// Share of unknown item ids above which the embedded item database is
+/// probably from an older season than the log.
+pub const UNKNOWN_ITEMS_WARN_FRACTION: f64 = 0.5;
+
+/// Fewer items than this are too few to judge the item database by.
+const UNKNOWN_ITEMS_MIN_ITEMS: usize = 4;
+
 impl LootSummary {
-    /// Return the net Flame Elementium delta from this summary.
-    pub fn flame_elementium_delta(&self) -> i64 {
+    /// Share of the items whose id the item database doesn't know (named
+    /// `Unknown <id>`), or `None` if there are no items.
+    pub fn unknown_fraction(&self) -> Option<f64> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let unknown = self
+            .items
+            .iter()
+            .filter(|i| i.item_name == format!("Unknown {}", i.config_base_id))
+            .count();
+        Some(unknown as f64 / self.items.len() as f64)
+    }
+
+    /// Warning for the user when most item ids are unknown, which usually
+    /// means the game has had a new season since this build.
+    pub fn item_db_warning(&self) -> Option<String> {
+        if self.items.len() < UNKNOWN_ITEMS_MIN_ITEMS {
+            return None;
+        }
+        let fraction = self.unknown_fraction()?;
+        (fraction > UNKNOWN_ITEMS_WARN_FRACTION).then(|| {
+            format!(
+                "{:.0}% of item ids are unknown — the item database looks older than the game. \
+                 Update tli-tracker for the new item names.",
+                fraction * 100.0
+            )
+        })
+    }
+
+    /// Return the net delta of the currency `config_base_id` from this summary.
+    pub fn primary_delta(&self, config_base_id: &str) -> i64 {
         self.items
             .iter()
-            .filter(|i| i.config_base_id == FLAME_ELEMENTIUM_ID)
+            .filter(|i| i.config_base_id == config_base_id)
             .map(|i| i.delta)
             .sum()
     }
+
+    /// Why no loot can be shown yet, if the log explains it.
+    pub fn hint(&self) -> Option<&'static str> {
+        if !self.events_seen {
+            Some("Log is empty — play and pick up items, then sort to sync.")
+        } else if !self.baseline_found {
+            Some("No inventory sort in the log yet — sort your inventory in-game to sync.")
+        } else {
+            None
+        }
+    }
+
+    /// Return the net Flame Elementium delta from this summary.
+    #[deprecated(note = "use `primary_delta` with the configured currency id")]
+    pub fn flame_elementium_delta(&self) -> i64 {
+        self.primary_delta(FLAME_ELEMENTIUM_ID)
+    }
+}
+
+/// Short label for a currency: "FE" for Flame Elementium, else its name.
+pub fn currency_label(config_base_id: &str) -> String {
+    if config_base_id == FLAME_ELEMENTIUM_ID {
+        "FE".to_string()
+    } else {
+        item_name(config_base_id)
+    }
 }
 
 // ── Inventory pages we care about ─────────────────────────────────────
@@ -104,6 +410,20 @@ fn is_tracked_page(page_id: u32) -> bool {
 
 // ── Line parsers ──────────────────────────────────────────────────────
 
+/// Markers of the bag lines `parse_line` understands.
+const BAG_MARKERS: &[&str] = &[
+    "BagMgr@:Modfy",
+    "BagMgr@:InitBagData",
+    "BagMgr@:RemoveBagItem",
+];
+
+/// Whether `line`, which `parse_line` rejected, looked like a bag event.
+/// Lines for untracked pages are skipped on purpose and don't count.
+fn is_anomaly(line: &str) -> bool {
+    BAG_MARKERS.iter().any(|marker| line.contains(marker))
+        && extract_field(line, "PageId").is_none_or(is_tracked_page)
+}
+
 fn parse_bag_modify(line: &str) -> Option<BagEvent> {
     // BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 671
     if !line.contains("BagMgr@:Modfy") {
@@ -190,7 +510,33 @@ fn parse_map_event(line: &str) -> Option<MapEvent> {
     let rest = &line[idx + prefix.len()..];
     let eq = rest.find('=')?;
     let path = rest[eq + 1..].trim().to_string();
-    Some(MapEvent { zone_path: path })
+    Some(MapEvent {
+        tier: zone_tier(&path),
+        zone_path: path,
+    })
+}
+
+/// The tier/level encoded as a numeric suffix on the level name in a zone
+/// path, e.g. 200 for `.../XZ_YuJinZhiXiBiNanSuo200/XZ_YuJinZhiXiBiNanSuo200`.
+///
+/// Only the level asset and its folder (the last two segments, with any
+/// `.Object` suffix dropped) are checked, and only names with letters before
+/// at most four trailing digits count, so `01SD` or a bare `2` do not.
+pub fn zone_tier(zone_path: &str) -> Option<u32> {
+    zone_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .take(2)
+        .find_map(|segment| {
+            let name = segment.split('.').next().unwrap_or(segment);
+            let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+            let digits = &name[stem.len()..];
+            let has_letters = stem.ends_with(|c: char| c.is_ascii_alphabetic());
+            if digits.is_empty() || digits.len() > 4 || !has_letters {
+                return None;
+            }
+            digits.parse().ok()
+        })
 }
 
 // ── Field extraction helpers ──────────────────────────────────────────
@@ -238,12 +584,124 @@ pub fn parse_line(line: &str) -> Option<LogEvent> {
     None
 }
 
-/// Parse loot from the most recent PickItems block(s) in the log file.
+/// Parse the complete lines written to the log after byte offset `since`.
 ///
-/// Reads the log, finds the last inventory snapshot (InitBagData block from
-/// sorting) or picks events, and returns item deltas.
-pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
-    let contents = fs::read_to_string(log_path)?;
+/// A last line without its newline yet is left for the next call. If the log
+/// is now shorter than `since` (the game restarted and truncated it), reading
+/// starts over from the beginning.
+pub fn read_events_since(log_path: &Path, since: u64) -> io::Result<EventBatch> {
+    LineMatchers::new().read_events_since(log_path, since)
+}
+
+/// Everything the tracker takes from one pass over the log.
+#[derive(Debug, Clone)]
+pub struct LogSnapshot {
+    pub loot: LootSummary,
+    pub inventory: Vec<BagEvent>,
+    /// Readable name of the last map entered, if any.
+    pub map: Option<String>,
+    /// Tier of that map, if its zone path had one.
+    pub map_tier: Option<u32>,
+}
+
+/// The item-change context pickups happen in; the default loot context.
+pub const PICK_ITEMS: &str = "PickItems";
+
+/// Loot contexts used unless the user configures others.
+pub fn default_loot_contexts() -> Vec<String> {
+    vec![PICK_ITEMS.to_string()]
+}
+
+/// Parse loot, inventory and current map from the text `source` hands out,
+/// read as a whole log. Item changes count as loot inside the
+/// `loot_contexts` (item-change proto names such as `PickItems`).
+pub fn parse_log(source: &mut dyn LogSource, loot_contexts: &[String]) -> io::Result<LogSnapshot> {
+    Ok(snapshot_from_text(&source.read_new()?, loot_contexts))
+}
+
+/// Parse loot, inventory and current map from the whole log `contents`.
+pub fn snapshot_from_text(contents: &str, loot_contexts: &[String]) -> LogSnapshot {
+    let (map, map_tier) = match last_map(contents) {
+        Some((map, tier)) => (Some(map), tier),
+        None => (None, None),
+    };
+    LogSnapshot {
+        loot: loot_from_text(contents, loot_contexts),
+        inventory: inventory_from_text(contents),
+        map,
+        map_tier,
+    }
+}
+
+/// Parse loot from the text `source` hands out, read as a whole log.
+pub fn parse_loot(source: &mut dyn LogSource, loot_contexts: &[String]) -> io::Result<LootSummary> {
+    Ok(loot_from_text(&source.read_new()?, loot_contexts))
+}
+
+/// Parse loot from the log file at `log_path`.
+pub fn parse_loot_from_log(log_path: &Path, loot_contexts: &[String]) -> io::Result<LootSummary> {
+    parse_loot(&mut FileLogSource::new(log_path), loot_contexts)
+}
+
+/// The current inventory in the log file at `log_path`.
+pub fn parse_inventory_from_log(log_path: &Path) -> io::Result<Vec<BagEvent>> {
+    Ok(inventory_from_text(
+        &FileLogSource::new(log_path).read_new()?,
+    ))
+}
+
+/// Readable name and tier of the last map entered in `contents`.
+fn last_map(contents: &str) -> Option<(String, Option<u32>)> {
+    contents
+        .lines()
+        .rev()
+        .find_map(|line| match parse_line(line) {
+            Some(LogEvent::Map(m)) => Some((m.name().to_string(), m.tier)),
+            _ => None,
+        })
+}
+
+/// Slot contents after replaying every bag line in `lines`.
+///
+/// A game start re-sends the whole inventory, so it clears every slot. A
+/// sort re-sends only the pages it sorted: the first InitBagData on a page
+/// after a sort clears that page, and pages the sort left alone keep the
+/// stacks seen before it.
+fn replay_inventory(lines: &[&str]) -> HashMap<(u32, u32), BagEvent> {
+    let mut slots: HashMap<(u32, u32), BagEvent> = HashMap::new();
+    // Pages already re-sent since the last sort; `None` outside a sort.
+    let mut resent: Option<HashSet<u32>> = None;
+    for line in lines {
+        if is_game_start(line) {
+            slots.clear();
+            resent = None;
+        } else if is_sort_end(line) {
+            resent = Some(HashSet::new());
+        }
+        match parse_line(line) {
+            Some(LogEvent::Bag(bag)) => {
+                if let Some(resent) = resent.as_mut().filter(|_| bag.is_init) {
+                    if resent.insert(bag.page_id) {
+                        slots.retain(|(page, _), _| *page != bag.page_id);
+                    }
+                }
+                slots.insert((bag.page_id, bag.slot_id), bag);
+            }
+            Some(LogEvent::BagRemove(rem)) => {
+                slots.remove(&(rem.page_id, rem.slot_id));
+            }
+            _ => {}
+        }
+    }
+    slots
+}
+
+/// Parse loot from the loot-context blocks (PickItems by default) after the
+/// most recent sort in the log.
+///
+/// Finds the last inventory snapshot (InitBagData block from sorting) or
+/// picks events, and returns item deltas.
+fn loot_from_text(contents: &str, loot_contexts: &[String]) -> LootSummary {
     let lines: Vec<&str> = contents.lines().collect();
 
     // Track slot state: (page_id, slot_id) -> (config_base_id, num)
@@ -251,25 +709,32 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
     // Track net deltas per config_base_id
     let mut deltas: HashMap<String, i64> = HashMap::new();
     let mut total_events: usize = 0;
+    let mut events_seen = false;
     let mut in_pickup = false;
+    let mut anomalies = ParseAnomalies::default();
 
     // Find last ResetItemsLayout (sort) to get baseline
-    let mut last_reset_end: Option<usize> = None;
-    for (i, line) in lines.iter().enumerate().rev() {
-        if line.contains("ItemChange@")
-            && line.contains("ProtoName=ResetItemsLayout")
-            && line.contains("end")
-        {
-            last_reset_end = Some(i);
-            break;
-        }
-    }
+    let last_reset_end = lines.iter().rposition(|l| is_sort_end(l));
+
+    // A relaunch after it re-sends the whole inventory at login, so the
+    // baseline is rebuilt from those InitBagData lines instead.
+    let last_game_start = lines.iter().rposition(|l| is_game_start(l));
 
     // If we found a sort, build baseline from InitBagData lines after it
-    let scan_start = last_reset_end.unwrap_or(0);
+    let scan_start = last_reset_end.max(last_game_start).unwrap_or(0);
+
+    // Deltas only look at events after the sort, but `current` must also
+    // count stacks on pages the sort did not re-send.
+    let inventory = replay_inventory(&lines);
 
-    for line in &lines[scan_start..] {
+    for (i, line) in lines.iter().enumerate() {
         if let Some(ev) = parse_line(line) {
+            if matches!(ev, LogEvent::Bag(_) | LogEvent::BagRemove(_)) {
+                events_seen = true;
+            }
+            if i < scan_start {
+                continue;
+            }
             match ev {
                 LogEvent::Bag(ref bag) if bag.is_init => {
                     // Snapshot: set slot state baseline
@@ -278,10 +743,8 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
                         (bag.config_base_id.clone(), bag.num),
                     );
                 }
-                LogEvent::Context(ref ctx) => {
-                    if ctx.proto_name == "PickItems" {
-                        in_pickup = ctx.is_start;
-                    }
+                LogEvent::Context(ref ctx) if loot_contexts.contains(&ctx.proto_name) => {
+                    in_pickup = ctx.is_start;
                 }
                 LogEvent::Bag(ref bag) if !bag.is_init => {
                     let key = (bag.page_id, bag.slot_id);
@@ -290,6 +753,8 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
                         .filter(|(cid, _)| *cid == bag.config_base_id)
                         .map(|(_, n)| *n as i64)
                         .unwrap_or(0);
+                    // `Num` is the stack's new total, so a line the game
+                    // flushes twice changes nothing the second time.
                     let delta = bag.num as i64 - prev_num;
                     if in_pickup && delta != 0 {
                         *deltas.entry(bag.config_base_id.clone()).or_insert(0) += delta;
@@ -309,6 +774,8 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
                 }
                 _ => {}
             }
+        } else if is_anomaly(line) {
+            anomalies.record(line);
         }
     }
 
@@ -316,10 +783,10 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
         .into_iter()
         .filter(|(_, d)| *d != 0)
         .map(|(cid, delta)| {
-            let current = slot_state
+            let current = inventory
                 .values()
-                .filter(|(c, _)| *c == cid)
-                .map(|(_, n)| *n)
+                .filter(|bag| bag.config_base_id == cid)
+                .map(|bag| bag.num)
                 .sum();
             ItemDelta {
                 item_name: item_name(&cid),
@@ -330,76 +797,168 @@ pub fn parse_loot_from_log(log_path: &Path) -> io::Result<LootSummary> {
         })
         .collect();
 
-    // Sort by absolute delta descending
-    items.sort_by(|a, b| b.delta.abs().cmp(&a.delta.abs()));
+    // Sort by absolute delta descending; ties by id so rows don't jitter
+    // between polls with the HashMap's iteration order.
+    items.sort_by(|a, b| {
+        b.delta
+            .abs()
+            .cmp(&a.delta.abs())
+            .then_with(|| a.config_base_id.cmp(&b.config_base_id))
+    });
 
-    Ok(LootSummary {
+    debug!(
+        scan_start,
+        baseline = last_reset_end.is_some(),
+        items = items.len(),
+        total_events,
+        anomalies = anomalies.count,
+        "parsed loot from log"
+    );
+    if let Some(sample) = anomalies.samples.first() {
+        warn!(
+            count = anomalies.count,
+            sample = %sample,
+            "bag lines in the game log failed to parse"
+        );
+    }
+
+    LootSummary {
         items,
         total_events,
-    })
+        baseline_found: last_reset_end.is_some(),
+        last_sort_at: last_reset_end.and_then(|i| line_time(lines[i])),
+        sorts_seen: lines.iter().filter(|l| is_sort_end(l)).count(),
+        events_seen,
+        anomalies,
+        game_start: last_game_start.map(|i| lines[i].to_string()),
+    }
 }
 
-/// Return the current inventory snapshot from the log file.
-///
-/// Reads InitBagData entries from the most recent sort and applies any
-/// subsequent Modfy / Remove events to produce the current state.
-pub fn parse_inventory_from_log(log_path: &Path) -> io::Result<Vec<BagEvent>> {
-    let contents = fs::read_to_string(log_path)?;
-    let lines: Vec<&str> = contents.lines().collect();
+/// The end of an inventory sort, after which InitBagData lists every slot.
+fn is_sort_end(line: &str) -> bool {
+    line.contains("ItemChange@")
+        && line.contains("ProtoName=ResetItemsLayout")
+        && line.contains("end")
+}
 
-    let mut slot_state: HashMap<(u32, u32), BagEvent> = HashMap::new();
-
-    // Find last sort event
-    let mut last_reset_end: Option<usize> = None;
-    for (i, line) in lines.iter().enumerate().rev() {
-        if line.contains("ItemChange@")
-            && line.contains("ProtoName=ResetItemsLayout")
-            && line.contains("end")
-        {
-            last_reset_end = Some(i);
-            break;
+/// The part of `contents` that loot parsing measures from: everything from
+/// the last inventory sort or game launch on, or the whole text if it has
+/// neither. Parsing the excerpt alone gives the same deltas as the full log.
+pub fn baseline_excerpt(contents: &str) -> &str {
+    let mut start = 0;
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        if is_sort_end(line) || is_game_start(line) {
+            start = offset;
         }
+        offset += line.len();
     }
+    &contents[start..]
+}
 
-    let scan_start = last_reset_end.unwrap_or(0);
+/// The line the engine writes first when the game launches, e.g.
+/// `Log file open, 10/16/26 10:00:00`.
+pub fn is_game_start(line: &str) -> bool {
+    line.trim_start().starts_with("Log file open")
+}
 
-    for line in &lines[scan_start..] {
-        if let Some(ev) = parse_line(line) {
-            match ev {
-                LogEvent::Bag(bag) => {
-                    slot_state.insert((bag.page_id, bag.slot_id), bag);
-                }
-                LogEvent::BagRemove(rem) => {
-                    slot_state.remove(&(rem.page_id, rem.slot_id));
-                }
-                _ => {}
-            }
-        }
-    }
+/// The `[2024.08.21-08.36.47:367]` timestamp UE puts at the start of log
+/// lines (UTC).
+pub fn line_time(line: &str) -> Option<DateTime<Utc>> {
+    let stamp = line.strip_prefix('[')?.get(..23)?;
+    NaiveDateTime::parse_from_str(stamp, "%Y.%m.%d-%H.%M.%S:%3f")
+        .ok()
+        .map(|t| t.and_utc())
+}
 
-    let mut items: Vec<BagEvent> = slot_state.into_values().collect();
+/// Return the current inventory snapshot from the log.
+///
+/// Replays the bag lines (see [`replay_inventory`]), ordered by page and slot.
+fn inventory_from_text(contents: &str) -> Vec<BagEvent> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut items: Vec<BagEvent> = replay_inventory(&lines).into_values().collect();
     items.sort_by(|a, b| {
         a.page_id
             .cmp(&b.page_id)
             .then(a.slot_id.cmp(&b.slot_id))
     });
-    Ok(items)
+    items
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::log_source::StringLogSource;
 
     #[test]
     fn test_item_name_lookup() {
         assert_eq!(item_name("100300"), "Flame Elementium");
     }
 
+    #[test]
+    fn test_display_name_prefers_user_alias() {
+        let aliases = BTreeMap::from([("5028".to_string(), "that one shard".to_string())]);
+        assert_eq!(display_name("5028", &aliases), "that one shard");
+        assert_eq!(
+            display_name(FLAME_ELEMENTIUM_ID, &aliases),
+            "Flame Elementium"
+        );
+        assert_eq!(display_name("999999999", &aliases), "Unknown 999999999");
+    }
+
     #[test]
     fn test_item_name_unknown() {
         assert_eq!(item_name("999999999"), "Unknown 999999999");
     }
 
+    fn table(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_alias_follows_chain() {
+        let aliases = table(&[("1", "2"), ("2", "3"), ("9", "9")]);
+        assert_eq!(resolve_alias("1", &aliases), "3");
+        assert_eq!(resolve_alias("2", &aliases), "3");
+        assert_eq!(resolve_alias("9", &aliases), "9");
+        assert_eq!(resolve_alias("4", &aliases), "4");
+
+        let cycle = table(&[("a", "b"), ("b", "a")]);
+        let end = resolve_alias("a", &cycle);
+        assert!(end == "a" || end == "b");
+    }
+
+    #[test]
+    fn test_lookup_name_uses_alias_for_unknown_id() {
+        let items = table(&[("100300", "Flame Elementium")]);
+        let aliases = table(&[("555", "100300")]);
+        assert_eq!(
+            lookup_name("555", &items, &aliases).as_deref(),
+            Some("Flame Elementium")
+        );
+        assert_eq!(lookup_name("556", &items, &aliases), None);
+    }
+
+    #[test]
+    fn test_rename_unknown() {
+        let items = table(&[("100300", "Flame Elementium")]);
+        let aliases = table(&[("555", "100300")]);
+        assert_eq!(
+            rename_unknown("Unknown 555", &items, &aliases).as_deref(),
+            Some("Flame Elementium")
+        );
+        assert_eq!(rename_unknown("Unknown 777", &items, &aliases), None);
+        assert_eq!(rename_unknown("Flame Core", &items, &aliases), None);
+    }
+
+    #[test]
+    fn test_embedded_alias_table_parses() {
+        assert!(serde_json::from_str::<HashMap<String, String>>(ALIASES_JSON).is_ok());
+    }
+
     #[test]
     fn test_parse_bag_modify() {
         let line = "GameLog: Display: [Game] BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 671";
@@ -464,11 +1023,203 @@ mod tests {
         match ev {
             LogEvent::Map(m) => {
                 assert!(m.zone_path.contains("XZ_YuJinZhiXiBiNanSuo200"));
+                assert_eq!(m.tier, Some(200));
             }
             _ => panic!("expected Map event"),
         }
     }
 
+    #[test]
+    fn test_zone_tier() {
+        let maps = "/Game/Art/Maps/01SD";
+        let tier = |level: &str| zone_tier(&format!("{}/{}", maps, level));
+        assert_eq!(
+            tier("XZ_YuJinZhiXiBiNanSuo200/XZ_YuJinZhiXiBiNanSuo200"),
+            Some(200)
+        );
+        assert_eq!(tier("XZ_YuJinZhiXiBiNanSuo200/test"), Some(200));
+        assert_eq!(tier("YJ_Rift/YJ_Rift_T3.YJ_Rift_T3"), Some(3));
+        // No suffix on the level or its folder: the 01SD folder above is
+        // not a tier.
+        assert_eq!(tier("XZ_YuJinZhiXiBiNanSuo/XZ_YuJinZhiXiBiNanSuo"), None);
+        assert_eq!(tier("Town/2"), None);
+        assert_eq!(tier("XZ_Map/XZ_Map20240101"), None);
+        assert_eq!(zone_tier(""), None);
+    }
+
+    #[test]
+    fn test_custom_matchers() {
+        let mut matchers = LineMatchers::new();
+        matchers
+            .register(|line| {
+                let name = line.split("Boss killed: ").nth(1)?;
+                Some(serde_json::json!({ "boss": name }))
+            })
+            .register(|line| {
+                line.contains("Boss")
+                    .then(|| serde_json::json!("boss line"))
+            });
+
+        let event = matchers
+            .parse_line("GameLog: Display: [Game] Boss killed: Ember")
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "Custom", "boss": "Ember" })
+        );
+        // Later matchers see what earlier ones pass on; bare values are wrapped.
+        let event = matchers.parse_line("Boss spawned").unwrap();
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "Custom", "value": "boss line" })
+        );
+        // Built-in events come first; unknown lines stay unknown.
+        let pickup = "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5";
+        assert!(matches!(
+            matchers.parse_line(pickup),
+            Some(LogEvent::Bag(_))
+        ));
+        assert!(matchers.parse_line("noise").is_none());
+        // The built-in parser alone ignores custom lines.
+        assert!(parse_line("Boss killed: Ember").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        std::fs::write(&log, format!("{pickup}\nBoss killed: Ember\nnoise\n")).unwrap();
+        let batch = matchers.read_events_since(&log, 0).unwrap();
+        assert_eq!(batch.events.len(), 2);
+        assert!(matches!(&batch.events[1], LogEvent::Custom(v) if v["boss"] == "Ember"));
+    }
+
+    #[test]
+    fn test_malformed_bag_lines_are_counted() {
+        let log = [
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5",
+            // Format changed: the count is now called Count.
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Count = 9",
+            "BagMgr@:RemoveBagItem PageId = 103 Slot 4",
+            // Gear is skipped on purpose, not an anomaly.
+            "BagMgr@:Modfy BagItem PageId = 100 SlotId = 0 ConfigBaseId = 1 Num = 1",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 1 ConfigBaseId = 1 Num = x",
+            "BagMgr@:Modfy BagItem PageId = 102",
+            "Some unrelated line",
+        ]
+        .join("\n");
+        let loot = parse_loot(&mut StringLogSource(log), &default_loot_contexts()).unwrap();
+        assert_eq!(loot.anomalies.count, 4);
+        assert_eq!(loot.anomalies.samples.len(), ANOMALY_SAMPLES);
+        assert!(loot.anomalies.samples[0].ends_with("Count = 9"));
+        assert_eq!(
+            loot.anomalies.warning().unwrap(),
+            "4 bag lines couldn't be parsed — please report this."
+        );
+        assert_eq!(ParseAnomalies::default().warning(), None);
+    }
+
+    #[test]
+    fn test_unknown_fraction_and_item_db_warning() {
+        let summary = |ids: &[&str]| LootSummary {
+            items: ids
+                .iter()
+                .map(|id| ItemDelta {
+                    config_base_id: id.to_string(),
+                    item_name: item_name(id),
+                    delta: 1,
+                    current: 1,
+                })
+                .collect(),
+            total_events: ids.len(),
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 1,
+            anomalies: ParseAnomalies::default(),
+            game_start: None,
+            events_seen: true,
+        };
+
+        assert_eq!(summary(&[]).unknown_fraction(), None);
+        let mostly_unknown = summary(&[FLAME_ELEMENTIUM_ID, "900001", "900002", "900003"]);
+        assert_eq!(mostly_unknown.unknown_fraction(), Some(0.75));
+        assert_eq!(
+            mostly_unknown.item_db_warning().unwrap(),
+            "75% of item ids are unknown — the item database looks older than the game. \
+             Update tli-tracker for the new item names."
+        );
+
+        // Half unknown is not over the threshold.
+        let half = summary(&[FLAME_ELEMENTIUM_ID, "5028", "900001", "900002"]);
+        assert_eq!(half.unknown_fraction(), Some(0.5));
+        assert_eq!(half.item_db_warning(), None);
+
+        // Too few items to judge.
+        let few = summary(&["900001", "900002"]);
+        assert_eq!(few.unknown_fraction(), Some(1.0));
+        assert_eq!(few.item_db_warning(), None);
+    }
+
+    #[test]
+    fn test_is_game_start() {
+        assert!(is_game_start("Log file open, 10/16/26 10:00:00"));
+        assert!(!is_game_start(
+            "[2026.10.16-10.00.00:000][  0]LogInit: Display: Engine ready"
+        ));
+        assert!(!is_game_start("ItemChange@ ProtoName=ResetItemsLayout end"));
+    }
+
+    #[test]
+    fn test_relaunch_rebaselines_from_login_inventory() {
+        let log = [
+            "Log file open, 10/16/26 10:00:00",
+            "ItemChange@ ProtoName=ResetItemsLayout end",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 3 ConfigBaseId = 5028 Num = 2",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 550",
+            "ItemChange@ ProtoName=PickItems end",
+            // Crash and relaunch: the login re-sends the inventory, in a new
+            // layout and without the 5028 stack.
+            "Log file open, 10/16/26 11:00:00",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 1 ConfigBaseId = 100300 Num = 550",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 1 ConfigBaseId = 100300 Num = 560",
+            "ItemChange@ ProtoName=PickItems end",
+        ]
+        .join("\n");
+        let loot = parse_loot(&mut StringLogSource(log.clone()), &default_loot_contexts()).unwrap();
+        assert_eq!(
+            loot.game_start.as_deref(),
+            Some("Log file open, 10/16/26 11:00:00")
+        );
+        assert_eq!(loot.items.len(), 1);
+        assert_eq!(loot.items[0].delta, 10);
+        assert_eq!(loot.items[0].current, 560);
+
+        let inventory = inventory_from_text(&log);
+        assert_eq!(inventory.len(), 1);
+        assert_eq!((inventory[0].slot_id, inventory[0].num), (1, 560));
+    }
+
+    #[test]
+    fn test_baseline_excerpt_keeps_deltas() {
+        let log = [
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 400",
+            "ItemChange@ ProtoName=ResetItemsLayout end",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 550",
+            "ItemChange@ ProtoName=PickItems end",
+        ]
+        .join("\n");
+        let excerpt = baseline_excerpt(&log);
+        assert!(excerpt.starts_with("ItemChange@ ProtoName=ResetItemsLayout end"));
+        let full = loot_from_text(&log, &default_loot_contexts());
+        let replayed = loot_from_text(excerpt, &default_loot_contexts());
+        assert_eq!(replayed.items[0].delta, full.items[0].delta);
+        assert_eq!(replayed.items[0].delta, 50);
+
+        assert_eq!(baseline_excerpt("no markers here\n"), "no markers here\n");
+    }
+
     #[test]
     fn test_excluded_page() {
         let line = "GameLog: Display: [Game] BagMgr@:Modfy BagItem PageId = 100 SlotId = 0 ConfigBaseId = 100300 Num = 1";
@@ -499,8 +1250,375 @@ mod tests {
                 },
             ],
             total_events: 5,
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 0,
+            anomalies: ParseAnomalies::default(),
+            game_start: None,
+            events_seen: true,
         };
-        assert_eq!(summary.flame_elementium_delta(), 150);
+        assert_eq!(summary.primary_delta(FLAME_ELEMENTIUM_ID), 150);
+        assert_eq!(summary.primary_delta("200100"), 20);
+        #[allow(deprecated)]
+        let fe = summary.flame_elementium_delta();
+        assert_eq!(fe, 150);
+    }
+
+    #[test]
+    fn test_currency_label() {
+        assert_eq!(currency_label(FLAME_ELEMENTIUM_ID), "FE");
+        assert_eq!(currency_label("999999999"), "Unknown 999999999");
+    }
+
+    /// Everything logged at WARN or above while `f` runs.
+    fn capture_warnings(f: impl FnOnce()) -> String {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::WARN)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        let output = capture.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_read_failure_emits_warning() {
+        let missing = Path::new("/nonexistent/tli-tracker/UE_game.log");
+        let output = capture_warnings(|| {
+            assert!(parse_loot_from_log(missing, &default_loot_contexts()).is_err());
+        });
+        assert!(output.contains("WARN"), "no warning emitted: {output}");
+        assert!(output.contains("failed to read game log"));
+    }
+
+    #[test]
+    fn test_malformed_bag_line_emits_warning() {
+        let line = "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Count = 9";
+        let output = capture_warnings(|| {
+            let loot = loot_from_text(line, &default_loot_contexts());
+            assert_eq!(loot.anomalies.count, 1);
+        });
+        assert!(output.contains("WARN"), "no warning emitted: {output}");
+        assert!(output.contains("bag lines in the game log failed to parse"));
+        assert!(output.contains("count=1"), "{output}");
+        assert!(output.contains("Count = 9"), "{output}");
+
+        let output = capture_warnings(|| {
+            loot_from_text("Some unrelated line", &default_loot_contexts());
+        });
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn test_current_counts_stacks_initialized_before_sort() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let lines = [
+            // Login snapshot: two FE stacks in the bag and one on page 103.
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 200",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 5 ConfigBaseId = 100300 Num = 300",
+            "BagMgr@:InitBagData PageId = 103 SlotId = 2 ConfigBaseId = 100300 Num = 40",
+            // Sorting page 102 merges its stacks; page 103 is not re-sent.
+            "ItemChange@ ProtoName=ResetItemsLayout start",
+            "ItemChange@ ProtoName=ResetItemsLayout end",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 520",
+            "ItemChange@ ProtoName=PickItems end",
+        ];
+        std::fs::write(&log, lines.join("\n")).unwrap();
+
+        let summary = parse_loot_from_log(&log, &default_loot_contexts()).unwrap();
+        assert_eq!(summary.items.len(), 1);
+        let fe = &summary.items[0];
+        assert_eq!(fe.delta, 20);
+        // The merged stack once, plus the page-103 stack from before the sort.
+        assert_eq!(fe.current, 560);
+
+        // The inventory view agrees.
+        let inventory = parse_inventory_from_log(&log).unwrap();
+        let total: u32 = inventory
+            .iter()
+            .filter(|b| b.config_base_id == "100300")
+            .map(|b| b.num)
+            .sum();
+        assert_eq!(total, fe.current);
+        assert_eq!(inventory.len(), 2);
+    }
+
+    #[test]
+    fn test_tied_deltas_sort_by_id() {
+        let mut log = vec![
+            "ItemChange@ ProtoName=ResetItemsLayout end".to_string(),
+            "ItemChange@ ProtoName=PickItems start".to_string(),
+        ];
+        // Equal absolute deltas, one of them a loss, plus one larger gain.
+        for (slot, id, num) in [
+            (0, "6002", 3),
+            (1, "100300", 3),
+            (2, "5028", 7),
+            (3, "200100", 3),
+        ] {
+            log.push(format!(
+                "BagMgr@:Modfy BagItem PageId = 102 SlotId = {slot} ConfigBaseId = {id} Num = {num}"
+            ));
+        }
+        log.push(
+            "BagMgr@:InitBagData PageId = 103 SlotId = 0 ConfigBaseId = 7001 Num = 3".to_string(),
+        );
+        log.push("BagMgr@:RemoveBagItem PageId = 103 SlotId = 0".to_string());
+        log.push("ItemChange@ ProtoName=PickItems end".to_string());
+        let log = log.join("\n");
+
+        let order = |log: &str| -> Vec<String> {
+            parse_loot(
+                &mut StringLogSource(log.to_string()),
+                &default_loot_contexts(),
+            )
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| i.config_base_id)
+            .collect()
+        };
+        let first = order(&log);
+        assert_eq!(first, ["5028", "100300", "200100", "6002", "7001"]);
+        for _ in 0..10 {
+            assert_eq!(order(&log), first);
+        }
+    }
+
+    #[test]
+    fn test_item_across_pickup_windows_is_one_row() {
+        let mut log = vec![
+            "ItemChange@ ProtoName=ResetItemsLayout end".to_string(),
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500"
+                .to_string(),
+        ];
+        // Three windows, the last one spilling into a new stack.
+        for (slot, num) in [(0, 510), (0, 530), (1, 4)] {
+            log.push("ItemChange@ ProtoName=PickItems start".to_string());
+            log.push(format!(
+                "BagMgr@:Modfy BagItem PageId = 102 SlotId = {slot} ConfigBaseId = 100300 Num = {num}"
+            ));
+            log.push("ItemChange@ ProtoName=PickItems end".to_string());
+        }
+        let loot = parse_loot(
+            &mut StringLogSource(log.join("\n")),
+            &default_loot_contexts(),
+        )
+        .unwrap();
+        assert_eq!(loot.items.len(), 1);
+        assert_eq!(loot.items[0].delta, 34);
+        assert_eq!(loot.items[0].current, 534);
+        assert_eq!(loot.total_events, 3);
+    }
+
+    #[test]
+    fn test_duplicated_bag_lines_count_once() {
+        let modify = |slot: u32, num: u32| {
+            format!("BagMgr@:Modfy BagItem PageId = 102 SlotId = {slot} ConfigBaseId = 100300 Num = {num}")
+        };
+        let log = [
+            "ItemChange@ ProtoName=ResetItemsLayout end".to_string(),
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500"
+                .to_string(),
+            "ItemChange@ ProtoName=PickItems start".to_string(),
+            modify(0, 520),
+            modify(0, 520),
+            // A new stack with no baseline, also flushed twice.
+            modify(1, 3),
+            modify(1, 3),
+            "ItemChange@ ProtoName=PickItems end".to_string(),
+            // A second, real pick of the same item changes the total.
+            "ItemChange@ ProtoName=PickItems start".to_string(),
+            modify(0, 540),
+            "ItemChange@ ProtoName=PickItems end".to_string(),
+        ]
+        .join("\n");
+        let loot = parse_loot(&mut StringLogSource(log), &default_loot_contexts()).unwrap();
+        assert_eq!(loot.primary_delta(FLAME_ELEMENTIUM_ID), 43);
+        assert_eq!(loot.total_events, 3);
+    }
+
+    #[test]
+    fn test_custom_loot_context() {
+        let log = [
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 520",
+            "ItemChange@ ProtoName=PickItems end",
+            "ItemChange@ ProtoName=ChestRewards start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 600",
+            "ItemChange@ ProtoName=ChestRewards end",
+        ]
+        .join("\n");
+
+        let loot = parse_loot(&mut StringLogSource(log.clone()), &default_loot_contexts()).unwrap();
+        assert_eq!(loot.primary_delta(FLAME_ELEMENTIUM_ID), 20);
+
+        let contexts = vec![PICK_ITEMS.to_string(), "ChestRewards".to_string()];
+        let loot = parse_loot(&mut StringLogSource(log.clone()), &contexts).unwrap();
+        assert_eq!(loot.primary_delta(FLAME_ELEMENTIUM_ID), 100);
+        assert_eq!(loot.total_events, 2);
+
+        let contexts = vec!["ChestRewards".to_string()];
+        let loot = parse_loot(&mut StringLogSource(log), &contexts).unwrap();
+        assert_eq!(loot.primary_delta(FLAME_ELEMENTIUM_ID), 80);
+    }
+
+    #[test]
+    fn test_read_events_since_is_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let pickup = "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5";
+        let start = "ItemChange@ ProtoName=PickItems start";
+        std::fs::write(&log, format!("{start}\nnoise\n{pickup}\n")).unwrap();
+
+        let first = read_events_since(&log, 0).unwrap();
+        assert_eq!(first.events.len(), 2);
+        assert_eq!(first.offset, std::fs::metadata(&log).unwrap().len());
+
+        // Nothing new; then one complete line and one still being written.
+        assert!(read_events_since(&log, first.offset)
+            .unwrap()
+            .events
+            .is_empty());
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        io::Write::write_all(&mut file, format!("{pickup}\n{start}").as_bytes()).unwrap();
+        let second = read_events_since(&log, first.offset).unwrap();
+        assert_eq!(second.events.len(), 1);
+        assert!(matches!(second.events[0], LogEvent::Bag(ref b) if b.num == 5));
+        assert_eq!(second.offset, first.offset + pickup.len() as u64 + 1);
+
+        // A truncated log is read again from the start.
+        std::fs::write(&log, format!("{start}\n")).unwrap();
+        let restarted = read_events_since(&log, second.offset).unwrap();
+        assert_eq!(restarted.events.len(), 1);
+        assert_eq!(restarted.offset, start.len() as u64 + 1);
+    }
+
+    #[test]
+    fn test_read_events_since_utf16le_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let text = "ItemChange@ ProtoName=PickItems start\n\
+                    BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5\n\
+                    ItemChange@ ProtoName=PickItems";
+        let bytes: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        std::fs::write(&log, &bytes).unwrap();
+
+        let batch = read_events_since(&log, 0).unwrap();
+        assert_eq!(batch.events.len(), 2);
+        assert!(matches!(batch.events[1], LogEvent::Bag(ref b) if b.num == 5));
+        let unfinished = "ItemChange@ ProtoName=PickItems".len() as u64 * 2;
+        assert_eq!(batch.offset, bytes.len() as u64 - unfinished);
+        assert!(read_events_since(&log, batch.offset)
+            .unwrap()
+            .events
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_from_memory() {
+        let log = [
+            "ItemChange@ ProtoName=ResetItemsLayout end",
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+            "SceneLevelMgr@ OpenMainWorld END! InMainLevelPath = /Game/Art/Maps/01SD/XZ_YuJinZhiXiBiNanSuo200/test",
+            "ItemChange@ ProtoName=PickItems start",
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 540",
+            "ItemChange@ ProtoName=PickItems end",
+        ]
+        .join("\n");
+        let mut source = StringLogSource(log);
+        let snapshot = parse_log(&mut source, &default_loot_contexts()).unwrap();
+        assert_eq!(snapshot.loot.primary_delta(FLAME_ELEMENTIUM_ID), 40);
+        assert_eq!(snapshot.inventory.len(), 1);
+        assert_eq!(snapshot.inventory[0].num, 540);
+        assert_eq!(snapshot.map.as_deref(), Some("test"));
+
+        // The source is used up: a second parse sees an empty log.
+        let loot = parse_loot(&mut source, &default_loot_contexts()).unwrap();
+        assert!(!loot.events_seen);
+    }
+
+    #[test]
+    fn test_last_sort_time_from_log() {
+        assert_eq!(
+            line_time("[2024.08.21-08.36.47:367][930]GameLog: Display: [Game] x"),
+            Some("2024-08-21T08:36:47.367Z".parse().unwrap())
+        );
+        assert_eq!(line_time("GameLog: Display: [Game] x"), None);
+        assert_eq!(line_time("[not a time]"), None);
+
+        let log = [
+            "[2024.08.21-08.00.00:000][  1]GameLog: ItemChange@ ProtoName=ResetItemsLayout end",
+            "[2024.08.21-08.30.00:000][  2]GameLog: ItemChange@ ProtoName=ResetItemsLayout end",
+            "[2024.08.21-08.30.00:001][  3]GameLog: BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500",
+        ]
+        .join("\n");
+        let loot = parse_loot(&mut StringLogSource(log), &default_loot_contexts()).unwrap();
+        assert_eq!(
+            loot.last_sort_at,
+            Some("2024-08-21T08:30:00Z".parse().unwrap())
+        );
+        assert_eq!(loot.sorts_seen, 2);
+    }
+
+    #[test]
+    fn test_empty_log_reports_no_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        std::fs::write(&log, "").unwrap();
+
+        let summary = parse_loot_from_log(&log, &default_loot_contexts()).unwrap();
+        assert!(summary.items.is_empty());
+        assert!(!summary.events_seen);
+        assert!(!summary.baseline_found);
+        assert!(summary.hint().unwrap().starts_with("Log is empty"));
+    }
+
+    #[test]
+    fn test_log_with_only_map_events_reports_no_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let lines = [
+            "SceneLevelMgr@ OpenMainWorld END! InMainLevelPath = /Game/Art/Maps/01SD/XZ_YuJinZhiXiBiNanSuo200/test",
+            "SceneLevelMgr@ OpenMainWorld END! InMainLevelPath = /Game/Art/Maps/01SD/XZ_YuJinZhiXiBiNanSuo200/test",
+        ];
+        std::fs::write(&log, lines.join("\n")).unwrap();
+
+        let summary = parse_loot_from_log(&log, &default_loot_contexts()).unwrap();
+        assert!(!summary.events_seen);
+        assert!(summary.hint().unwrap().starts_with("Log is empty"));
+
+        // Bag events but no sort yet: ask for a sort instead.
+        let pickup = "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5";
+        std::fs::write(&log, [lines[0], pickup].join("\n")).unwrap();
+        let summary = parse_loot_from_log(&log, &default_loot_contexts()).unwrap();
+        assert!(summary.events_seen && !summary.baseline_found);
+        assert!(summary.hint().unwrap().starts_with("No inventory sort"));
     }
 
     #[test]
@@ -513,7 +1631,13 @@ mod tests {
                 current: 30,
             }],
             total_events: 1,
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 0,
+            anomalies: ParseAnomalies::default(),
+            game_start: None,
+            events_seen: true,
         };
-        assert_eq!(summary.flame_elementium_delta(), 0);
+        assert_eq!(summary.primary_delta(FLAME_ELEMENTIUM_ID), 0);
     }
 }
@@ -0,0 +1,465 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use encoding_rs::{UTF_16LE, UTF_8};
+use tracing::{debug, warn};
+
+use crate::storage;
+
+/// How long to wait for a remote log before giving up on this poll.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bytes at the start of a log looked at to tell its encoding.
+const ENCODING_SNIFF_BYTES: usize = 64;
+
+/// How a game log's bytes are encoded. Most installs write UTF-8, but on
+/// some Windows setups Unreal writes UTF-16LE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogEncoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+}
+
+impl LogEncoding {
+    /// Tell the encoding from the first bytes of a log: a UTF-16LE byte
+    /// order mark, or (for logs written without one) mostly-ASCII text with
+    /// a NUL after every character. Anything else is read as UTF-8.
+    pub fn detect(head: &[u8]) -> Self {
+        let head = &head[..head.len().min(ENCODING_SNIFF_BYTES)];
+        if head.starts_with(&[0xFF, 0xFE]) {
+            return Self::Utf16Le;
+        }
+        let units = head.len() / 2;
+        let nul_high = head
+            .chunks_exact(2)
+            .filter(|u| u[0] != 0 && u[1] == 0)
+            .count();
+        if units > 0 && nul_high * 4 >= units * 3 {
+            Self::Utf16Le
+        } else {
+            Self::Utf8
+        }
+    }
+
+    /// Decode `bytes`, dropping a byte order mark and replacing anything
+    /// malformed. Returns the text and how many bytes it used: a UTF-16 read
+    /// that ends halfway through a code unit leaves that byte for the next.
+    pub fn decode(self, bytes: &[u8]) -> (String, usize) {
+        let (encoding, used) = match self {
+            Self::Utf8 => (UTF_8, bytes.len()),
+            Self::Utf16Le => (UTF_16LE, bytes.len() & !1),
+        };
+        let (text, _) = encoding.decode_with_bom_removal(&bytes[..used]);
+        (text.into_owned(), used)
+    }
+
+    /// Length of `bytes` up to and including the last complete line.
+    pub fn complete_lines(self, bytes: &[u8]) -> usize {
+        match self {
+            Self::Utf8 => bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1),
+            Self::Utf16Le => bytes
+                .chunks_exact(2)
+                .rposition(|u| u == [b'\n', 0])
+                .map_or(0, |i| (i + 1) * 2),
+        }
+    }
+
+    /// The encoding of the log at `path`, from its first bytes.
+    pub fn of_file(path: &Path) -> io::Result<Self> {
+        let mut head = Vec::with_capacity(ENCODING_SNIFF_BYTES);
+        fs::File::open(path)?
+            .take(ENCODING_SNIFF_BYTES as u64)
+            .read_to_end(&mut head)?;
+        Ok(Self::detect(&head))
+    }
+}
+
+/// Somewhere game-log text comes from.
+///
+/// Each call returns the text appended since the previous call; the first
+/// call on a new source returns the whole log.
+pub trait LogSource {
+    fn name(&self) -> String;
+    fn read_new(&mut self) -> io::Result<String>;
+
+    /// Whether the last `read_new` started over from the beginning of the
+    /// log: the first read, or one after the log was truncated.
+    fn restarted(&self) -> bool {
+        false
+    }
+}
+
+/// A log source and all the text it has handed out, so each poll can parse
+/// the whole log while reading only what is new.
+pub struct TailedLog {
+    source: Box<dyn LogSource>,
+    text: String,
+}
+
+impl TailedLog {
+    pub fn new(source: Box<dyn LogSource>) -> Self {
+        Self {
+            source,
+            text: String::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.source.name()
+    }
+
+    /// Read what is new and return the whole log so far. When the source
+    /// starts over, its text replaces what was read before.
+    pub fn read(&mut self) -> io::Result<&str> {
+        let new = self.source.read_new()?;
+        if self.source.restarted() {
+            self.text.clear();
+        }
+        self.text.push_str(&new);
+        Ok(&self.text)
+    }
+}
+
+/// A log file on this machine.
+pub struct FileLogSource {
+    path: PathBuf,
+    offset: u64,
+    /// Detected on the read from the start of the file.
+    encoding: LogEncoding,
+    restarted: bool,
+}
+
+impl FileLogSource {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            offset: 0,
+            encoding: LogEncoding::default(),
+            restarted: false,
+        }
+    }
+}
+
+impl LogSource for FileLogSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn read_new(&mut self) -> io::Result<String> {
+        let read = |offset: &mut u64| -> io::Result<Vec<u8>> {
+            let mut file = fs::File::open(&self.path)?;
+            if *offset > file.metadata()?.len() {
+                // The game restarted and truncated the log.
+                *offset = 0;
+            }
+            file.seek(SeekFrom::Start(*offset))?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            Ok(buf)
+        };
+        let mut offset = self.offset;
+        let buf = read(&mut offset).map_err(|e| {
+            warn!(path = %self.path.display(), error = %e, "failed to read game log");
+            storage::path_context(e, "read game log", &self.path)
+        })?;
+        self.restarted = offset == 0;
+        if self.restarted {
+            self.encoding = LogEncoding::detect(&buf);
+        }
+        let (text, used) = self.encoding.decode(&buf);
+        self.offset = offset + used as u64;
+        Ok(text)
+    }
+
+    fn restarted(&self) -> bool {
+        self.restarted
+    }
+}
+
+/// A log served over HTTP, e.g. by a static file server on the gaming PC.
+///
+/// Later reads ask for the new bytes only, with a `Range` request.
+pub struct HttpLogSource {
+    url: String,
+    offset: u64,
+    /// Detected on the read from the start of the log.
+    encoding: LogEncoding,
+    restarted: bool,
+}
+
+impl HttpLogSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            offset: 0,
+            encoding: LogEncoding::default(),
+            restarted: false,
+        }
+    }
+}
+
+/// The full length in a `Content-Range: bytes */<len>` header, as sent with
+/// a 416 response.
+fn unsatisfied_range_len(response: &ureq::Response) -> Option<u64> {
+    response
+        .header("Content-Range")?
+        .trim()
+        .strip_prefix("bytes */")?
+        .parse()
+        .ok()
+}
+
+impl LogSource for HttpLogSource {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn read_new(&mut self) -> io::Result<String> {
+        let response = ureq::get(&self.url)
+            .timeout(HTTP_TIMEOUT)
+            .set("Range", &format!("bytes={}-", self.offset))
+            .call();
+        let response = match response {
+            Err(ureq::Error::Status(416, response)) => {
+                match unsatisfied_range_len(&response) {
+                    // The log is shorter than what we have seen: it was
+                    // truncated, so fetch it again from the start.
+                    Some(len) if len < self.offset => {
+                        debug!(url = %self.url, len, "remote game log truncated");
+                        self.offset = 0;
+                        return self.read_new();
+                    }
+                    // Nothing past the offset yet.
+                    _ => {
+                        self.restarted = false;
+                        return Ok(String::new());
+                    }
+                }
+            }
+            Ok(response) => response,
+            Err(e) => {
+                warn!(url = %self.url, error = %e, "failed to fetch remote game log");
+                return Err(io::Error::other(e));
+            }
+        };
+        let partial = response.status() == 206;
+        let mut buf = Vec::new();
+        response.into_reader().read_to_end(&mut buf)?;
+        let new = if partial {
+            &buf[..]
+        } else {
+            // The server ignored the range and sent the whole log; one
+            // shorter than what we have seen was truncated.
+            if self.offset as usize > buf.len() {
+                self.offset = 0;
+            }
+            &buf[self.offset as usize..]
+        };
+        debug!(url = %self.url, bytes = new.len(), partial, "fetched remote game log");
+        self.restarted = self.offset == 0;
+        if self.restarted {
+            self.encoding = LogEncoding::detect(new);
+        }
+        let (text, used) = self.encoding.decode(new);
+        self.offset += used as u64;
+        Ok(text)
+    }
+
+    fn restarted(&self) -> bool {
+        self.restarted
+    }
+}
+
+/// In-memory log text, handed out once.
+#[cfg(test)]
+pub struct StringLogSource(pub String);
+
+#[cfg(test)]
+impl LogSource for StringLogSource {
+    fn name(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn read_new(&mut self) -> io::Result<String> {
+        Ok(std::mem::take(&mut self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_file_source_reads_appended_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("UE_game.log");
+        fs::write(&path, "one\n").unwrap();
+        let mut source = FileLogSource::new(&path);
+        assert_eq!(source.read_new().unwrap(), "one\n");
+        assert_eq!(source.read_new().unwrap(), "");
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"two\n")
+            .unwrap();
+        assert_eq!(source.read_new().unwrap(), "two\n");
+
+        // Truncated by a game restart: start over.
+        fs::write(&path, "x\n").unwrap();
+        assert_eq!(source.read_new().unwrap(), "x\n");
+    }
+
+    #[test]
+    fn test_file_source_missing_file_errors() {
+        let mut source = FileLogSource::new(Path::new("/nonexistent/UE_game.log"));
+        let err = source.read_new().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let message = err.to_string();
+        assert!(
+            message.starts_with("Failed to read game log /nonexistent/UE_game.log: "),
+            "{message}"
+        );
+    }
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let bom = bom.then_some(0xFEFF);
+        bom.into_iter()
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    #[test]
+    fn test_utf16le_log_decodes_and_parses() {
+        let line = "BagMgr@:Modfy BagItem PageId = 102 SlotId = 3 ConfigBaseId = 100300 Num = 42\n";
+        for bom in [true, false] {
+            let bytes = utf16le(line, bom);
+            let encoding = LogEncoding::detect(&bytes);
+            assert_eq!(encoding, LogEncoding::Utf16Le);
+            let (text, used) = encoding.decode(&bytes);
+            assert_eq!(text, line);
+            assert_eq!(used, bytes.len());
+            assert_eq!(encoding.complete_lines(&bytes), bytes.len());
+            match crate::log_parser::parse_line(text.trim_end()) {
+                Some(crate::log_parser::LogEvent::Bag(bag)) => {
+                    assert_eq!(bag.config_base_id, "100300");
+                    assert_eq!((bag.page_id, bag.slot_id, bag.num), (102, 3, 42));
+                }
+                other => panic!("expected a bag event, got {other:?}"),
+            }
+        }
+        assert_eq!(LogEncoding::detect(line.as_bytes()), LogEncoding::Utf8);
+        assert_eq!(LogEncoding::detect(b""), LogEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_file_source_reads_utf16le_in_pieces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("UE_game.log");
+        let bytes = utf16le("one\ntwo\n", true);
+        // The game has written half of the last code unit so far.
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let mut source = FileLogSource::new(&path);
+        assert_eq!(source.read_new().unwrap(), "one\ntwo");
+        assert_eq!(source.offset as usize, bytes.len() - 2);
+
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(source.read_new().unwrap(), "\n");
+        assert_eq!(source.read_new().unwrap(), "");
+    }
+
+    /// Serve `logs` in turn, one per request, honouring `Range: bytes=N-`
+    /// and answering a range past the end with 416.
+    fn serve(logs: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/UE_game.log", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for (stream, log) in listener.incoming().zip(logs) {
+                let mut stream = stream.unwrap();
+                let mut start = 0;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        start = range.trim().trim_end_matches('-').parse().unwrap();
+                    }
+                }
+                if start > 0 && start >= log.len() {
+                    write!(
+                        stream,
+                        "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        log.len()
+                    )
+                    .unwrap();
+                    continue;
+                }
+                let body = &log[start..];
+                let status = if start == 0 {
+                    "200 OK"
+                } else {
+                    "206 Partial Content"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_http_source_requests_new_bytes_only() {
+        let url = serve(vec!["one\ntwo\n"; 2]);
+        let mut source = HttpLogSource::new(&url);
+        assert_eq!(source.read_new().unwrap(), "one\ntwo\n");
+        assert_eq!(source.read_new().unwrap(), "");
+        assert_eq!(source.offset, 8);
+    }
+
+    #[test]
+    fn test_http_source_starts_over_after_truncation() {
+        // The second request's range is past the end of the shorter log.
+        let url = serve(vec!["one\ntwo\n", "x\n", "x\n", "x\ny\n"]);
+        let mut log = TailedLog::new(Box::new(HttpLogSource::new(&url)));
+        assert_eq!(log.read().unwrap(), "one\ntwo\n");
+        assert_eq!(log.read().unwrap(), "x\n");
+        assert_eq!(log.read().unwrap(), "x\ny\n");
+    }
+
+    #[test]
+    fn test_tailed_log_keeps_text_until_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("UE_game.log");
+        fs::write(&path, "one\n").unwrap();
+        let mut log = TailedLog::new(Box::new(FileLogSource::new(&path)));
+        assert_eq!(log.read().unwrap(), "one\n");
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"two\n")
+            .unwrap();
+        assert_eq!(log.read().unwrap(), "one\ntwo\n");
+        assert_eq!(log.read().unwrap(), "one\ntwo\n");
+
+        fs::write(&path, "x\n").unwrap();
+        assert_eq!(log.read().unwrap(), "x\n");
+    }
+}
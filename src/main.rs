@@ -1,21 +1,103 @@
 mod gui;
-mod log_parser;
-mod models;
-mod storage;
+mod prices;
+mod prompt;
+mod record;
+mod scorecard;
+mod tui;
+mod watch;
 
-use chrono::Utc;
-use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
-use models::{DropItem, Session};
+use tli_tracker::{
+    debounce, demo, inventory, log_parser, log_source, models, records, scoring, settings, stats,
+    storage, tracker, viewmodel,
+};
+
+use models::{DropItem, Session, SessionFilter};
+use viewmodel::NumberLocale;
 
 #[derive(Parser)]
 #[command(name = "tli-tracker", version, about = "Torchlight: Infinite farming tracker")]
 struct Cli {
+    /// Show more detail (-v) and debug diagnostics (-vv for trace)
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Print only essential output (e.g. just the session id)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Emit JSON instead of text (List, Summary, Stats)
+    #[arg(long, global = true)]
+    json: bool,
+    /// Show what a mutating command would change without writing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Store sessions, settings and prices in this directory instead of the
+    /// platform data directory
+    #[arg(long, global = true, env = "TLI_DATA_DIR", value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    /// Use a named profile: separate sessions, settings and prices kept
+    /// under profiles/<NAME> in the data directory
+    #[arg(
+        long,
+        global = true,
+        env = "TLI_PROFILE",
+        value_name = "NAME",
+        value_parser = storage::parse_profile_name
+    )]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
 
+/// How much the CLI prints, derived from `-v`/`-q`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose(u8),
+}
+
+impl Verbosity {
+    fn from_flags(verbose: u8, quiet: bool) -> Self {
+        if quiet {
+            Verbosity::Quiet
+        } else if verbose > 0 {
+            Verbosity::Verbose(verbose)
+        } else {
+            Verbosity::Normal
+        }
+    }
+
+    fn is_quiet(self) -> bool {
+        self == Verbosity::Quiet
+    }
+
+    fn is_verbose(self) -> bool {
+        matches!(self, Verbosity::Verbose(_))
+    }
+
+    /// Default tracing filter when `RUST_LOG` is not set.
+    fn default_filter(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "error",
+            Verbosity::Normal => "warn",
+            Verbosity::Verbose(1) => "debug",
+            Verbosity::Verbose(_) => "trace",
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize local data storage
@@ -26,49 +108,563 @@ enum Commands {
         map: String,
         #[arg(long)]
         notes: Option<String>,
+        /// Game log to snapshot the inventory from (defaults to the detected one)
+        #[arg(long)]
+        log: Option<PathBuf>,
     },
     /// Add a drop to a session (defaults to active session)
+    ///
+    /// Prompts for any of --name/--quantity/--value that are omitted.
     AddDrop {
         #[arg(long)]
-        name: String,
-        #[arg(long, default_value_t = 1)]
-        quantity: u32,
+        name: Option<String>,
+        /// Defaults to 1 unless prompting
         #[arg(long)]
-        value: f64,
+        quantity: Option<u32>,
+        /// Value per item; must be finite and non-negative
+        #[arg(long, allow_hyphen_values = true)]
+        value: Option<f64>,
         #[arg(long)]
         session: Option<String>,
+        /// Prompt for the drop even when no arguments are missing
+        #[arg(short, long)]
+        interactive: bool,
+        /// Fail unless the session is still at this version (see `summary -v`)
+        #[arg(long)]
+        expect_version: Option<u64>,
+    },
+    /// Add several drops at once from a `name,quantity,value` list
+    ///
+    /// Every line is validated first; nothing is saved if any line is bad.
+    AddDrops {
+        /// File with one drop per line, or `-` for stdin
+        #[arg(long)]
+        file: PathBuf,
+        #[arg(long)]
+        session: Option<String>,
+        /// Fail unless the session is still at this version (see `summary -v`)
+        #[arg(long)]
+        expect_version: Option<u64>,
     },
     /// End a session (defaults to active session)
     EndSession {
         #[arg(long)]
         session: Option<String>,
+        /// Fail unless the session is still at this version (see `summary -v`)
+        #[arg(long)]
+        expect_version: Option<u64>,
+        /// Game log to snapshot the inventory from (defaults to the detected one)
+        #[arg(long)]
+        log: Option<PathBuf>,
     },
     /// List sessions
-    List,
+    List {
+        /// Only sessions on this map (case-insensitive)
+        #[arg(long)]
+        map: Option<String>,
+        /// Only active (true) or ended (false) sessions
+        #[arg(long)]
+        active: Option<bool>,
+        /// Only sessions started at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = models::parse_filter_time)]
+        since: Option<DateTime<Utc>>,
+        /// Only sessions started at or before this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = models::parse_filter_time)]
+        until: Option<DateTime<Utc>>,
+    },
     /// Show summary for a session (defaults to active session)
     Summary {
         #[arg(long)]
         session: Option<String>,
     },
+    /// Find the sessions in which an item dropped
+    ///
+    /// Sessions recorded by the GUI/TUI only keep their primary currency
+    /// per run, so other items are found in added drops only.
+    Find {
+        /// Item name (any case) or ConfigBaseId
+        item: String,
+    },
+    /// FE per map from runs recorded by the GUI/TUI tracker
+    Stats {
+        /// Break each map down by tier (runs without one are "unknown tier")
+        #[arg(long)]
+        by_tier: bool,
+    },
+    /// Histogram of FE per run, from runs recorded by the GUI/TUI tracker
+    Histogram {
+        /// Only runs on this map
+        #[arg(long)]
+        map: Option<String>,
+        /// FE covered by each bucket (default: the histogram_bucket setting)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        bucket_size: Option<u64>,
+    },
+    /// Compare the FE/hour of several sessions as a bar chart
+    Compare {
+        /// Session ids, separated by spaces or commas
+        #[arg(required = true, value_delimiter = ',')]
+        sessions: Vec<String>,
+    },
+    /// Pool several sessions of one farming setup into a single FE/hour
+    ///
+    /// The rate is total FE over total run time, so short sessions don't
+    /// count as much as long ones the way an average of rates would.
+    Combine {
+        /// Session ids, separated by spaces or commas
+        #[arg(required = true, value_delimiter = ',')]
+        sessions: Vec<String>,
+    },
+    /// Show a session's drop value by item as a bar chart
+    ///
+    /// The most valuable items get a bar each, the rest share an "Other"
+    /// bar. A session whose drops are worth nothing prints no bars.
+    Breakdown {
+        /// Session id (defaults to the active session)
+        #[arg(long)]
+        session: Option<String>,
+        /// Items shown before the rest are grouped as "Other"
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Show what a session's inventory actually changed by, from the
+    /// snapshots taken when it started and ended
+    NetChange {
+        /// Session id (defaults to the active session)
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Show or set daily FE goals per map (progress uses the local date)
+    Goal {
+        /// Map name (case-insensitive); omit to show every goal
+        map: Option<String>,
+        /// Daily FE goal for the map; 0 removes it
+        #[arg(long, requires = "map", allow_hyphen_values = true)]
+        set: Option<i64>,
+    },
+    /// Suggest the map with the best FE/hour so far
+    Recommend {
+        /// Only consider maps run at least this many times
+        #[arg(long, default_value_t = 3)]
+        min_runs: usize,
+        /// Recommend a map and tier rather than a map
+        #[arg(long)]
+        by_tier: bool,
+    },
     /// Export sessions to a JSON file
     Export {
         #[arg(long)]
         out: String,
+        /// Add each session's total value, duration, profit per minute and FE
+        #[arg(long)]
+        with_summary: bool,
+        /// Replace the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Render a session scorecard (map, time, FE, FE/hour, top items) as a PNG
+    Scorecard {
+        /// Session id (defaults to the active session)
+        #[arg(long)]
+        session: Option<String>,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Continuously redraw a session summary with live FE from the game log
+    Watch {
+        #[arg(long)]
+        session: Option<String>,
+        /// Seconds between refreshes
+        #[arg(long, default_value_t = 3)]
+        interval: u64,
+    },
+    /// Print game-log events after a byte offset as JSON, with the next offset
+    ///
+    /// Run it repeatedly with the returned offset to follow the log from
+    /// another machine (e.g. over ssh) without reading the file directly.
+    LogEvents {
+        /// Byte offset returned by the previous call (0 reads the whole log)
+        #[arg(long, default_value_t = 0)]
+        since: u64,
+        /// Game log to read instead of the detected UE_game.log
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Show the loot deltas parsed from the game log, like the GUI's Items tab
+    ///
+    /// With --json, prints the whole loot summary, including the event count,
+    /// whether an inventory sort was found to measure from, and a hint when
+    /// there is nothing to show yet.
+    Loot {
+        /// Show at most this many items, largest change first
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Game log to read instead of the detected UE_game.log
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Save the game-log lines behind a session to a file, for `replay`
+    ///
+    /// Starts with the log from the last inventory sort or game launch, so
+    /// the excerpt carries its own loot baseline, then appends new lines as
+    /// the game writes them until Ctrl-C.
+    Record {
+        /// File to write the excerpt to (overwritten)
+        out: PathBuf,
+        /// Seconds between reads of the log
+        #[arg(long, default_value_t = 3)]
+        interval: u64,
+        /// Game log to read instead of the detected UE_game.log
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Rebuild a session's loot offline from an excerpt saved by `record`
+    Replay {
+        file: PathBuf,
+        /// Show at most this many items, largest change first
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Export the current inventory from the game log as CSV (or JSON with --json)
+    Inventory {
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// One line per item with its total quantity instead of one per slot
+        #[arg(long)]
+        grouped: bool,
+        /// Game log to read instead of the detected UE_game.log
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Show what changed between two inventory states
+    ///
+    /// Each state is a snapshot name (see `snapshot`) or a file written by
+    /// `inventory --json --out`. Without AFTER, compares against the
+    /// current inventory in the game log.
+    InventoryDiff {
+        before: String,
+        after: Option<String>,
+        /// Game log to read the current inventory from
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Save the current inventory under a name, or list/delete saved ones
+    /// (snapshots.json next to sessions.json)
+    Snapshot {
+        /// Snapshot name; omit to list saved snapshots
+        name: Option<String>,
+        /// Delete the named snapshot instead of saving one
+        #[arg(long, requires = "name")]
+        delete: bool,
+        /// Game log to read instead of the detected UE_game.log
+        #[arg(long)]
+        log: Option<PathBuf>,
     },
     /// Launch standalone GUI application
     Gui,
+    /// Launch the terminal UI (for headless/SSH setups)
+    Tui,
+    /// Write a synthetic game log and show what the tracker reads from it
+    ///
+    /// For trying the app without the game: the log (demo/UE_game.log next
+    /// to sessions.json unless --out is given) has an inventory sort and
+    /// RUNS map runs. With --gui the GUI opens on it instead.
+    Demo {
+        /// Map runs to generate
+        #[arg(long, default_value_t = 5)]
+        runs: u32,
+        /// Flame Elementium picked up in each run
+        #[arg(long, default_value_t = 300)]
+        fe_per_run: u32,
+        /// Write the log here instead
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Replace the --out file if it already exists
+        #[arg(long, requires = "out")]
+        force: bool,
+        /// Open the GUI on the generated log
+        #[arg(long)]
+        gui: bool,
+    },
+    /// Show or change settings (settings.json next to sessions.json)
+    Settings(Box<SettingsArgs>),
+    /// End all but the most recent active session
+    Fix,
+    /// Delete ended sessions older than a cutoff, after backing up the file
+    ///
+    /// Active sessions are always kept. Use --dry-run to see what would go.
+    Prune {
+        /// Delete sessions that ended more than this many days ago
+        #[arg(long, value_parser = clap::value_parser!(i64).range(0..))]
+        older_than_days: i64,
+    },
+    /// Fetch item prices from a JSON endpoint into the local price cache
+    ///
+    /// The cache (prices.json next to sessions.json) supplies default values
+    /// when add-drop prompts. If the fetch fails the cached prices are kept.
+    UpdatePrices {
+        /// Price endpoint returning {"<id or name>": price, ...} or
+        /// [{"config_base_id"|"id"|"name": ..., "price": ...}, ...]
+        #[arg(long, env = "TLI_PRICE_URL")]
+        url: String,
+        /// Skip fetching if the cache is younger than this many minutes
+        #[arg(long, default_value_t = 60)]
+        ttl_minutes: i64,
+        /// Fetch even if the cache is still fresh
+        #[arg(long)]
+        force: bool,
+    },
+    /// Rename stored "Unknown <id>" drops whose id is now known or aliased
+    Migrate,
+    /// List the profiles in the data directory (select one with --profile)
+    Profiles,
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print a roff man page (or write it to --out)
+    Man {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+/// Flags of the `settings` command; each one given changes that setting.
+#[derive(Args)]
+struct SettingsArgs {
+    /// ConfigBaseId of the headline currency (default 100300, Flame Elementium)
+    #[arg(long)]
+    primary_currency: Option<String>,
+    /// Group digits of large counts in the GUI and TUI (true/false)
+    #[arg(long)]
+    group_digits: Option<bool>,
+    /// Shorten large numbers in the session stat boxes, e.g. 123.5k (true/false)
+    #[arg(long)]
+    abbreviate_numbers: Option<bool>,
+    /// Character placed between digit groups (default ',')
+    #[arg(long)]
+    thousands_separator: Option<char>,
+    /// Decimal mark (default '.', or ',' when the thousands separator is '.')
+    #[arg(long)]
+    decimal_separator: Option<char>,
+    /// Number style to use: us (1,234.5), eu (1.234,5), fr (1 234,5) or ch (1'234.5)
+    #[arg(long, value_parser = settings::parse_number_locale)]
+    number_locale: Option<NumberLocale>,
+    /// Decimals shown on rates such as FE/hour (default 0)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(..=6))]
+    rate_precision: Option<u32>,
+    /// Decimals shown on drop values and totals (default 2)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(..=6))]
+    value_precision: Option<u32>,
+    /// Completed runs averaged by the recent-pace stat (default 3)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    recent_runs: Option<u32>,
+    /// Warn when the recent pace stays below this many per hour (0 turns the alert off)
+    #[arg(long)]
+    pace_floor: Option<f64>,
+    /// Target primary currency per hour; the GUI colours the session rate
+    /// against it (0 turns it off)
+    #[arg(long, value_name = "RATE")]
+    target_per_hour: Option<f64>,
+    /// Consecutive completed runs below the floor before warning (default 2)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pace_alert_runs: Option<u32>,
+    /// End an active session left running this many hours once it has gone
+    /// idle, at its last drop (0 turns it off)
+    #[arg(long, value_name = "HOURS")]
+    auto_end_hours: Option<u64>,
+    /// Minutes without a drop before auto-end may end a session (default 60)
+    #[arg(long, value_name = "MINUTES")]
+    auto_end_idle_mins: Option<u64>,
+    /// FE covered by each bucket of the FE-per-run histogram (default 100)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    histogram_bucket: Option<u64>,
+    /// Leave ended sessions shorter than this many seconds out of stats (0 keeps all)
+    #[arg(long)]
+    min_session_secs: Option<u64>,
+    /// Read the game log over HTTP from this URL in the GUI/TUI ("" reads the local file)
+    #[arg(long)]
+    log_url: Option<String>,
+    /// Seconds between log polls in the GUI/TUI (default 3)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    poll_interval_secs: Option<u64>,
+    /// Hide an item (by id) from loot tables; repeatable
+    #[arg(long, value_name = "ID")]
+    ignore_item: Vec<String>,
+    /// Show a previously ignored item again; repeatable
+    #[arg(long, value_name = "ID")]
+    unignore_item: Vec<String>,
+    /// Whether ignored items still count toward item totals (true/false)
+    #[arg(long)]
+    count_ignored: Option<bool>,
+    /// Always list an item (by id) first in loot tables; repeatable
+    #[arg(long, value_name = "ID")]
+    pin_item: Vec<String>,
+    /// Stop pinning an item; repeatable
+    #[arg(long, value_name = "ID")]
+    unpin_item: Vec<String>,
+    /// How loot tables identify items: name, id or name_and_id
+    #[arg(long, value_name = "MODE")]
+    item_display: Option<viewmodel::ItemDisplay>,
+    /// Your own name for an item, as ID=NAME, shown instead of the official
+    /// one in tables; repeatable
+    #[arg(long, value_name = "ID=NAME", value_parser = settings::parse_item_alias)]
+    item_alias: Vec<(String, String)>,
+    /// Forget an item's alias; repeatable
+    #[arg(long, value_name = "ID")]
+    remove_item_alias: Vec<String>,
+    /// How loot totals are worked out: count, priced or weighted
+    #[arg(long, value_name = "MODE")]
+    score_mode: Option<scoring::ScoreMode>,
+    /// Weight of an item in the weighted score, as ID=WEIGHT; repeatable
+    #[arg(long, value_name = "ID=WEIGHT", value_parser = settings::parse_item_weight)]
+    item_weight: Vec<(String, f64)>,
+    /// Forget an item's weight (it weighs 1 again); repeatable
+    #[arg(long, value_name = "ID")]
+    remove_item_weight: Vec<String>,
+    /// Slots on a bag page, as PAGE=SLOTS (SLOTS 0 forgets the page); repeatable
+    #[arg(long, value_name = "PAGE=SLOTS", value_parser = settings::parse_page_capacity)]
+    page_capacity: Vec<(u32, usize)>,
+    /// Warn when a bag page is at least this many percent full (default 90)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    bag_warn_percent: Option<u8>,
+    /// Remind to sort the inventory after this many minutes (0 turns it off)
+    #[arg(long, value_name = "MINUTES")]
+    sort_reminder_mins: Option<u64>,
+    /// Also count item changes under this log ProtoName as loot; repeatable
+    #[arg(long, value_name = "NAME")]
+    loot_context: Vec<String>,
+    /// Stop counting item changes under this ProtoName as loot; repeatable
+    #[arg(long, value_name = "NAME")]
+    remove_loot_context: Vec<String>,
+    /// Session bar stats in display order, comma-separated (map, time,
+    /// primary, primary_per_hour, recent_pace, items, runs)
+    #[arg(long, value_delimiter = ',', num_args = 1.., value_name = "STATS")]
+    stat_boxes: Option<Vec<viewmodel::StatKind>>,
+}
+
+/// Failures the CLI reports, each with a distinct process exit code.
+#[derive(Debug)]
+enum CliError {
+    /// No `--session` given and no session is active (exit 2).
+    NoActiveSession,
+    /// The requested session id does not exist (exit 3).
+    SessionNotFound,
+    /// Reading or writing the data file failed (exit 4).
+    Storage(std::io::Error),
+    /// The session changed since the version the caller expected (exit 5).
+    Conflict { expected: u64, actual: u64 },
+    /// Anything else (exit 1).
+    Other(anyhow::Error),
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::NoActiveSession => 2,
+            CliError::SessionNotFound => 3,
+            CliError::Storage(_) => 4,
+            CliError::Conflict { .. } => 5,
+            CliError::Other(_) => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::NoActiveSession => {
+                write!(f, "No active session found. Specify --session <id>.")
+            }
+            CliError::SessionNotFound => write!(f, "Session not found"),
+            CliError::Storage(e) => write!(f, "{}", e),
+            CliError::Conflict { expected, actual } => write!(
+                f,
+                "Session changed: expected version {}, found {}. Reload and try again.",
+                expected, actual
+            ),
+            CliError::Other(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// IO on the sessions file (loading, saving, locking). Errors reading logs or
+/// writing other files are mapped to `Other` where they happen.
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        CliError::Storage(e)
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(e: serde_json::Error) -> Self {
+        CliError::Other(e.into())
+    }
 }
 
-fn main() -> anyhow::Result<()> {
+impl From<anyhow::Error> for CliError {
+    fn from(e: anyhow::Error) -> Self {
+        CliError::Other(e)
+    }
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    init_tracing(Verbosity::from_flags(cli.verbose, cli.quiet));
+
+    let data_file = match (&cli.data_dir, &cli.profile) {
+        (None, None) => storage::data_file_path(),
+        (dir, profile) => dir
+            .clone()
+            .map_or_else(storage::default_data_dir, Ok)
+            .and_then(|base| storage::use_data_dir(&storage::store_dir(&base, profile.as_deref()))),
+    };
+    let result = data_file
+        .map_err(CliError::from)
+        .and_then(|data_file| run(cli, &data_file, &mut std::io::stdout()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// `writeln!` to the command output; a failed write is not a storage error.
+macro_rules! say {
+    ($out:expr, $($arg:tt)*) => {
+        writeln!($out, $($arg)*).map_err(|e| CliError::Other(e.into()))?
+    };
+}
+
+/// Execute the parsed command against the sessions file at `data_file`,
+/// writing user-facing output to `out`.
+fn run(cli: Cli, data_file: &Path, out: &mut dyn Write) -> Result<(), CliError> {
+    let verbosity = Verbosity::from_flags(cli.verbose, cli.quiet);
+    let dry_run = cli.dry_run;
 
     match cli.command {
         Commands::Init => {
-            let path = storage::ensure_data_file()?;
-            println!("Storage initialized at {}", path.display());
+            storage::ensure_data_file(data_file)?;
+            if !verbosity.is_quiet() {
+                say!(out, "Storage initialized at {}", data_file.display());
+            }
         }
-        Commands::StartSession { map, notes } => {
-            let mut sessions = storage::load_sessions()?;
+        Commands::StartSession { map, notes, log } => {
+            let map = models::normalize_map(&map);
+            if map.is_empty() {
+                return Err(CliError::Other(anyhow::anyhow!(
+                    "Map name must not be empty"
+                )));
+            }
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
+            auto_end_sessions(&mut sessions, data_file, verbosity, dry_run, out)?;
             let session = Session {
                 id: Uuid::new_v4().to_string(),
                 map,
@@ -76,112 +672,3288 @@ fn main() -> anyhow::Result<()> {
                 start_time: Utc::now(),
                 end_time: None,
                 drops: Vec::new(),
+                runs: Vec::new(),
+                version: 0,
+                start_inventory: inventory_snapshot(log),
+                end_inventory: None,
             };
             sessions.push(session.clone());
-            storage::save_sessions(&sessions)?;
-            println!("Session started: {}", session.id);
+            if dry_run {
+                say!(out, "[dry-run] Would start session on map {}", session.map);
+            } else {
+                storage::save_sessions(data_file, &sessions)?;
+                say!(out, "{}", session_started_message(&session, verbosity));
+            }
         }
         Commands::AddDrop {
             name,
             quantity,
             value,
             session,
+            interactive,
+            expect_version,
         } => {
-            let mut sessions = storage::load_sessions()?;
-            let target_id = resolve_session_id(&sessions, session)?;
+            let target_id = resolve_session_id(&storage::load_sessions(data_file)?, session)?;
+            let (name, quantity, value) = match (name, value) {
+                (Some(name), Some(value)) if !interactive => (name, quantity.unwrap_or(1), value),
+                (name, value) => {
+                    let prices = prices::load_cache(&prices::cache_path(data_file));
+                    let prefs = settings::load(&settings::settings_path(data_file));
+                    prompt::prompt_drop(name, quantity, value, |name| {
+                        prefs
+                            .price_override(name)
+                            .or_else(|| prices.as_ref()?.lookup(None, name))
+                    })
+                    .map_err(|e| CliError::Other(e.into()))?
+                }
+            };
             let drop = DropItem {
                 name,
                 quantity,
                 value,
+                added_at: Some(Utc::now()),
             };
+            drop.validate()
+                .map_err(|e| CliError::Other(anyhow::anyhow!(e)))?;
+            // Reload under the lock: the prompt may have waited on the user.
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
             let session = sessions
                 .iter_mut()
                 .find(|s| s.id == target_id)
-                .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
-            session.drops.push(drop);
+                .ok_or(CliError::SessionNotFound)?;
+            check_version(session, expect_version)?;
             let session_id = session.id.clone();
-            storage::save_sessions(&sessions)?;
-            println!("Drop added to session {}", session_id);
+            if dry_run {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                say!(
+                    out,
+                    "[dry-run] Would add {} x{} @ {} to session {}",
+                    drop.name,
+                    drop.quantity,
+                    viewmodel::format_number(
+                        drop.value,
+                        prefs.value_precision,
+                        &prefs.number_locale()
+                    ),
+                    session_id
+                );
+            } else {
+                session.drops.push(drop);
+                session.touch();
+                storage::save_sessions(data_file, &sessions)?;
+                if !verbosity.is_quiet() {
+                    say!(out, "Drop added to session {}", session_id);
+                }
+            }
+        }
+        Commands::AddDrops {
+            file,
+            session,
+            expect_version,
+        } => {
+            let input = if file.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())
+            } else {
+                std::fs::read_to_string(&file)
+            }
+            .map_err(|e| CliError::Other(anyhow::anyhow!("{}: {}", file.display(), e)))?;
+            let drops = prompt::parse_drop_list(&input).map_err(|e| {
+                CliError::Other(anyhow::anyhow!(
+                    "{}: {} (no drops added)",
+                    file.display(),
+                    e
+                ))
+            })?;
+
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
+            let target_id = resolve_session_id(&sessions, session)?;
+            let session = sessions
+                .iter_mut()
+                .find(|s| s.id == target_id)
+                .ok_or(CliError::SessionNotFound)?;
+            check_version(session, expect_version)?;
+            let count = drops.len();
+            let added: f64 = drops.iter().map(|d| d.quantity as f64 * d.value).sum();
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let value =
+                |v: f64| viewmodel::format_number(v, prefs.value_precision, &prefs.number_locale());
+            if dry_run {
+                say!(
+                    out,
+                    "[dry-run] Would add {} drops worth {} to session {} (new total: {})",
+                    count,
+                    value(added),
+                    session.id,
+                    value(session.total_value() + added)
+                );
+            } else {
+                let now = Utc::now();
+                session.drops.extend(drops.into_iter().map(|d| DropItem {
+                    added_at: Some(now),
+                    ..d
+                }));
+                session.touch();
+                let message = format!(
+                    "Added {} drops to session {} (new total: {})",
+                    count,
+                    session.id,
+                    value(session.total_value())
+                );
+                storage::save_sessions(data_file, &sessions)?;
+                if !verbosity.is_quiet() {
+                    say!(out, "{}", message);
+                }
+            }
         }
-        Commands::EndSession { session } => {
-            let mut sessions = storage::load_sessions()?;
+        Commands::EndSession {
+            session,
+            expect_version,
+            log,
+        } => {
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
             let target_id = resolve_session_id(&sessions, session)?;
             let session = sessions
                 .iter_mut()
                 .find(|s| s.id == target_id)
-                .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+                .ok_or(CliError::SessionNotFound)?;
+            check_version(session, expect_version)?;
             let session_id = session.id.clone();
             if session.end_time.is_some() {
-                println!("Session already ended: {}", session_id);
+                if !verbosity.is_quiet() {
+                    say!(out, "Session already ended: {}", session_id);
+                }
+            } else if dry_run {
+                say!(out, "[dry-run] Would end session {}", session_id);
             } else {
                 session.end_time = Some(Utc::now());
-                storage::save_sessions(&sessions)?;
-                println!("Session ended: {}", session_id);
+                session.end_inventory = inventory_snapshot(log);
+                session.touch();
+                let ended = session.clone();
+                storage::save_sessions(data_file, &sessions)?;
+                if !verbosity.is_quiet() {
+                    say!(out, "Session ended: {}", session_id);
+                }
+                let records_path = records::records_path(data_file);
+                let mut best = records::load(&records_path);
+                let broken = best.update(&ended);
+                if !broken.is_empty() {
+                    if let Err(e) = records::save(&records_path, &best) {
+                        warn!(error = %e, "failed to save records");
+                    }
+                    if !verbosity.is_quiet() {
+                        for kind in broken {
+                            say!(out, "New record! {}", kind.label());
+                        }
+                    }
+                }
             }
         }
-        Commands::List => {
-            let sessions = storage::load_sessions()?;
+        Commands::List {
+            map,
+            active,
+            since,
+            until,
+        } => {
+            let filter = SessionFilter {
+                map,
+                active,
+                since,
+                until,
+            };
+            let all = storage::load_sessions(data_file)?;
+            // Numbered over every session so labels don't shift with the filter.
+            let numbers = models::map_sequence_numbers(&all);
+            let sessions: Vec<&Session> = all
+                .iter()
+                .filter(|s| models::session_matches(s, &filter))
+                .collect();
+            if cli.json {
+                say!(out, "{}", list_json(&sessions)?);
+                return Ok(());
+            }
             if sessions.is_empty() {
-                println!("No sessions found.");
+                if !verbosity.is_quiet() {
+                    say!(out, "No sessions found.");
+                }
                 return Ok(());
             }
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let locale = prefs.number_locale();
             for session in sessions {
-                let status = if session.is_active() { "active" } else { "ended" };
-                println!(
-                    "{} | {} | {} | drops: {}",
-                    session.id,
-                    session.map,
-                    status,
-                    session.drops.len()
-                );
+                let name = session.display_name(numbers[session.id.as_str()]);
+                let line = list_line(session, &name, verbosity, prefs.value_precision, &locale);
+                say!(out, "{}", line);
             }
         }
         Commands::Summary { session } => {
-            let sessions = storage::load_sessions()?;
+            let sessions = storage::load_sessions(data_file)?;
             let target_id = resolve_session_id(&sessions, session)?;
             let session = sessions
                 .iter()
                 .find(|s| s.id == target_id)
-                .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+                .ok_or(CliError::SessionNotFound)?;
 
-            println!("Session: {}", session.id);
-            println!("Map: {}", session.map);
-            if let Some(notes) = &session.notes {
-                println!("Notes: {}", notes);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&session.summary())?);
+            } else {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                let text = summary_text(
+                    session,
+                    verbosity,
+                    prefs.value_precision,
+                    &prefs.number_locale(),
+                );
+                write!(out, "{}", text).map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Compare { sessions: ids } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let comparison = stats::compare_sessions(&sessions, &ids, Utc::now());
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&comparison)?);
+            } else {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                write!(
+                    out,
+                    "{}",
+                    stats::comparison_chart(
+                        &comparison.sessions,
+                        prefs.rate_precision,
+                        &prefs.number_locale()
+                    )
+                )
+                .map_err(|e| CliError::Other(e.into()))?;
+                for id in &comparison.missing {
+                    say!(out, "Skipped {}: no such session", id);
+                }
+            }
+        }
+        Commands::Combine { sessions: ids } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let combined = stats::combine_sessions(&sessions, &ids);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&combined)?);
+            } else {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                write!(
+                    out,
+                    "{}",
+                    stats::combined_report(&combined, prefs.rate_precision, &prefs.number_locale())
+                )
+                .map_err(|e| CliError::Other(e.into()))?;
+                for id in &combined.missing {
+                    say!(out, "Skipped {}: no such session", id);
+                }
+            }
+        }
+        Commands::Find { item } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let query = models::ItemQuery::new(&item, log_parser::known_item_name);
+            let primary_name = log_parser::item_name(&prefs.primary_currency);
+            let found =
+                models::find_item(&sessions, &query, (&prefs.primary_currency, &primary_name));
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&found)?);
+                return Ok(());
+            }
+            if found.is_empty() {
+                if !verbosity.is_quiet() {
+                    say!(out, "No sessions contain {}.", item);
+                }
+                return Ok(());
+            }
+            let numbers = models::map_sequence_numbers(&sessions);
+            for m in &found {
+                let name = sessions
+                    .iter()
+                    .find(|s| s.id == m.session_id)
+                    .map(|s| s.display_name(numbers[s.id.as_str()]))
+                    .unwrap_or_default();
+                say!(
+                    out,
+                    "{} ({})  x{}",
+                    name,
+                    m.session_id,
+                    viewmodel::format_count(m.quantity, &prefs.number_locale())
+                );
+            }
+        }
+        Commands::Breakdown { session, top } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let target_id = resolve_session_id(&sessions, session)?;
+            let session = sessions
+                .iter()
+                .find(|s| s.id == target_id)
+                .ok_or(CliError::SessionNotFound)?;
+            let slices = stats::value_breakdown(session, top);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&slices)?);
+            } else if slices.is_empty() {
+                say!(out, "No valued drops in this session.");
+            } else {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                write!(
+                    out,
+                    "{}",
+                    stats::breakdown_chart(&slices, prefs.value_precision, &prefs.number_locale())
+                )
+                .map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::NetChange { session } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let target_id = resolve_session_id(&sessions, session)?;
+            let session = sessions
+                .iter()
+                .find(|s| s.id == target_id)
+                .ok_or(CliError::SessionNotFound)?;
+            let changes = session.inventory_change();
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&changes)?);
+            } else if let Some(changes) = changes {
+                if changes.is_empty() {
+                    say!(out, "No changes.");
+                } else {
+                    let locale =
+                        settings::load(&settings::settings_path(data_file)).number_locale();
+                    write!(out, "{}", inventory::diff_table(&changes, &locale))
+                        .map_err(|e| CliError::Other(e.into()))?;
+                }
+            } else {
+                say!(
+                    out,
+                    "No inventory snapshots for this session (a game log is read when it starts and ends)."
+                );
+            }
+        }
+        Commands::Histogram { map, bucket_size } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let fe = stats::run_fe(&sessions, map.as_deref(), prefs.min_session_secs);
+            let size = bucket_size.unwrap_or(prefs.histogram_bucket);
+            let buckets = stats::histogram(&fe, i64::try_from(size).unwrap_or(i64::MAX));
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&buckets)?);
+            } else if buckets.is_empty() {
+                say!(
+                    out,
+                    "No runs recorded yet. Track a session in the GUI or TUI."
+                );
+            } else {
+                write!(out, "{}", stats::histogram_chart(&buckets))
+                    .map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Stats { by_tier } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let stats = stats::map_stats(&sessions, prefs.min_session_secs, by_tier);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&stats)?);
+            } else if stats.is_empty() {
+                say!(
+                    out,
+                    "No runs recorded yet. Track a session in the GUI or TUI."
+                );
+            } else {
+                write!(
+                    out,
+                    "{}",
+                    stats::stats_table(&stats, prefs.rate_precision, &prefs.number_locale())
+                )
+                .map_err(|e| CliError::Other(e.into()))?;
+            }
+            // Sessions ended by the tracker or imported may not be counted yet.
+            let records_path = records::records_path(data_file);
+            let mut best = records::load(&records_path);
+            if best.update_all(&sessions) {
+                if let Err(e) = records::save(&records_path, &best) {
+                    warn!(error = %e, "failed to save records");
+                }
+            }
+            if !cli.json && !best.is_empty() {
+                say!(out, "");
+                write!(
+                    out,
+                    "{}",
+                    records::records_table(&best, &prefs.number_locale())
+                )
+                .map_err(|e| CliError::Other(e.into()))?;
+            }
+            let goals = prefs.map_goals;
+            if !cli.json && !goals.is_empty() {
+                let today = Local::now().date_naive();
+                let progress =
+                    stats::goal_progress(&sessions, &goals, today, &Local, prefs.min_session_secs);
+                say!(out, "");
+                write!(out, "{}", stats::goals_table(&progress))
+                    .map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Goal { map, set } => {
+            let path = settings::settings_path(data_file);
+            let mut current = settings::load(&path);
+            // Reuse the stored spelling of a map that already has a goal.
+            let key = map.as_deref().map(|m| {
+                current
+                    .map_goals
+                    .keys()
+                    .find(|k| models::same_map(k, m))
+                    .cloned()
+                    .unwrap_or_else(|| models::normalize_map(m))
+            });
+            if let (Some(key), Some(goal)) = (&key, set) {
+                if goal < 0 {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Goal must not be negative (0 removes it)"
+                    )));
+                }
+                if goal == 0 {
+                    current.map_goals.remove(key);
+                } else {
+                    current.map_goals.insert(key.clone(), goal);
+                }
+                if dry_run {
+                    say!(out, "[dry-run] Would save settings to {}", path.display());
+                } else {
+                    settings::save(&path, &current).map_err(anyhow::Error::from)?;
+                }
+            }
+            let mut goals = current.map_goals.clone();
+            if let Some(key) = &key {
+                goals.retain(|k, _| k == key);
+            }
+            let sessions = storage::load_sessions(data_file)?;
+            let today = Local::now().date_naive();
+            let progress =
+                stats::goal_progress(&sessions, &goals, today, &Local, current.min_session_secs);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&progress)?);
+            } else if progress.is_empty() {
+                if !verbosity.is_quiet() {
+                    say!(out, "No goals set. Set one with: goal <MAP> --set <FE>");
+                }
+            } else {
+                write!(out, "{}", stats::goals_table(&progress))
+                    .map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Recommend { min_runs, by_tier } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let stats = stats::map_stats(&sessions, prefs.min_session_secs, by_tier);
+            match stats::recommend(&stats, min_runs) {
+                Some(best) => say!(
+                    out,
+                    "Best map: {} ({} FE/hr over {} runs)",
+                    best.label(),
+                    viewmodel::format_number(
+                        best.fe_per_hour,
+                        prefs.rate_precision,
+                        &prefs.number_locale()
+                    ),
+                    best.runs
+                ),
+                None => say!(
+                    out,
+                    "Not enough data yet: no map has {} recorded runs.",
+                    min_runs
+                ),
+            }
+        }
+        Commands::Export {
+            out: path,
+            with_summary,
+            force,
+        } => {
+            if !force && Path::new(&path).exists() {
+                return Err(CliError::Other(anyhow::anyhow!(
+                    "{} already exists; pass --force to overwrite it",
+                    path
+                )));
             }
-            println!("Drops: {}", session.drops.len());
-            println!("Total value: {:.2}", session.total_value());
-            if let Some(minutes) = session.duration_minutes() {
-                println!("Duration: {:.2} minutes", minutes);
+            let sessions = storage::load_sessions(data_file)?;
+            if with_summary {
+                storage::export_sessions_with_summary(&sessions, path)
+                    .map_err(anyhow::Error::from)?;
+            } else {
+                storage::export_sessions(&sessions, path).map_err(anyhow::Error::from)?;
+            }
+            if !verbosity.is_quiet() {
+                say!(out, "Exported sessions.");
+            }
+        }
+        Commands::Scorecard { session, out: path } => {
+            let sessions = storage::load_sessions(data_file)?;
+            let target_id = resolve_session_id(&sessions, session)?;
+            let session = sessions
+                .iter()
+                .find(|s| s.id == target_id)
+                .ok_or(CliError::SessionNotFound)?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let card = scorecard::Scorecard::from_session(
+                session,
+                &prefs.primary_currency,
+                prefs.value_precision,
+                &prefs.number_locale(),
+                Utc::now(),
+            );
+            if dry_run {
+                say!(out, "[dry-run] Would write scorecard to {}", path.display());
+                return Ok(());
+            }
+            card.save_png(&path).map_err(|e| {
+                CliError::Other(anyhow::anyhow!(
+                    "Failed to write scorecard {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            if !verbosity.is_quiet() {
+                say!(out, "Scorecard written to {}", path.display());
+            }
+        }
+        Commands::Watch { session, interval } => {
+            watch::run(data_file, session, Duration::from_secs(interval.max(1)))?;
+        }
+        Commands::LogEvents { since, log } => {
+            let path = log.or_else(storage::detect_game_log).ok_or_else(|| {
+                CliError::Other(anyhow::anyhow!("UE_game.log not found; pass --log <path>"))
+            })?;
+            let batch = log_parser::read_events_since(&path, since).map_err(anyhow::Error::from)?;
+            say!(out, "{}", serde_json::to_string_pretty(&batch)?);
+        }
+        Commands::Loot { top, log } => {
+            let path = log.or_else(storage::detect_game_log).ok_or_else(|| {
+                CliError::Other(anyhow::anyhow!("UE_game.log not found; pass --log <path>"))
+            })?;
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let loot = log_parser::parse_loot_from_log(&path, &prefs.loot_contexts)
+                .map_err(anyhow::Error::from)?;
+            let score = loot_score(&loot, &prefs, data_file);
+            if cli.json {
+                let items: Vec<_> = viewmodel::visible_items(&loot, &prefs)
+                    .into_iter()
+                    .take(top)
+                    .collect();
+                let mut json = serde_json::to_value(&loot)?;
+                json["items"] = serde_json::to_value(items)?;
+                json["hint"] = loot.hint().into();
+                json["item_db_warning"] = loot.item_db_warning().into();
+                json["score_mode"] = prefs.score_mode.key().into();
+                json["score"] = score.into();
+                say!(out, "{}", serde_json::to_string_pretty(&json)?);
+            } else {
+                let text = loot_table(&loot, &prefs, top, score);
+                write!(out, "{}", text).map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Record {
+            out: path,
+            interval,
+            log,
+        } => {
+            let log = log.or_else(storage::detect_game_log).ok_or_else(|| {
+                CliError::Other(anyhow::anyhow!("UE_game.log not found; pass --log <path>"))
+            })?;
+            if !verbosity.is_quiet() {
+                say!(
+                    out,
+                    "Recording {} to {} (Ctrl-C to stop)",
+                    log.display(),
+                    path.display()
+                );
+            }
+            record::run(&log, &path, Duration::from_secs(interval.max(1)))
+                .map_err(anyhow::Error::from)?;
+        }
+        Commands::Replay { file, top } => {
+            let prefs = settings::load(&settings::settings_path(data_file));
+            let replay =
+                record::replay(&file, &prefs.loot_contexts).map_err(anyhow::Error::from)?;
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&replay)?);
+            } else {
+                let score = loot_score(&replay.loot, &prefs, data_file);
+                say!(
+                    out,
+                    "Replayed {} lines ({} events)",
+                    replay.lines,
+                    replay.events
+                );
+                if !replay.maps.is_empty() {
+                    say!(out, "Maps: {}", replay.maps.join(" -> "));
+                }
+                let text = loot_table(&replay.loot, &prefs, top, score);
+                write!(out, "{}", text).map_err(|e| CliError::Other(e.into()))?;
+            }
+        }
+        Commands::Inventory {
+            out: path,
+            grouped,
+            log,
+        } => {
+            let rows = inventory::rows(&current_inventory(log)?, grouped);
+            let text = if cli.json {
+                serde_json::to_string_pretty(&rows)? + "\n"
+            } else {
+                inventory::to_csv(&rows, grouped)
+            };
+            match path {
+                Some(path) => {
+                    write_output(&path, text, "write inventory to")?;
+                    if !verbosity.is_quiet() {
+                        say!(
+                            out,
+                            "Exported {} inventory rows to {}",
+                            rows.len(),
+                            path.display()
+                        );
+                    }
+                }
+                None => write!(out, "{}", text).map_err(|e| CliError::Other(e.into()))?,
             }
-            if let Some(ppm) = session.profit_per_minute() {
-                println!("Profit/min: {:.2}", ppm);
+        }
+        Commands::InventoryDiff { before, after, log } => {
+            let snapshots = inventory::load_snapshots(&inventory::snapshots_path(data_file))?;
+            let before = inventory::resolve_rows(&before, &snapshots)?;
+            let after = match after {
+                Some(after) => inventory::resolve_rows(&after, &snapshots)?,
+                None => inventory::rows(&current_inventory(log)?, false),
+            };
+            let changes = inventory::diff(&before, &after);
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&changes)?);
+            } else if changes.is_empty() {
+                say!(out, "No changes.");
+            } else {
+                let locale = settings::load(&settings::settings_path(data_file)).number_locale();
+                write!(out, "{}", inventory::diff_table(&changes, &locale))
+                    .map_err(|e| CliError::Other(e.into()))?;
             }
         }
-        Commands::Export { out } => {
-            let sessions = storage::load_sessions()?;
-            storage::export_sessions(&sessions, out)?;
-            println!("Exported sessions.");
+        Commands::Snapshot { name, delete, log } => {
+            let path = inventory::snapshots_path(data_file);
+            let mut snapshots = inventory::load_snapshots(&path)?;
+            let Some(name) = name else {
+                if cli.json {
+                    say!(out, "{}", serde_json::to_string_pretty(&snapshots)?);
+                } else if snapshots.is_empty() {
+                    if !verbosity.is_quiet() {
+                        say!(out, "No snapshots saved. Save one with: snapshot <NAME>");
+                    }
+                } else {
+                    for (name, snapshot) in &snapshots {
+                        say!(
+                            out,
+                            "{:<24} {}  {} slots",
+                            name,
+                            snapshot
+                                .taken_at
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d %H:%M"),
+                            snapshot.rows.len()
+                        );
+                    }
+                }
+                return Ok(());
+            };
+            let name = name.trim().to_string();
+            let verb = if delete {
+                if snapshots.remove(&name).is_none() {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "No snapshot named {:?}",
+                        name
+                    )));
+                }
+                "Deleted"
+            } else {
+                let rows = inventory::rows(&current_inventory(log)?, false);
+                snapshots.insert(
+                    name.clone(),
+                    inventory::Snapshot {
+                        taken_at: Utc::now(),
+                        rows,
+                    },
+                );
+                "Saved"
+            };
+            if dry_run {
+                say!(out, "[dry-run] Would save snapshots to {}", path.display());
+            } else {
+                inventory::save_snapshots(&path, &snapshots)?;
+                if !verbosity.is_quiet() {
+                    say!(out, "{} snapshot {:?}", verb, name);
+                }
+            }
         }
         Commands::Gui => {
             gui::run()?;
         }
-    }
-
-    Ok(())
-}
-
-fn resolve_session_id(sessions: &[Session], requested: Option<String>) -> anyhow::Result<String> {
-    if let Some(id) = requested {
-        return Ok(id);
-    }
-
-    let active = sessions.iter().find(|s| s.is_active());
-    if let Some(session) = active {
-        return Ok(session.id.clone());
-    }
-
-    Err(anyhow::anyhow!(
-        "No active session found. Specify --session <id>."
-    ))
+        Commands::Tui => {
+            tui::run().map_err(anyhow::Error::from)?;
+        }
+        Commands::Demo {
+            runs,
+            fe_per_run,
+            out: path,
+            force,
+            gui,
+        } => {
+            if let Some(path) = path.as_ref().filter(|p| !force && p.exists()) {
+                return Err(CliError::Other(anyhow::anyhow!(
+                    "{} already exists; pass --force to overwrite it",
+                    path.display()
+                )));
+            }
+            // The default location is the demo's own, so it is simply replaced.
+            let path = path.unwrap_or_else(|| data_file.with_file_name("demo").join("UE_game.log"));
+            if dry_run {
+                say!(out, "[dry-run] Would write demo log to {}", path.display());
+                return Ok(());
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| {
+                    CliError::Other(storage::path_context(e, "create directory", dir).into())
+                })?;
+            }
+            write_output(&path, demo::demo_log(runs, fe_per_run), "write demo log to")?;
+            storage::use_game_log(&path);
+            if gui {
+                gui::run()?;
+            } else {
+                let prefs = settings::load(&settings::settings_path(data_file));
+                let loot = log_parser::parse_loot_from_log(&path, &prefs.loot_contexts)
+                    .map_err(anyhow::Error::from)?;
+                let score = loot_score(&loot, &prefs, data_file);
+                say!(
+                    out,
+                    "Wrote a demo log with {} runs to {}\n",
+                    runs,
+                    path.display()
+                );
+                write!(out, "{}", loot_table(&loot, &prefs, 10, score))
+                    .map_err(|e| CliError::Other(e.into()))?;
+                if !verbosity.is_quiet() {
+                    say!(out, "\nRun `tli-tracker demo --gui` to open the GUI on it.");
+                }
+            }
+        }
+        Commands::Settings(args) => {
+            let SettingsArgs {
+                primary_currency,
+                group_digits,
+                abbreviate_numbers,
+                thousands_separator,
+                decimal_separator,
+                number_locale,
+                rate_precision,
+                value_precision,
+                recent_runs,
+                pace_floor,
+                target_per_hour,
+                pace_alert_runs,
+                auto_end_hours,
+                auto_end_idle_mins,
+                histogram_bucket,
+                min_session_secs,
+                log_url,
+                poll_interval_secs,
+                ignore_item,
+                unignore_item,
+                count_ignored,
+                pin_item,
+                unpin_item,
+                item_display,
+                item_alias,
+                remove_item_alias,
+                score_mode,
+                item_weight,
+                remove_item_weight,
+                page_capacity,
+                bag_warn_percent,
+                sort_reminder_mins,
+                loot_context,
+                remove_loot_context,
+                stat_boxes,
+            } = *args;
+            let path = settings::settings_path(data_file);
+            let mut current = settings::load(&path);
+            let changed = primary_currency.is_some()
+                || group_digits.is_some()
+                || abbreviate_numbers.is_some()
+                || thousands_separator.is_some()
+                || decimal_separator.is_some()
+                || number_locale.is_some()
+                || rate_precision.is_some()
+                || value_precision.is_some()
+                || recent_runs.is_some()
+                || pace_floor.is_some()
+                || target_per_hour.is_some()
+                || pace_alert_runs.is_some()
+                || auto_end_hours.is_some()
+                || auto_end_idle_mins.is_some()
+                || histogram_bucket.is_some()
+                || min_session_secs.is_some()
+                || log_url.is_some()
+                || poll_interval_secs.is_some()
+                || !ignore_item.is_empty()
+                || !unignore_item.is_empty()
+                || count_ignored.is_some()
+                || !pin_item.is_empty()
+                || !unpin_item.is_empty()
+                || item_display.is_some()
+                || !item_alias.is_empty()
+                || !remove_item_alias.is_empty()
+                || score_mode.is_some()
+                || !item_weight.is_empty()
+                || !remove_item_weight.is_empty()
+                || !page_capacity.is_empty()
+                || bag_warn_percent.is_some()
+                || sort_reminder_mins.is_some()
+                || !loot_context.is_empty()
+                || !remove_loot_context.is_empty()
+                || stat_boxes.is_some();
+            if let Some(id) = primary_currency {
+                let id = id.trim().to_string();
+                if log_parser::item_name(&id).starts_with("Unknown ") {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Unknown item id {}; expected a ConfigBaseId such as {}",
+                        id,
+                        log_parser::FLAME_ELEMENTIUM_ID
+                    )));
+                }
+                current.primary_currency = id;
+            }
+            // A preset first, so the single-separator flags can adjust it.
+            if let Some(locale) = number_locale {
+                current.set_number_locale(locale);
+            }
+            if let Some(group) = group_digits {
+                current.group_digits = group;
+            }
+            if let Some(abbreviate) = abbreviate_numbers {
+                current.abbreviate_numbers = abbreviate;
+            }
+            if let Some(sep) = thousands_separator {
+                if !settings::is_valid_separator(sep) {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Invalid thousands separator {:?}",
+                        sep
+                    )));
+                }
+                current.thousands_separator = sep;
+            }
+            if let Some(sep) = decimal_separator {
+                if !settings::is_valid_separator(sep) {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Invalid decimal separator {:?}",
+                        sep
+                    )));
+                }
+                current.decimal_separator = Some(sep);
+            }
+            let locale = current.number_locale();
+            if current.thousands_separator == locale.decimal {
+                return Err(CliError::Other(anyhow::anyhow!(
+                    "The thousands and decimal separators must differ (both {:?})",
+                    locale.decimal
+                )));
+            }
+            if let Some(precision) = rate_precision {
+                current.rate_precision = precision as usize;
+            }
+            if let Some(precision) = value_precision {
+                current.value_precision = precision as usize;
+            }
+            if let Some(n) = recent_runs {
+                current.recent_runs = n as usize;
+            }
+            if let Some(floor) = pace_floor {
+                if !floor.is_finite() || floor < 0.0 {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Invalid pace floor {}; expected a rate per hour, or 0 to turn the alert off",
+                        floor
+                    )));
+                }
+                current.pace_floor = (floor > 0.0).then_some(floor);
+            }
+            if let Some(target) = target_per_hour {
+                if !target.is_finite() || target < 0.0 {
+                    return Err(CliError::Other(anyhow::anyhow!(
+                        "Invalid target {}; expected a rate per hour, or 0 to turn it off",
+                        target
+                    )));
+                }
+                current.target_per_hour = (target > 0.0).then_some(target);
+            }
+            if let Some(n) = pace_alert_runs {
+                current.pace_alert_runs = n as usize;
+            }
+            if let Some(hours) = auto_end_hours {
+                current.auto_end_hours = hours;
+            }
+            if let Some(mins) = auto_end_idle_mins {
+                current.auto_end_idle_mins = mins;
+            }
+            if let Some(size) = histogram_bucket {
+                current.histogram_bucket = size;
+            }
+            if let Some(secs) = min_session_secs {
+                current.min_session_secs = secs;
+            }
+            if let Some(url) = log_url {
+                current.log_url = settings::parse_log_url(&url)
+                    .map_err(|e| CliError::Other(anyhow::anyhow!(e)))?;
+            }
+            if let Some(secs) = poll_interval_secs {
+                current.poll_interval_secs = secs;
+            }
+            for id in ignore_item {
+                current.ignored_items.insert(id.trim().to_string());
+            }
+            for id in unignore_item {
+                current.ignored_items.remove(id.trim());
+            }
+            if let Some(count) = count_ignored {
+                current.count_ignored = count;
+            }
+            for id in pin_item {
+                current.pinned_items.insert(id.trim().to_string());
+            }
+            for id in unpin_item {
+                current.pinned_items.remove(id.trim());
+            }
+            if let Some(mode) = item_display {
+                current.item_display = mode;
+            }
+            for (id, alias) in item_alias {
+                current.item_aliases.insert(id, alias);
+            }
+            for id in remove_item_alias {
+                current.item_aliases.remove(id.trim());
+            }
+            if let Some(mode) = score_mode {
+                current.score_mode = mode;
+            }
+            for (id, weight) in item_weight {
+                current.item_weights.insert(id, weight);
+            }
+            for id in remove_item_weight {
+                current.item_weights.remove(id.trim());
+            }
+            for (page, slots) in page_capacity {
+                if slots == 0 {
+                    current.page_capacities.remove(&page);
+                } else {
+                    current.page_capacities.insert(page, slots);
+                }
+            }
+            if let Some(percent) = bag_warn_percent {
+                current.bag_warn_percent = percent;
+            }
+            if let Some(mins) = sort_reminder_mins {
+                current.sort_reminder_mins = mins;
+            }
+            for name in loot_context {
+                let name = name.trim().to_string();
+                if !current.loot_contexts.contains(&name) {
+                    current.loot_contexts.push(name);
+                }
+            }
+            for name in remove_loot_context {
+                current.loot_contexts.retain(|c| c != name.trim());
+            }
+            if let Some(boxes) = stat_boxes {
+                current.stat_boxes = boxes;
+            }
+            if changed {
+                if dry_run {
+                    say!(out, "[dry-run] Would save settings to {}", path.display());
+                } else {
+                    settings::save(&path, &current).map_err(anyhow::Error::from)?;
+                }
+            }
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&current)?);
+            } else if !verbosity.is_quiet() {
+                for (name, value) in current.entries() {
+                    say!(out, "{} = {}", name, value);
+                }
+            }
+        }
+        Commands::Fix => {
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
+            let auto_ended = auto_end_sessions(&mut sessions, data_file, verbosity, dry_run, out)?;
+            let ended = models::end_extra_active(&mut sessions, Utc::now());
+            if ended.is_empty() {
+                if !auto_ended.is_empty() {
+                    if !dry_run {
+                        storage::save_sessions(data_file, &sessions)?;
+                    }
+                } else if !verbosity.is_quiet() {
+                    say!(out, "Nothing to fix: at most one session is active.");
+                }
+            } else if dry_run {
+                for id in &ended {
+                    say!(out, "[dry-run] Would end session {}", id);
+                }
+            } else {
+                storage::save_sessions(data_file, &sessions)?;
+                for id in &ended {
+                    if verbosity.is_quiet() {
+                        say!(out, "{}", id);
+                    } else {
+                        say!(out, "Session ended: {}", id);
+                    }
+                }
+            }
+        }
+        Commands::Prune { older_than_days } => {
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
+            let now = Utc::now();
+            let ids = models::prune_ids(&sessions, older_than_days, now);
+            if dry_run {
+                for id in &ids {
+                    say!(out, "[dry-run] Would delete session {}", id);
+                }
+                say!(
+                    out,
+                    "[dry-run] Would delete {} of {} sessions",
+                    ids.len(),
+                    sessions.len()
+                );
+            } else if ids.is_empty() {
+                if !verbosity.is_quiet() {
+                    say!(
+                        out,
+                        "Nothing to prune: no session ended more than {} days ago.",
+                        older_than_days
+                    );
+                }
+            } else {
+                let backup = storage::backup_sessions(data_file, now)?;
+                sessions.retain(|s| !ids.contains(&s.id));
+                storage::save_sessions(data_file, &sessions)?;
+                if verbosity.is_quiet() {
+                    for id in &ids {
+                        say!(out, "{}", id);
+                    }
+                } else {
+                    say!(
+                        out,
+                        "Deleted {} sessions, kept {}. Backup: {}",
+                        ids.len(),
+                        sessions.len(),
+                        backup.display()
+                    );
+                }
+            }
+        }
+        Commands::UpdatePrices {
+            url,
+            ttl_minutes,
+            force,
+        } => {
+            let path = prices::cache_path(data_file);
+            if dry_run {
+                say!(
+                    out,
+                    "[dry-run] Would fetch prices from {} into {}",
+                    url,
+                    path.display()
+                );
+                return Ok(());
+            }
+            let source = prices::HttpSource { url };
+            let ttl =
+                chrono::Duration::try_minutes(ttl_minutes.max(0)).unwrap_or(chrono::Duration::MAX);
+            let update = prices::update(&source, &path, ttl, force, Utc::now())?;
+            if !verbosity.is_quiet() {
+                say!(out, "{}", price_update_message(&update));
+            }
+        }
+        Commands::Profiles => {
+            let root = storage::store_root(data_file, cli.profile.as_deref());
+            let profiles = storage::list_profiles(&root).map_err(anyhow::Error::from)?;
+            if cli.json {
+                say!(out, "{}", serde_json::to_string_pretty(&profiles)?);
+            } else if verbosity.is_quiet() {
+                for name in &profiles {
+                    say!(out, "{}", name);
+                }
+            } else {
+                let current = cli.profile.as_deref();
+                let marker = |active: bool| if active { "*" } else { " " };
+                say!(out, "{} (default)", marker(current.is_none()));
+                for name in &profiles {
+                    say!(out, "{} {}", marker(current == Some(name.as_str())), name);
+                }
+            }
+        }
+        Commands::Migrate => {
+            let _lock = lock_for_write(data_file, dry_run)?;
+            let mut sessions = storage::load_sessions(data_file)?;
+            let changed = models::rename_drops(&mut sessions, log_parser::resolve_unknown_name);
+            if dry_run {
+                say!(out, "[dry-run] Would rename {} drops", changed);
+            } else {
+                if changed > 0 {
+                    storage::save_sessions(data_file, &sessions)?;
+                }
+                if !verbosity.is_quiet() {
+                    say!(out, "Renamed {} drops", changed);
+                }
+            }
+        }
+        Commands::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "tli-tracker", out);
+        }
+        Commands::Man { out: path } => {
+            let page = man_page().map_err(anyhow::Error::from)?;
+            match path {
+                Some(path) => {
+                    write_output(&path, page, "write man page to")?;
+                    if !verbosity.is_quiet() {
+                        say!(out, "Man page written to {}", path.display());
+                    }
+                }
+                None => out
+                    .write_all(&page)
+                    .map_err(|e| CliError::Other(e.into()))?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Route diagnostics to stderr, filtered by `RUST_LOG` or else by `-v`/`-q`.
+fn init_tracing(verbosity: Verbosity) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity.default_filter()));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// Render the `tli-tracker(1)` man page, including every subcommand.
+fn man_page() -> std::io::Result<Vec<u8>> {
+    let mut page = Vec::new();
+    clap_mangen::Man::new(Cli::command()).render(&mut page)?;
+    Ok(page)
+}
+
+/// Output for `StartSession`; quiet mode prints only the id for scripting.
+fn session_started_message(session: &Session, verbosity: Verbosity) -> String {
+    match verbosity {
+        Verbosity::Quiet => session.id.clone(),
+        Verbosity::Normal => format!("Session started: {}", session.id),
+        Verbosity::Verbose(_) => format!(
+            "Session started: {} (map: {}, at {})",
+            session.id,
+            session.map,
+            session.start_time.to_rfc3339()
+        ),
+    }
+}
+
+/// One `List` row for a session, labelled `name` (see `Session::display_name`).
+fn list_line(
+    session: &Session,
+    name: &str,
+    verbosity: Verbosity,
+    precision: usize,
+    locale: &NumberLocale,
+) -> String {
+    let status = if session.is_active() { "active" } else { "ended" };
+    match verbosity {
+        Verbosity::Quiet => session.id.clone(),
+        Verbosity::Normal => format!(
+            "{} | {} | {} | drops: {}",
+            session.id,
+            name,
+            status,
+            session.drops.len()
+        ),
+        Verbosity::Verbose(_) => format!(
+            "{} | {} | {} | drops: {} | value: {} | started: {}",
+            session.id,
+            name,
+            status,
+            session.drops.len(),
+            viewmodel::format_number(session.total_value(), precision, locale),
+            session.start_time.to_rfc3339()
+        ),
+    }
+}
+
+/// `List --json` output: every session with its computed fields.
+fn list_json(sessions: &[&Session]) -> serde_json::Result<String> {
+    let summaries: Vec<_> = sessions.iter().map(|s| s.summary()).collect();
+    serde_json::to_string_pretty(&summaries)
+}
+
+/// `Summary` text for a session, one field per line.
+fn summary_text(
+    session: &Session,
+    verbosity: Verbosity,
+    precision: usize,
+    locale: &NumberLocale,
+) -> String {
+    let value = |v: f64| viewmodel::format_number(v, precision, locale);
+    let mut out = String::new();
+    if !verbosity.is_quiet() {
+        out.push_str(&format!("Session: {}\n", session.id));
+        out.push_str(&format!("Map: {}\n", session.map));
+        if let Some(notes) = &session.notes {
+            out.push_str(&format!("Notes: {}\n", notes));
+        }
+    }
+    if verbosity.is_verbose() {
+        out.push_str(&format!("Started: {}\n", session.start_time.to_rfc3339()));
+        if let Some(end) = session.end_time {
+            out.push_str(&format!("Ended: {}\n", end.to_rfc3339()));
+        }
+        out.push_str(&format!("Version: {}\n", session.version));
+    }
+    out.push_str(&format!("Drops: {}\n", session.drops.len()));
+    if verbosity.is_verbose() {
+        for drop in &session.drops {
+            out.push_str(&format!(
+                "  {} x{} @ {}\n",
+                drop.name,
+                drop.quantity,
+                value(drop.value)
+            ));
+        }
+    }
+    out.push_str(&format!("Total value: {}\n", value(session.total_value())));
+    if let Some(minutes) = session.duration_minutes() {
+        out.push_str(&format!("Duration: {} minutes\n", value(minutes)));
+    }
+    if let Some(ppm) = session.profit_per_minute() {
+        out.push_str(&format!("Profit/min: {}\n", value(ppm)));
+    }
+    out
+}
+
+/// `Loot` output: the `top` visible item deltas as a table (ignored items
+/// left out, pinned first, as on the Items tab), the primary currency
+/// total, the event count, the hint when nothing can be measured yet, and
+/// a warning when most item ids are unknown to the item database.
+fn loot_table(
+    loot: &log_parser::LootSummary,
+    prefs: &settings::Settings,
+    top: usize,
+    score: f64,
+) -> String {
+    let separator = &prefs.number_locale();
+    let items = viewmodel::visible_items(loot, prefs);
+    let mut out = String::new();
+    if !items.is_empty() {
+        out.push_str(&format!(
+            "{} {:>10} {:>10}\n",
+            prefs
+                .item_display
+                .cells(format!("{:<28}", "Item"), format!("{:>8}", "ID"))
+                .join(" "),
+            "Delta",
+            "Now"
+        ));
+        for item in items.iter().take(top) {
+            out.push_str(&format!(
+                "{} {:>10} {:>10}\n",
+                prefs
+                    .item_display
+                    .cells(
+                        format!(
+                            "{:<28}",
+                            log_parser::display_name(&item.config_base_id, &prefs.item_aliases)
+                        ),
+                        format!("{:>8}", item.config_base_id)
+                    )
+                    .join(" "),
+                viewmodel::format_delta(item.delta, separator),
+                viewmodel::format_count(item.current as i64, separator)
+            ));
+        }
+        if items.len() > top {
+            out.push_str(&format!(
+                "... {} more (use --top to show more)\n",
+                items.len() - top
+            ));
+        }
+    }
+    out.push_str(&format!(
+        "{} total: {}\n",
+        log_parser::currency_label(&prefs.primary_currency),
+        viewmodel::format_delta(loot.primary_delta(&prefs.primary_currency), separator)
+    ));
+    out.push_str(&format!("{}\n", viewmodel::score_label(score, prefs)));
+    out.push_str(&format!("Loot events: {}\n", loot.total_events));
+    if let Some(hint) = loot.hint() {
+        out.push_str(&format!("{}\n", hint));
+    }
+    if let Some(warning) = loot.item_db_warning() {
+        out.push_str(&format!("Warning: {}\n", warning));
+    }
+    out
+}
+
+/// Prices for the items in `loot`: hand-set ones, else the price cache's.
+fn loot_prices(
+    loot: &log_parser::LootSummary,
+    prefs: &settings::Settings,
+    data_file: &Path,
+) -> BTreeMap<String, f64> {
+    let mut prices = prefs.item_prices.clone();
+    if let Some(cache) = prices::load_cache(&prices::cache_path(data_file)) {
+        for item in &loot.items {
+            if let Some(price) = cache.lookup(Some(&item.config_base_id), &item.item_name) {
+                prices.entry(item.config_base_id.clone()).or_insert(price);
+            }
+        }
+    }
+    prices
+}
+
+/// Score of `loot` under the settings, valued with [`loot_prices`].
+fn loot_score(loot: &log_parser::LootSummary, prefs: &settings::Settings, data_file: &Path) -> f64 {
+    let prices = loot_prices(loot, prefs, data_file);
+    scoring::score(
+        loot,
+        &scoring::ScoringConfig {
+            prices: &prices,
+            ..prefs.scoring()
+        },
+    )
+}
+
+/// End stale active sessions per the auto-end settings, reporting each one.
+/// The caller saves `sessions`; returns the ids that were ended.
+fn auto_end_sessions(
+    sessions: &mut [Session],
+    data_file: &Path,
+    verbosity: Verbosity,
+    dry_run: bool,
+    out: &mut dyn Write,
+) -> Result<Vec<String>, CliError> {
+    let prefs = settings::load(&settings::settings_path(data_file));
+    let Some((max_age, max_idle)) = prefs.auto_end() else {
+        return Ok(Vec::new());
+    };
+    let ended = models::auto_end_stale(sessions, max_age, max_idle, Utc::now());
+    for id in &ended {
+        let Some(session) = sessions.iter().find(|s| &s.id == id) else {
+            continue;
+        };
+        let end = session
+            .end_time
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        if dry_run {
+            say!(out, "[dry-run] Would auto-end session {} at {}", id, end);
+        } else if !verbosity.is_quiet() {
+            say!(
+                out,
+                "Auto-ended session {} at its last activity ({})",
+                id,
+                end
+            );
+        }
+    }
+    Ok(ended)
+}
+
+/// The current inventory from `log`, or from the detected game log.
+fn current_inventory(log: Option<PathBuf>) -> Result<Vec<log_parser::BagEvent>, CliError> {
+    let log = log.or_else(storage::detect_game_log).ok_or_else(|| {
+        CliError::Other(anyhow::anyhow!("UE_game.log not found; pass --log <path>"))
+    })?;
+    Ok(log_parser::parse_inventory_from_log(&log).map_err(anyhow::Error::from)?)
+}
+
+/// Write a file the user asked for. Failures name the file and are not
+/// storage errors: the sessions file is never touched.
+fn write_output(path: &Path, contents: impl AsRef<[u8]>, action: &str) -> Result<(), CliError> {
+    std::fs::write(path, contents)
+        .map_err(|e| CliError::Other(storage::path_context(e, action, path).into()))
+}
+
+/// The inventory to store with a session as it starts or ends. Best effort:
+/// without a readable log the session is saved without one.
+fn inventory_snapshot(log: Option<PathBuf>) -> Option<Vec<inventory::InventoryRow>> {
+    let log = log.or_else(storage::detect_game_log)?;
+    match log_parser::parse_inventory_from_log(&log) {
+        Ok(items) => inventory::session_snapshot(&items),
+        Err(e) => {
+            warn!(error = %e, "failed to snapshot inventory");
+            None
+        }
+    }
+}
+
+/// One-line report for `UpdatePrices`.
+fn price_update_message(update: &prices::PriceUpdate) -> String {
+    let cache = update.cache();
+    let age = cache.fetched_at.to_rfc3339();
+    match update {
+        prices::PriceUpdate::Cached(_) => format!(
+            "Prices are up to date ({} items, fetched {}). Use --force to refetch.",
+            cache.prices.len(),
+            age
+        ),
+        prices::PriceUpdate::Fetched(_) => {
+            format!(
+                "Fetched {} prices from {}",
+                cache.prices.len(),
+                cache.source
+            )
+        }
+        prices::PriceUpdate::Fallback(_, e) => format!(
+            "Could not fetch prices ({:#}); using {} cached prices from {}",
+            e,
+            cache.prices.len(),
+            age
+        ),
+    }
+}
+
+/// Refuse to change a session that moved past the version the caller saw.
+fn check_version(session: &Session, expected: Option<u64>) -> Result<(), CliError> {
+    if session.version_matches(expected) {
+        Ok(())
+    } else {
+        Err(CliError::Conflict {
+            expected: expected.unwrap_or_default(),
+            actual: session.version,
+        })
+    }
+}
+
+/// Take the sessions lock for a load-modify-save cycle. Dry runs never
+/// write, so they skip it and keep working in read-only directories.
+fn lock_for_write(
+    data_file: &Path,
+    dry_run: bool,
+) -> std::io::Result<Option<storage::SessionsLock>> {
+    if dry_run {
+        return Ok(None);
+    }
+    storage::lock_sessions(data_file).map(Some)
+}
+
+fn resolve_session_id(sessions: &[Session], requested: Option<String>) -> Result<String, CliError> {
+    if let Some(id) = requested {
+        return Ok(id);
+    }
+
+    if let Some(warning) = models::multiple_active_warning(sessions) {
+        warn!("{}", warning);
+    }
+    let active = sessions.iter().find(|s| s.is_active());
+    if let Some(session) = active {
+        return Ok(session.id.clone());
+    }
+
+    Err(CliError::NoActiveSession)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_session() -> Session {
+        Session {
+            id: "0b6f5c1e-1111-2222-3333-444455556666".to_string(),
+            map: "Netherrealm".to_string(),
+            notes: Some("Test run".to_string()),
+            start_time: Utc::now(),
+            end_time: None,
+            drops: vec![DropItem {
+                name: "Flame Core".to_string(),
+                quantity: 2,
+                value: 18.5,
+                added_at: None,
+            }],
+            runs: Vec::new(),
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    /// A tracked run on `map` with no item count or timestamps.
+    fn run_record(map: &str, duration_secs: f64, fe: i64) -> models::RunRecord {
+        models::RunRecord {
+            map: map.to_string(),
+            duration_secs,
+            fe,
+            items: 0,
+            started_at: None,
+            ended_at: None,
+            tier: None,
+        }
+    }
+
+    #[test]
+    fn test_quiet_flag_parses_to_quiet_verbosity() {
+        let cli =
+            Cli::try_parse_from(["tli-tracker", "-q", "start-session", "--map", "X"]).unwrap();
+        assert_eq!(
+            Verbosity::from_flags(cli.verbose, cli.quiet),
+            Verbosity::Quiet
+        );
+
+        let cli = Cli::try_parse_from(["tli-tracker", "list", "-vv"]).unwrap();
+        assert_eq!(
+            Verbosity::from_flags(cli.verbose, cli.quiet),
+            Verbosity::Verbose(2)
+        );
+    }
+
+    #[test]
+    fn test_quiet_and_verbose_conflict() {
+        assert!(Cli::try_parse_from(["tli-tracker", "-q", "-v", "list"]).is_err());
+    }
+
+    #[test]
+    fn test_quiet_start_session_prints_only_id() {
+        let session = sample_session();
+        assert_eq!(
+            session_started_message(&session, Verbosity::Quiet),
+            "0b6f5c1e-1111-2222-3333-444455556666"
+        );
+        assert_eq!(
+            session_started_message(&session, Verbosity::Normal),
+            "Session started: 0b6f5c1e-1111-2222-3333-444455556666"
+        );
+    }
+
+    #[test]
+    fn test_list_json_has_expected_keys() {
+        let session = sample_session();
+        let value: serde_json::Value =
+            serde_json::from_str(&list_json(&[&session]).unwrap()).unwrap();
+        let first = &value.as_array().unwrap()[0];
+        for key in [
+            "id",
+            "map",
+            "notes",
+            "start_time",
+            "end_time",
+            "drops",
+            "active",
+            "total_value",
+            "duration_minutes",
+            "profit_per_minute",
+        ] {
+            assert!(first.get(key).is_some(), "missing key {key}");
+        }
+        assert_eq!(first["total_value"], 37.0);
+        assert_eq!(first["active"], true);
+    }
+
+    #[test]
+    fn test_list_json_empty_is_array() {
+        assert_eq!(list_json(&[]).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_list_shows_display_names() {
+        let (_dir, data_file) = temp_data_file();
+        let mut first = sample_session();
+        first.id = "first".to_string();
+        first.start_time -= chrono::Duration::hours(2);
+        first.end_time = Some(first.start_time + chrono::Duration::hours(1));
+        let mut other = sample_session();
+        other.id = "other".to_string();
+        other.map = "Abyss".to_string();
+        other.end_time = Some(other.start_time);
+        storage::save_sessions(&data_file, &[sample_session(), first, other]).unwrap();
+
+        let (result, output) = run_args(&["list"], &data_file);
+        result.unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].contains(" | Netherrealm #2 · "), "{output}");
+        assert!(
+            lines[1].starts_with("first | Netherrealm #1 · "),
+            "{output}"
+        );
+        assert!(lines[2].starts_with("other | Abyss #1 · "), "{output}");
+
+        // Filtering keeps the numbers the whole set gives.
+        let (result, output) = run_args(&["list", "--active", "true"], &data_file);
+        result.unwrap();
+        assert!(output.contains("Netherrealm #2"), "{output}");
+    }
+
+    #[test]
+    fn test_summary_json_has_computed_fields() {
+        let mut session = sample_session();
+        session.end_time = Some(session.start_time + chrono::Duration::minutes(10));
+        let value = serde_json::to_value(session.summary()).unwrap();
+        assert_eq!(value["id"], "0b6f5c1e-1111-2222-3333-444455556666");
+        assert_eq!(value["active"], false);
+        assert_eq!(value["duration_minutes"], 10.0);
+        assert_eq!(value["profit_per_minute"], 3.7);
+        assert_eq!(value["drops"][0]["name"], "Flame Core");
+    }
+
+    #[test]
+    fn test_no_active_session_exit_code() {
+        let mut ended = sample_session();
+        ended.end_time = Some(Utc::now());
+        let err = resolve_session_id(&[ended], None).unwrap_err();
+        assert!(matches!(err, CliError::NoActiveSession));
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!(
+            err.to_string(),
+            "No active session found. Specify --session <id>."
+        );
+    }
+
+    #[test]
+    fn test_session_not_found_exit_code() {
+        let sessions = vec![sample_session()];
+        let target = resolve_session_id(&sessions, Some("missing".to_string())).unwrap();
+        let err = sessions
+            .iter()
+            .find(|s| s.id == target)
+            .ok_or(CliError::SessionNotFound)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 3);
+        assert_eq!(err.to_string(), "Session not found");
+    }
+
+    #[test]
+    fn test_storage_error_exit_code() {
+        let err: CliError =
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied").into();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.to_string(), "denied");
+    }
+
+    #[test]
+    fn test_other_error_exit_code() {
+        let err: CliError = anyhow::anyhow!("GUI error: boom").into();
+        assert_eq!(err.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_missing_log_is_not_a_storage_error() {
+        let (_dir, data_file) = temp_data_file();
+        for args in [
+            ["loot", "--log", "/nonexistent/UE_game.log"],
+            ["inventory", "--log", "/nonexistent/x.log"],
+        ] {
+            let (result, _) = run_args(&args, &data_file);
+            assert_eq!(result.unwrap_err().exit_code(), 1, "{args:?}");
+        }
+    }
+
+    /// A scratch data dir (removed on drop) and the sessions file inside it.
+    fn temp_data_file() -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let data_file = dir.path().join("sessions.json");
+        (dir, data_file)
+    }
+
+    fn run_args(args: &[&str], data_file: &Path) -> (Result<(), CliError>, String) {
+        let cli = Cli::try_parse_from(std::iter::once("tli-tracker").chain(args.iter().copied()))
+            .unwrap();
+        let mut out = Vec::new();
+        let result = run(cli, data_file, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_start_session_normalizes_map() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, _) = run_args(
+            &["start-session", "--map", "  Glacial   Abyss "],
+            &data_file,
+        );
+        result.unwrap();
+        let sessions = storage::load_sessions(&data_file).unwrap();
+        assert_eq!(sessions[0].map, "Glacial Abyss");
+
+        let (result, _) = run_args(&["start-session", "--map", "  "], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_export_with_summary() {
+        let (dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.end_time = Some(session.start_time + chrono::Duration::minutes(10));
+        session.runs = vec![models::RunRecord {
+            items: 4,
+            ..run_record("Netherrealm", 600.0, 250)
+        }];
+        storage::save_sessions(&data_file, &[session.clone()]).unwrap();
+        let export = dir.path().join("export.json");
+        let path = export.to_str().unwrap();
+
+        let (result, _) = run_args(&["export", "--out", path, "--with-summary"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+        let row = &json[0];
+        assert_eq!(row["id"], session.id);
+        assert_eq!(row["map"], "Netherrealm");
+        assert_eq!(row["drops"][0]["name"], "Flame Core");
+        assert_eq!(row["total_value"], 37.0);
+        assert_eq!(row["duration_minutes"], 10.0);
+        assert_eq!(row["profit_per_minute"], 3.7);
+        assert_eq!(row["fe"], 250);
+
+        // Without the flag the export is the raw sessions.
+        let (result, _) = run_args(&["export", "--out", path, "--force"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+        assert_eq!(json[0]["id"], session.id);
+        assert!(json[0].get("total_value").is_none());
+    }
+
+    #[test]
+    fn test_export_refuses_to_overwrite_without_force() {
+        let (dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        let export = dir.path().join("export.json");
+        let path = export.to_str().unwrap();
+        std::fs::write(&export, "previous export").unwrap();
+
+        let (result, _) = run_args(&["export", "--out", path], &data_file);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        assert!(err.to_string().contains("--force"), "{err}");
+        assert_eq!(std::fs::read_to_string(&export).unwrap(), "previous export");
+
+        let (result, _) = run_args(&["export", "--out", path, "--force"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+        assert_eq!(json[0]["map"], "Netherrealm");
+    }
+
+    #[test]
+    fn test_bash_completions_list_subcommands() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["completions", "bash"], &data_file);
+        assert!(result.is_ok());
+        assert!(!output.is_empty());
+        for name in [
+            "start-session",
+            "add-drop",
+            "end-session",
+            "list",
+            "summary",
+            "export",
+        ] {
+            assert!(output.contains(name), "missing {name}");
+        }
+    }
+
+    #[test]
+    fn test_man_page_lists_subcommands() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["man"], &data_file);
+        assert!(result.is_ok());
+        assert!(output.contains(".TH tli-tracker"));
+        for name in ["start\\-session", "add\\-drop", "summary", "completions"] {
+            assert!(output.contains(name), "missing {name}");
+        }
+        assert!(output.contains("Start a new farming session"));
+    }
+
+    #[test]
+    fn test_dry_run_end_session_leaves_file_unchanged() {
+        let (_dir, data_file) = temp_data_file();
+        let session = sample_session();
+        storage::save_sessions(&data_file, std::slice::from_ref(&session)).unwrap();
+        let before = std::fs::read(&data_file).unwrap();
+
+        let (result, output) = run_args(&["--dry-run", "end-session"], &data_file);
+        result.unwrap();
+
+        assert_eq!(std::fs::read(&data_file).unwrap(), before);
+        assert_eq!(
+            output,
+            format!("[dry-run] Would end session {}\n", session.id)
+        );
+        assert!(storage::load_sessions(&data_file).unwrap()[0].is_active());
+    }
+
+    #[test]
+    fn test_dry_run_add_drop_previews_drop() {
+        let (_dir, data_file) = temp_data_file();
+        let session = sample_session();
+        storage::save_sessions(&data_file, std::slice::from_ref(&session)).unwrap();
+        let before = std::fs::read(&data_file).unwrap();
+
+        let (result, output) = run_args(
+            &["add-drop", "--name", "Ember", "--value", "3", "--dry-run"],
+            &data_file,
+        );
+        result.unwrap();
+
+        assert_eq!(std::fs::read(&data_file).unwrap(), before);
+        assert_eq!(
+            output,
+            format!(
+                "[dry-run] Would add Ember x1 @ 3.00 to session {}\n",
+                session.id
+            )
+        );
+    }
+
+    #[test]
+    fn test_add_drop_rejects_invalid_drops() {
+        let (_dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        let before = std::fs::read(&data_file).unwrap();
+
+        for args in [
+            ["--value", "NaN", "--quantity", "1"],
+            ["--value", "inf", "--quantity", "1"],
+            ["--value", "-2", "--quantity", "1"],
+            ["--value", "2", "--quantity", "0"],
+        ] {
+            let mut argv = vec!["add-drop", "--name", "Ember"];
+            argv.extend(args);
+            let (result, _) = run_args(&argv, &data_file);
+            assert_eq!(result.unwrap_err().exit_code(), 1, "{args:?}");
+        }
+        assert_eq!(std::fs::read(&data_file).unwrap(), before);
+
+        let (result, _) = run_args(&["add-drop", "--name", "Ember", "--value", "2"], &data_file);
+        result.unwrap();
+        assert_eq!(
+            storage::load_sessions(&data_file).unwrap()[0].drops.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_concurrent_add_drops_all_survive() {
+        let (_dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+
+        std::thread::scope(|scope| {
+            for name in ["Ember", "Glass"] {
+                let data_file = &data_file;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        let (result, _) =
+                            run_args(&["add-drop", "--name", name, "--value", "1"], data_file);
+                        result.unwrap();
+                    }
+                });
+            }
+        });
+
+        let drops = &storage::load_sessions(&data_file).unwrap()[0].drops;
+        assert_eq!(drops.len(), 21);
+        assert_eq!(drops.iter().filter(|d| d.name == "Ember").count(), 10);
+    }
+
+    #[test]
+    fn test_loot_table() {
+        let item = |id: &str, delta: i64, current: u32| log_parser::ItemDelta {
+            config_base_id: id.to_string(),
+            item_name: log_parser::item_name(id),
+            delta,
+            current,
+        };
+        let loot = log_parser::LootSummary {
+            items: vec![
+                item("100300", 1250, 4800),
+                item("6002", -3, 0),
+                item("200100", 2, 2),
+                item("5028", 1, 7),
+            ],
+            total_events: 9,
+            baseline_found: true,
+            events_seen: true,
+            last_sort_at: None,
+            sorts_seen: 1,
+            anomalies: Default::default(),
+            game_start: None,
+        };
+        let mut prefs = settings::Settings::default();
+        prefs.ignored_items.insert("200100".to_string());
+
+        let score = scoring::score(&loot, &prefs.scoring());
+        let table = loot_table(&loot, &prefs, 2, score);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("Item"));
+        assert!(lines[1].starts_with("Flame Elementium"));
+        assert!(lines[1].contains(" 100300 "));
+        assert!(lines[1].contains("+1,250"));
+        assert!(lines[1].ends_with("4,800"));
+        assert!(lines[2].contains("-3"));
+        assert_eq!(lines[3], "... 1 more (use --top to show more)");
+        assert_eq!(lines[4], "FE total: +1,250");
+        assert_eq!(lines[5], "Items: 1,250");
+        assert_eq!(lines[6], "Loot events: 9");
+        assert_eq!(lines.len(), 7);
+
+        prefs
+            .item_aliases
+            .insert("6002".to_string(), "glacial key".to_string());
+        let table = loot_table(&loot, &prefs, 2, score);
+        assert!(table.lines().nth(2).unwrap().starts_with("glacial key "));
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("Flame Elementium"));
+
+        prefs.item_display = viewmodel::ItemDisplay::Name;
+        let table = loot_table(&loot, &prefs, 2, score);
+        assert!(!table.contains("ID"));
+        assert!(!table.contains("100300"));
+        assert!(table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("Flame Elementium"));
+
+        prefs.item_display = viewmodel::ItemDisplay::Id;
+        let table = loot_table(&loot, &prefs, 2, score);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].trim_start().starts_with("ID"));
+        assert!(lines[1].trim_start().starts_with("100300"));
+        assert!(!table.contains("Flame Elementium"));
+    }
+
+    #[test]
+    fn test_demo_writes_log_and_shows_loot() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("demo.log");
+        let (result, output) = run_args(
+            &[
+                "demo",
+                "--runs",
+                "3",
+                "--fe-per-run",
+                "200",
+                "--out",
+                log.to_str().unwrap(),
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.starts_with("Wrote a demo log with 3 runs"));
+        assert!(output.contains("Flame Elementium"));
+        assert!(output.contains("+600"));
+
+        let (result, output) = run_args(
+            &["--json", "loot", "--log", log.to_str().unwrap()],
+            &data_file,
+        );
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["baseline_found"], true);
+        assert_eq!(json["total_events"], 12);
+    }
+
+    #[test]
+    fn test_demo_honours_dry_run_and_force() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("demo.log");
+        let log_arg = log.to_str().unwrap();
+
+        let (result, output) = run_args(&["--dry-run", "demo", "--out", log_arg], &data_file);
+        result.unwrap();
+        assert_eq!(
+            output,
+            format!("[dry-run] Would write demo log to {}\n", log.display())
+        );
+        assert!(!log.exists());
+
+        std::fs::write(&log, "keep me").unwrap();
+        let (result, _) = run_args(&["demo", "--out", log_arg], &data_file);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        assert!(err.to_string().contains("--force"), "{err}");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "keep me");
+
+        let (result, _) = run_args(&["demo", "--out", log_arg, "--force"], &data_file);
+        result.unwrap();
+        assert!(std::fs::read_to_string(&log)
+            .unwrap()
+            .starts_with("Log file open"));
+    }
+
+    #[test]
+    fn test_replay_recorded_excerpt() {
+        let (dir, data_file) = temp_data_file();
+        let excerpt = dir.path().join("run.log");
+        std::fs::write(
+            &excerpt,
+            "ItemChange@ ProtoName=ResetItemsLayout end\n\
+             BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500\n\
+             ItemChange@ ProtoName=PickItems start\n\
+             BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 520\n\
+             BagMgr@:Modfy BagItem PageId = 102 SlotId = 4 ConfigBaseId = 5028 Num = 3\n\
+             ItemChange@ ProtoName=PickItems end\n",
+        )
+        .unwrap();
+
+        let (result, output) =
+            run_args(&["--json", "replay", excerpt.to_str().unwrap()], &data_file);
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["lines"], 6);
+        assert_eq!(json["loot"]["baseline_found"], true);
+        let deltas: Vec<(String, i64)> = json["loot"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| {
+                (
+                    i["config_base_id"].as_str().unwrap().to_string(),
+                    i["delta"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            deltas,
+            vec![("100300".to_string(), 20), ("5028".to_string(), 3)]
+        );
+
+        let (result, output) = run_args(&["replay", excerpt.to_str().unwrap()], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Replayed 6 lines"));
+        assert!(output.contains("Flame Elementium"));
+    }
+
+    #[test]
+    fn test_loot_json_reports_baseline() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        let pickup = "ItemChange@ ProtoName=PickItems start\n\
+                      BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 520\n\
+                      ItemChange@ ProtoName=PickItems end\n";
+        let loot_json = || {
+            let (result, output) = run_args(
+                &["--json", "loot", "--log", log.to_str().unwrap()],
+                &data_file,
+            );
+            result.unwrap();
+            serde_json::from_str::<serde_json::Value>(&output).unwrap()
+        };
+
+        std::fs::write(&log, pickup).unwrap();
+        let json = loot_json();
+        // The pickup is seen, but there is no sort to measure it from.
+        assert_eq!(json["baseline_found"], false);
+        assert_eq!(json["total_events"], 1);
+        assert!(json["hint"]
+            .as_str()
+            .unwrap()
+            .contains("sort your inventory"));
+
+        std::fs::write(
+            &log,
+            format!(
+                "ItemChange@ ProtoName=ResetItemsLayout end\n\
+                 BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500\n\
+                 {pickup}"
+            ),
+        )
+        .unwrap();
+        let json = loot_json();
+        assert_eq!(json["baseline_found"], true);
+        assert_eq!(json["total_events"], 1);
+        assert_eq!(json["hint"], serde_json::Value::Null);
+        assert_eq!(json["items"][0]["delta"], 20);
+
+        let (result, output) = run_args(&["loot", "--log", log.to_str().unwrap()], &data_file);
+        result.unwrap();
+        assert!(output.contains("+20"));
+        assert!(output.ends_with("Loot events: 1\n"));
+
+        let (result, _) = run_args(
+            &[
+                "settings",
+                "--score-mode",
+                "weighted",
+                "--item-weight",
+                "100300=0.5",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        let json = loot_json();
+        assert_eq!(json["score_mode"], "weighted");
+        assert_eq!(json["score"], 10.0);
+    }
+
+    #[test]
+    fn test_inventory_export() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        std::fs::write(
+            &log,
+            "ItemChange@ ProtoName=ResetItemsLayout end\n\
+             BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500\n\
+             BagMgr@:InitBagData PageId = 102 SlotId = 1 ConfigBaseId = 100300 Num = 20\n",
+        )
+        .unwrap();
+        let log_arg = log.to_str().unwrap();
+
+        let (result, output) = run_args(&["inventory", "--log", log_arg], &data_file);
+        result.unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert!(output.starts_with("item_name,config_base_id,page,slot,quantity\n"));
+
+        let csv = dir.path().join("inventory.csv");
+        let (result, output) = run_args(
+            &[
+                "inventory",
+                "--log",
+                log_arg,
+                "--grouped",
+                "--out",
+                csv.to_str().unwrap(),
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.starts_with("Exported 1 inventory rows"));
+        let written = std::fs::read_to_string(&csv).unwrap();
+        assert!(written.ends_with(",100300,520,2\n"), "{written}");
+
+        let (result, output) = run_args(&["--json", "inventory", "--log", log_arg], &data_file);
+        result.unwrap();
+        let rows: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(rows[1]["slot"], 1);
+    }
+
+    #[test]
+    fn test_inventory_out_error_names_the_file() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        std::fs::write(
+            &log,
+            "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 5\n",
+        )
+        .unwrap();
+        let target = dir.path().join("missing-dir").join("inventory.csv");
+        let (result, _) = run_args(
+            &[
+                "inventory",
+                "--log",
+                log.to_str().unwrap(),
+                "--out",
+                target.to_str().unwrap(),
+            ],
+            &data_file,
+        );
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        let message = err.to_string();
+        assert!(
+            message.starts_with(&format!(
+                "Failed to write inventory to {}: ",
+                target.display()
+            )),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_inventory_diff_between_exports() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        let sort = "ItemChange@ ProtoName=ResetItemsLayout end\n";
+        let slot = |num: u32| {
+            format!(
+                "BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = {num}\n"
+            )
+        };
+        let export = |name: &str| {
+            let path = dir.path().join(name);
+            let (result, _) = run_args(
+                &[
+                    "--json",
+                    "inventory",
+                    "--log",
+                    log.to_str().unwrap(),
+                    "--out",
+                    path.to_str().unwrap(),
+                ],
+                &data_file,
+            );
+            result.unwrap();
+            path
+        };
+        std::fs::write(&log, format!("{sort}{}", slot(500))).unwrap();
+        let before = export("before.json");
+        std::fs::write(&log, format!("{sort}{}", slot(380))).unwrap();
+        let after = export("after.json");
+
+        let args = [
+            "inventory-diff",
+            before.to_str().unwrap(),
+            after.to_str().unwrap(),
+        ];
+        let (result, output) = run_args(&args, &data_file);
+        result.unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(output.lines().nth(1).unwrap().ends_with("-120"), "{output}");
+
+        let (result, output) = run_args(
+            &[
+                "inventory-diff",
+                before.to_str().unwrap(),
+                before.to_str().unwrap(),
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert_eq!(output, "No changes.\n");
+    }
+
+    #[test]
+    fn test_snapshot_save_list_diff_delete() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        let log_arg = log.to_str().unwrap();
+        let write_log = |num: u32| {
+            std::fs::write(
+                &log,
+                format!(
+                    "ItemChange@ ProtoName=ResetItemsLayout end\n\
+                     BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = {num}\n"
+                ),
+            )
+            .unwrap();
+        };
+        write_log(500);
+
+        let (result, output) = run_args(&["snapshot", "start", "--log", log_arg], &data_file);
+        result.unwrap();
+        assert_eq!(output, "Saved snapshot \"start\"\n");
+
+        let (result, output) = run_args(&["snapshot"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("start "), "{output}");
+        assert!(output.trim_end().ends_with("1 slots"), "{output}");
+
+        write_log(450);
+        let (result, output) = run_args(
+            &["--json", "inventory-diff", "start", "--log", log_arg],
+            &data_file,
+        );
+        result.unwrap();
+        let changes: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(changes[0]["delta"], -50);
+
+        let (result, _) = run_args(&["snapshot", "start", "--delete"], &data_file);
+        result.unwrap();
+        let (result, _) = run_args(&["snapshot", "start", "--delete"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+        let (result, _) = run_args(&["inventory-diff", "start", "--log", log_arg], &data_file);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_net_change_from_session_snapshots() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        let log_arg = log.to_str().unwrap();
+        let write_log = |fe: u32, embers: u32| {
+            std::fs::write(
+                &log,
+                format!(
+                    "ItemChange@ ProtoName=ResetItemsLayout end\n\
+                     BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = {fe}\n\
+                     BagMgr@:InitBagData PageId = 102 SlotId = 1 ConfigBaseId = 5028 Num = {embers}\n"
+                ),
+            )
+            .unwrap();
+        };
+
+        write_log(500, 8);
+        let (result, _) = run_args(
+            &["start-session", "--map", "Netherrealm", "--log", log_arg],
+            &data_file,
+        );
+        result.unwrap();
+        let (result, output) = run_args(&["net-change"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("No inventory snapshots"), "{output}");
+
+        write_log(650, 5);
+        let (result, _) = run_args(&["end-session", "--log", log_arg], &data_file);
+        result.unwrap();
+        let id = storage::load_sessions(&data_file).unwrap()[0].id.clone();
+        let (result, output) = run_args(&["--json", "net-change", "--session", &id], &data_file);
+        result.unwrap();
+        let changes: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(changes[0]["config_base_id"], "100300");
+        assert_eq!(changes[0]["delta"], 150);
+        assert_eq!(changes[1]["delta"], -3);
+    }
+
+    #[test]
+    fn test_log_events_follow_offset() {
+        let (dir, data_file) = temp_data_file();
+        let log = dir.path().join("UE_game.log");
+        let map = "SceneLevelMgr@ OpenMainWorld END! InMainLevelPath = /Game/Art/Maps/01SD/XZ_YuJinZhiXiBiNanSuo200/test";
+        std::fs::write(&log, format!("{map}\n")).unwrap();
+        let log_arg = log.to_str().unwrap();
+
+        let (result, output) = run_args(&["log-events", "--log", log_arg], &data_file);
+        result.unwrap();
+        let batch: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(batch["events"][0]["type"], "Map");
+        let offset = batch["offset"].as_u64().unwrap();
+
+        let since = offset.to_string();
+        let (result, output) = run_args(
+            &["log-events", "--log", log_arg, "--since", &since],
+            &data_file,
+        );
+        result.unwrap();
+        let batch: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(batch["events"], serde_json::json!([]));
+        assert_eq!(batch["offset"], offset);
+    }
+
+    #[test]
+    fn test_add_drops_appends_batch() {
+        let (dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        let list = dir.path().join("drops.csv");
+        std::fs::write(&list, "Ember,3,2\nGlass,1\n").unwrap();
+
+        let (result, output) =
+            run_args(&["add-drops", "--file", list.to_str().unwrap()], &data_file);
+        result.unwrap();
+
+        let session = &storage::load_sessions(&data_file).unwrap()[0];
+        assert_eq!(session.drops.len(), 3);
+        assert_eq!(
+            output,
+            format!(
+                "Added 2 drops to session {} (new total: 44.00)\n",
+                session.id
+            )
+        );
+    }
+
+    #[test]
+    fn test_add_drops_bad_line_adds_nothing() {
+        let (dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        let before = std::fs::read(&data_file).unwrap();
+        let list = dir.path().join("drops.csv");
+        std::fs::write(&list, "Ember,3,2\nGlass,x,1\nShard,1,1\n").unwrap();
+
+        let (result, _) = run_args(&["add-drops", "--file", list.to_str().unwrap()], &data_file);
+        let err = result.unwrap_err();
+
+        assert_eq!(err.exit_code(), 1);
+        assert!(err.to_string().contains("line 2:"), "{err}");
+        assert_eq!(std::fs::read(&data_file).unwrap(), before);
+    }
+
+    #[test]
+    fn test_migrate_renames_resolvable_unknown_drops() {
+        let (_dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.drops.push(DropItem {
+            name: format!("Unknown {}", log_parser::FLAME_ELEMENTIUM_ID),
+            quantity: 5,
+            value: 1.0,
+            added_at: None,
+        });
+        session.drops.push(DropItem {
+            name: "Unknown 999999999".to_string(),
+            quantity: 1,
+            value: 1.0,
+            added_at: None,
+        });
+        storage::save_sessions(&data_file, &[session]).unwrap();
+
+        let (result, output) = run_args(&["migrate"], &data_file);
+        result.unwrap();
+
+        assert_eq!(output, "Renamed 1 drops\n");
+        let names: Vec<_> = storage::load_sessions(&data_file).unwrap()[0]
+            .drops
+            .iter()
+            .map(|d| d.name.clone())
+            .collect();
+        assert_eq!(
+            names,
+            ["Flame Core", "Flame Elementium", "Unknown 999999999"]
+        );
+    }
+
+    #[test]
+    fn test_recommend_uses_recorded_runs() {
+        let (_dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.runs = vec![
+            models::RunRecord {
+                items: 4,
+                ..run_record("Rift", 600.0, 100)
+            };
+            3
+        ];
+        storage::save_sessions(&data_file, &[session]).unwrap();
+
+        let (result, output) = run_args(&["recommend"], &data_file);
+        result.unwrap();
+        assert_eq!(output, "Best map: Rift (600 FE/hr over 3 runs)\n");
+        let (result, output) = run_args(&["recommend", "--by-tier"], &data_file);
+        result.unwrap();
+        assert_eq!(
+            output,
+            "Best map: Rift (unknown tier) (600 FE/hr over 3 runs)\n"
+        );
+
+        let (result, output) = run_args(&["recommend", "--min-runs", "4"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Not enough data"));
+    }
+
+    #[test]
+    fn test_fix_ends_older_active_sessions() {
+        let (_dir, data_file) = temp_data_file();
+        let mut older = sample_session();
+        older.id = "older".to_string();
+        older.start_time = Utc::now() - chrono::Duration::hours(2);
+        let newer = sample_session();
+        storage::save_sessions(&data_file, &[older, newer.clone()]).unwrap();
+
+        let (result, output) = run_args(&["fix"], &data_file);
+        result.unwrap();
+
+        assert_eq!(output, "Session ended: older\n");
+        let sessions = storage::load_sessions(&data_file).unwrap();
+        assert!(!sessions[0].is_active());
+        assert!(sessions[1].is_active());
+        assert_eq!(resolve_session_id(&sessions, None).unwrap(), newer.id);
+
+        let (result, output) = run_args(&["fix"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Nothing to fix"));
+    }
+
+    #[test]
+    fn test_prune_deletes_old_ended_sessions_only() {
+        let (dir, data_file) = temp_data_file();
+        let long_ago = Utc::now() - chrono::Duration::days(60);
+        let mut old = sample_session();
+        old.id = "old".to_string();
+        old.start_time = long_ago;
+        old.end_time = Some(long_ago + chrono::Duration::hours(1));
+        let mut old_active = sample_session();
+        old_active.id = "old-active".to_string();
+        old_active.start_time = long_ago;
+        let mut recent = sample_session();
+        recent.id = "recent".to_string();
+        recent.end_time = Some(Utc::now());
+        storage::save_sessions(&data_file, &[old, old_active, recent]).unwrap();
+
+        let (result, output) = run_args(
+            &["--dry-run", "prune", "--older-than-days", "30"],
+            &data_file,
+        );
+        result.unwrap();
+        assert_eq!(
+            output,
+            "[dry-run] Would delete session old\n[dry-run] Would delete 1 of 3 sessions\n"
+        );
+        assert_eq!(storage::load_sessions(&data_file).unwrap().len(), 3);
+
+        let (result, output) = run_args(&["prune", "--older-than-days", "30"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Deleted 1 sessions, kept 2. Backup: "));
+        let ids: Vec<String> = storage::load_sessions(&data_file)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, ["old-active", "recent"]);
+        let backups: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backed_up = storage::load_sessions(&backups[0].path()).unwrap();
+        assert_eq!(backed_up.len(), 3);
+
+        let (result, output) = run_args(&["prune", "--older-than-days", "30"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Nothing to prune"));
+
+        let max = i64::MAX.to_string();
+        let (result, output) = run_args(&["prune", "--older-than-days", &max], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Nothing to prune"));
+    }
+
+    #[test]
+    fn test_find_sessions_by_item() {
+        let (_dir, data_file) = temp_data_file();
+        let session = sample_session();
+        storage::save_sessions(&data_file, std::slice::from_ref(&session)).unwrap();
+
+        let (result, output) = run_args(&["find", "flame core"], &data_file);
+        result.unwrap();
+        assert!(output.contains(&session.id));
+        assert!(output.trim_end().ends_with("x2"));
+
+        let (result, output) = run_args(&["--json", "find", "Flame Core"], &data_file);
+        result.unwrap();
+        let found: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(found[0]["quantity"], 2);
+
+        let (result, output) = run_args(&["find", "Ember"], &data_file);
+        result.unwrap();
+        assert_eq!(output, "No sessions contain Ember.\n");
+    }
+
+    #[test]
+    fn test_start_session_auto_ends_forgotten_session() {
+        let (_dir, data_file) = temp_data_file();
+        let last_drop = Utc::now() - chrono::Duration::hours(10);
+        let mut forgotten = sample_session();
+        forgotten.start_time = Utc::now() - chrono::Duration::hours(12);
+        forgotten.drops[0].added_at = Some(last_drop);
+        storage::save_sessions(&data_file, &[forgotten.clone()]).unwrap();
+
+        // Off by default.
+        let (result, _) = run_args(
+            &["--dry-run", "start-session", "--map", "Abyss"],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(storage::load_sessions(&data_file).unwrap()[0].is_active());
+
+        let (result, _) = run_args(&["settings", "--auto-end-hours", "8"], &data_file);
+        result.unwrap();
+        let (result, output) = run_args(&["start-session", "--map", "Abyss"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with(&format!("Auto-ended session {}", forgotten.id)));
+        let sessions = storage::load_sessions(&data_file).unwrap();
+        assert_eq!(sessions[0].end_time, Some(last_drop));
+        assert!(sessions[0].notes.as_deref().unwrap().contains("Auto-ended"));
+        assert!(sessions[1].is_active());
+        assert_eq!(resolve_session_id(&sessions, None).unwrap(), sessions[1].id);
+    }
+
+    #[test]
+    fn test_settings_sets_primary_currency() {
+        let (_dir, data_file) = temp_data_file();
+        let id = "6002";
+        assert!(!log_parser::item_name(id).starts_with("Unknown"));
+
+        let (result, _) = run_args(&["settings", "--primary-currency", id], &data_file);
+        result.unwrap();
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.primary_currency, id);
+
+        let (result, _) = run_args(&["settings", "--primary-currency", "nope"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.primary_currency, id);
+    }
+
+    #[test]
+    fn test_settings_number_formatting() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["settings"], &data_file);
+        result.unwrap();
+        assert!(output.contains("group_digits = true"));
+        assert!(output.contains("thousands_separator = ','"));
+
+        let (result, _) = run_args(
+            &[
+                "settings",
+                "--group-digits",
+                "false",
+                "--thousands-separator",
+                ".",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.thousands_separator, '.');
+        assert_eq!(saved.number_locale(), NumberLocale::EU.ungrouped());
+
+        let (result, _) = run_args(&["settings", "--thousands-separator", "5"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+
+        let (result, _) = run_args(&["settings", "--number-locale", "fr"], &data_file);
+        result.unwrap();
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.number_locale(), NumberLocale::preset("fr").unwrap());
+        let (result, _) = run_args(&["settings", "--decimal-separator", " "], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_settings_precision() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(
+            &[
+                "settings",
+                "--rate-precision",
+                "1",
+                "--value-precision",
+                "0",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("rate_precision = 1\n"));
+        assert!(output.contains("value_precision = 0\n"));
+
+        let mut session = sample_session();
+        session.end_time = Some(session.start_time + chrono::Duration::minutes(7));
+        storage::save_sessions(&data_file, &[session]).unwrap();
+        let (result, output) = run_args(
+            &[
+                "summary",
+                "--session",
+                "0b6f5c1e-1111-2222-3333-444455556666",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("Total value: 37\n"), "{output}");
+        assert!(output.contains("Profit/min: 5\n"), "{output}");
+
+        assert!(Cli::try_parse_from(["tli-tracker", "settings", "--rate-precision", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_settings_recent_runs() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["settings", "--recent-runs", "5"], &data_file);
+        result.unwrap();
+        assert!(output.contains("recent_runs = 5"));
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.recent_runs, 5);
+
+        assert!(Cli::try_parse_from(["tli-tracker", "settings", "--recent-runs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_settings_pace_floor() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(
+            &["settings", "--pace-floor", "1500", "--pace-alert-runs", "3"],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("pace_floor = 1500"));
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.pace_floor, Some(1500.0));
+        assert_eq!(saved.pace_alert_runs, 3);
+
+        let (result, output) = run_args(&["settings", "--pace-floor", "0"], &data_file);
+        result.unwrap();
+        assert!(output.contains("pace_floor = off"));
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.pace_floor, None);
+
+        let (result, _) = run_args(&["settings", "--pace-floor=-5"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_settings_target_per_hour() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["settings", "--target-per-hour", "2400"], &data_file);
+        result.unwrap();
+        assert!(output.contains("target_per_hour = 2400"));
+
+        let (result, output) = run_args(&["settings", "--target-per-hour", "0"], &data_file);
+        result.unwrap();
+        assert!(output.contains("target_per_hour = off"));
+
+        let (result, _) = run_args(&["settings", "--target-per-hour=-1"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_settings_log_url() {
+        let (_dir, data_file) = temp_data_file();
+        let url = "http://gaming-pc:8000/UE_game.log";
+        let (result, output) = run_args(&["settings", "--log-url", url], &data_file);
+        result.unwrap();
+        assert!(output.contains(&format!("log_url = {}", url)));
+
+        let (result, _) = run_args(&["settings", "--log-url", "ftp://x"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+
+        let (result, output) = run_args(&["settings", "--log-url", ""], &data_file);
+        result.unwrap();
+        assert!(output.contains("log_url = (local file)"));
+    }
+
+    #[test]
+    fn test_settings_poll_interval() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["settings", "--poll-interval-secs", "10"], &data_file);
+        result.unwrap();
+        assert!(output.contains("poll_interval_secs = 10"));
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(saved.poll_interval_secs, 10);
+
+        assert!(
+            Cli::try_parse_from(["tli-tracker", "settings", "--poll-interval-secs", "0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_settings_ignore_items() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(
+            &["settings", "--ignore-item", "6002", "--ignore-item", "6003"],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("ignored_items = 6002 ("));
+        let (result, output) = run_args(
+            &[
+                "settings",
+                "--unignore-item",
+                "6003",
+                "--count-ignored",
+                "true",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("count_ignored = true"));
+        let saved = settings::load(&settings::settings_path(&data_file));
+        assert_eq!(
+            saved.ignored_items.into_iter().collect::<Vec<_>>(),
+            ["6002"]
+        );
+        assert!(saved.count_ignored);
+    }
+
+    #[test]
+    fn test_settings_pin_items() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["settings", "--pin-item", "6002"], &data_file);
+        result.unwrap();
+        assert!(output.contains("pinned_items = 6002 ("));
+        let (result, output) = run_args(&["settings", "--unpin-item", "6002"], &data_file);
+        result.unwrap();
+        assert!(output.contains("pinned_items = (none)"));
+    }
+
+    #[test]
+    fn test_settings_loot_contexts() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(
+            &[
+                "settings",
+                "--loot-context",
+                "ChestRewards",
+                "--loot-context",
+                "PickItems",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("loot_contexts = PickItems, ChestRewards\n"));
+        let (result, output) = run_args(
+            &["settings", "--remove-loot-context", "PickItems"],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("loot_contexts = ChestRewards\n"));
+    }
+
+    #[test]
+    fn test_settings_page_capacity() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(
+            &[
+                "settings",
+                "--page-capacity",
+                "102=60",
+                "--page-capacity",
+                "103=40",
+                "--bag-warn-percent",
+                "85",
+                "--sort-reminder-mins",
+                "0",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("page_capacities = 102=60, 103=40"));
+        assert!(output.contains("bag_warn_percent = 85"));
+        assert!(output.contains("sort_reminder_mins = 0"));
+
+        let (result, output) = run_args(
+            &["settings", "--stat-boxes", "time,primary_per_hour,runs"],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.contains("stat_boxes = time, primary_per_hour, runs\n"));
+        assert!(Cli::try_parse_from(["tli-tracker", "settings", "--stat-boxes", "fe"]).is_err());
+
+        let (result, output) = run_args(&["settings", "--page-capacity", "103=0"], &data_file);
+        result.unwrap();
+        assert!(output.contains("page_capacities = 102=60\n"));
+
+        assert!(
+            Cli::try_parse_from(["tli-tracker", "settings", "--page-capacity", "102"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_min_session_secs_filters_stats_not_list() {
+        let (_dir, data_file) = temp_data_file();
+        let mut aborted = sample_session();
+        aborted.end_time = Some(aborted.start_time + chrono::Duration::seconds(10));
+        aborted.runs.push(models::RunRecord {
+            items: 1,
+            ..run_record("Abyss", 8.0, 50)
+        });
+        storage::save_sessions(&data_file, &[aborted]).unwrap();
+
+        let (result, output) = run_args(&["stats"], &data_file);
+        result.unwrap();
+        assert!(output.contains("Abyss"));
+
+        let (result, output) = run_args(&["settings", "--min-session-secs", "60"], &data_file);
+        result.unwrap();
+        assert!(output.contains("min_session_secs = 60"));
+
+        let (result, output) = run_args(&["stats"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("No runs recorded yet"));
+        let (result, output) = run_args(&["recommend", "--min-runs", "1"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Not enough data yet"));
+        let (result, output) = run_args(&["list"], &data_file);
+        result.unwrap();
+        assert!(output.contains("0b6f5c1e"));
+    }
+
+    #[test]
+    fn test_data_dir_flag_selects_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("league-x");
+        let cli = Cli::try_parse_from([
+            "tli-tracker",
+            "start-session",
+            "--map",
+            "Abyss",
+            "--data-dir",
+            store.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(cli.data_dir.as_deref(), Some(store.as_path()));
+
+        let data_file = storage::data_file_in(cli.data_dir.as_deref().unwrap()).unwrap();
+        run(cli, &data_file, &mut Vec::new()).unwrap();
+        assert_eq!(data_file, store.join("sessions.json"));
+        assert_eq!(storage::load_sessions(&data_file).unwrap().len(), 1);
+
+        // Settings and prices live next to the sessions file.
+        let (result, _) = run_args(&["settings", "--recent-runs", "4"], &data_file);
+        result.unwrap();
+        assert!(store.join("settings.json").is_file());
+        assert_eq!(prices::cache_path(&data_file), store.join("prices.json"));
+    }
+
+    #[test]
+    fn test_update_prices_accepts_huge_ttl() {
+        let (_dir, data_file) = temp_data_file();
+        let cache = prices::PriceCache {
+            fetched_at: Utc::now(),
+            source: "cached".to_string(),
+            prices: [("100300".to_string(), 1.5)].into_iter().collect(),
+        };
+        let path = prices::cache_path(&data_file);
+        std::fs::write(&path, serde_json::to_string(&cache).unwrap()).unwrap();
+
+        let max = i64::MAX.to_string();
+        let (result, output) = run_args(
+            &[
+                "update-prices",
+                "--url",
+                "http://127.0.0.1:9/",
+                "--ttl-minutes",
+                &max,
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert!(output.starts_with("Prices are up to date (1 items"));
+    }
+
+    #[test]
+    fn test_breakdown_command() {
+        let (_dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.drops.push(DropItem {
+            name: "Ember".to_string(),
+            quantity: 3,
+            value: 1.0,
+            added_at: None,
+        });
+        storage::save_sessions(&data_file, std::slice::from_ref(&session)).unwrap();
+
+        let (result, output) = run_args(&["--json", "breakdown", "--top", "1"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json[0]["name"], "Flame Core");
+        assert_eq!(json[0]["value"], 37.0);
+        assert_eq!(json[1]["name"], "Other");
+        assert_eq!(json[1]["value"], 3.0);
+
+        let (result, output) = run_args(&["breakdown"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Flame Core"));
+        assert!(output.lines().nth(1).unwrap().starts_with("Ember"));
+
+        session.drops.clear();
+        storage::save_sessions(&data_file, &[session]).unwrap();
+        let (result, output) = run_args(&["breakdown"], &data_file);
+        result.unwrap();
+        assert_eq!(output, "No valued drops in this session.\n");
+    }
+
+    #[test]
+    fn test_compare_sessions() {
+        let (_dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.start_time = Utc::now() - chrono::Duration::hours(2);
+        session.end_time = Some(session.start_time + chrono::Duration::hours(1));
+        session.runs = vec![run_record("Abyss", 600.0, 900)];
+        storage::save_sessions(&data_file, &[session.clone()]).unwrap();
+
+        let ids = format!("{},missing", session.id);
+        let (result, output) = run_args(&["compare", &ids], &data_file);
+        result.unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert!(lines[0].starts_with("0b6f5c1e Netherrealm"));
+        assert!(lines[0].ends_with("900 FE/hr"));
+        assert_eq!(lines[1], "Skipped missing: no such session");
+
+        let (result, output) = run_args(&["--json", "compare", &session.id, "x"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["sessions"][0]["fe"], 900);
+        assert_eq!(json["sessions"][0]["fe_per_hour"], 900.0);
+        assert_eq!(json["missing"], serde_json::json!(["x"]));
+
+        assert!(Cli::try_parse_from(["tli-tracker", "compare"]).is_err());
+    }
+
+    #[test]
+    fn test_combine_sessions() {
+        let (_dir, data_file) = temp_data_file();
+        let mut short = sample_session();
+        short.runs = vec![run_record("Abyss", 600.0, 500)];
+        let mut long = sample_session();
+        long.id = "long".to_string();
+        long.runs = vec![run_record("Abyss", 3000.0, 1000)];
+        storage::save_sessions(&data_file, &[short.clone(), long]).unwrap();
+
+        let ids = format!("{},long,missing", short.id);
+        let (result, output) = run_args(&["--json", "combine", &ids], &data_file);
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["fe"], 1500);
+        assert_eq!(json["fe_per_hour"], 1500.0);
+        assert_eq!(json["mean_fe_per_hour"], 2100.0);
+        assert_eq!(json["missing"], serde_json::json!(["missing"]));
+
+        let (result, output) = run_args(&["combine", &short.id, "long"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Sessions:     2 (2 runs)\n"), "{output}");
+        assert!(Cli::try_parse_from(["tli-tracker", "combine"]).is_err());
+    }
+
+    #[test]
+    fn test_histogram_command() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["histogram"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("No runs recorded yet"));
+
+        let mut session = sample_session();
+        session.runs = [120, 180, 450]
+            .into_iter()
+            .map(|fe| run_record("Abyss", 600.0, fe))
+            .collect();
+        storage::save_sessions(&data_file, &[session]).unwrap();
+
+        let (result, output) =
+            run_args(&["--json", "histogram", "--bucket-size", "200"], &data_file);
+        result.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"start": 0, "size": 200, "runs": 2},
+                {"start": 200, "size": 200, "runs": 0},
+                {"start": 400, "size": 200, "runs": 1},
+            ])
+        );
+
+        let (result, output) = run_args(&["histogram", "--map", "Rift"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("No runs recorded yet"));
+    }
+
+    #[test]
+    fn test_scorecard_writes_png() {
+        let (dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        let png = dir.path().join("card.png");
+
+        let (result, output) = run_args(&["scorecard", "--out", png.to_str().unwrap()], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("Scorecard written"));
+        let img = image::open(&png).unwrap();
+        assert_eq!(
+            (img.width(), img.height()),
+            (scorecard::WIDTH, scorecard::HEIGHT)
+        );
+
+        let (result, _) = run_args(
+            &["scorecard", "--session", "missing", "--out", "x.png"],
+            &data_file,
+        );
+        assert_eq!(result.unwrap_err().exit_code(), 3);
+    }
+
+    #[test]
+    fn test_goal_set_show_and_clear() {
+        let (_dir, data_file) = temp_data_file();
+        let (result, output) = run_args(&["goal"], &data_file);
+        result.unwrap();
+        assert!(output.starts_with("No goals set"));
+
+        let (result, output) = run_args(&["goal", "Abyss", "--set", "2000"], &data_file);
+        result.unwrap();
+        assert!(output.lines().nth(1).unwrap().starts_with("Abyss"));
+        // Same map, different case: updates the existing goal.
+        let (result, _) = run_args(&["goal", "abyss", "--set", "2500"], &data_file);
+        result.unwrap();
+        let goals = settings::load(&settings::settings_path(&data_file)).map_goals;
+        assert_eq!(
+            goals.into_iter().collect::<Vec<_>>(),
+            [("Abyss".to_string(), 2500)]
+        );
+
+        let (result, output) = run_args(&["stats"], &data_file);
+        result.unwrap();
+        assert!(output.contains("Goal (today)"));
+
+        let (result, _) = run_args(&["goal", "Abyss", "--set", "-1"], &data_file);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+        let (result, _) = run_args(&["goal", "Abyss", "--set", "0"], &data_file);
+        result.unwrap();
+        assert!(settings::load(&settings::settings_path(&data_file))
+            .map_goals
+            .is_empty());
+        assert!(Cli::try_parse_from(["tli-tracker", "goal", "--set", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_profiles_keep_sessions_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        let league = storage::data_file_in(&storage::store_dir(base, Some("league-x"))).unwrap();
+        let other = storage::data_file_in(&storage::store_dir(base, Some("other"))).unwrap();
+        let default = base.join("sessions.json");
+
+        let (result, _) = run_args(&["start-session", "--map", "Abyss"], &league);
+        result.unwrap();
+        let (result, _) = run_args(&["settings", "--recent-runs", "7"], &league);
+        result.unwrap();
+
+        for data_file in [&other, &default] {
+            let (result, output) = run_args(&["list"], data_file);
+            result.unwrap();
+            assert_eq!(output, "No sessions found.\n");
+            assert_eq!(
+                settings::load(&settings::settings_path(data_file)).recent_runs,
+                3
+            );
+        }
+        let (result, output) = run_args(&["list"], &league);
+        result.unwrap();
+        assert!(output.contains("Abyss"));
+
+        let (result, output) = run_args(&["--profile", "league-x", "profiles"], &league);
+        result.unwrap();
+        assert_eq!(output, "  (default)\n* league-x\n  other\n");
+        let (result, output) = run_args(&["-q", "profiles"], &default);
+        result.unwrap();
+        assert_eq!(output, "league-x\nother\n");
+    }
+
+    #[test]
+    fn test_profile_flag_rejects_paths() {
+        assert!(Cli::try_parse_from(["tli-tracker", "--profile", "../x", "list"]).is_err());
+        let cli = Cli::try_parse_from(["tli-tracker", "list", "--profile", "league-x"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("league-x"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_data_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+        std::fs::set_permissions(&data_file, std::fs::Permissions::from_mode(0o444)).unwrap();
+        if std::fs::OpenOptions::new()
+            .write(true)
+            .open(&data_file)
+            .is_ok()
+        {
+            // Running as root: permissions are not enforced.
+            return;
+        }
+
+        let (result, output) = run_args(&["list"], &data_file);
+        result.unwrap();
+        assert!(output.contains("Netherrealm"));
+        let (result, _) = run_args(&["summary"], &data_file);
+        result.unwrap();
+        let export = dir.path().join("export.json");
+        let (result, _) = run_args(&["export", "--out", export.to_str().unwrap()], &data_file);
+        result.unwrap();
+
+        let (result, _) = run_args(&["end-session"], &data_file);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert!(err.to_string().contains("not writable"));
+        assert!(err.to_string().contains(&data_file.display().to_string()));
+    }
+
+    #[test]
+    fn test_unwritable_data_dir_reports_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not-a-dir");
+        std::fs::write(&blocker, "").unwrap();
+        let data_file = blocker.join("sessions.json");
+
+        let (result, output) = run_args(&["list"], &data_file);
+        result.unwrap();
+        assert_eq!(output, "No sessions found.\n");
+
+        let (result, _) = run_args(&["start-session", "--map", "Abyss"], &data_file);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert!(err.to_string().contains("not writable"));
+    }
+
+    #[test]
+    fn test_end_session_without_dry_run_writes() {
+        let (_dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+
+        let (result, _) = run_args(&["end-session"], &data_file);
+        result.unwrap();
+
+        assert!(!storage::load_sessions(&data_file).unwrap()[0].is_active());
+    }
+
+    #[test]
+    fn test_expect_version_updates_and_rejects_stale() {
+        let (_dir, data_file) = temp_data_file();
+        storage::save_sessions(&data_file, &[sample_session()]).unwrap();
+
+        let (result, _) = run_args(
+            &[
+                "add-drop",
+                "--name",
+                "Ember",
+                "--value",
+                "2",
+                "--expect-version",
+                "0",
+            ],
+            &data_file,
+        );
+        result.unwrap();
+        assert_eq!(storage::load_sessions(&data_file).unwrap()[0].version, 1);
+
+        // A second writer still holding version 0 is turned away.
+        let before = std::fs::read(&data_file).unwrap();
+        let (result, _) = run_args(&["end-session", "--expect-version", "0"], &data_file);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 5);
+        assert!(err.to_string().contains("expected version 0, found 1"));
+        assert_eq!(std::fs::read(&data_file).unwrap(), before);
+
+        let (result, _) = run_args(&["end-session", "--expect-version", "1"], &data_file);
+        result.unwrap();
+        let session = &storage::load_sessions(&data_file).unwrap()[0];
+        assert!(!session.is_active());
+        assert_eq!(session.version, 2);
+    }
+
+    #[test]
+    fn test_end_session_and_stats_show_records() {
+        let (_dir, data_file) = temp_data_file();
+        let mut session = sample_session();
+        session.start_time = Utc::now() - chrono::Duration::hours(2);
+        session.runs.push(models::RunRecord {
+            items: 10,
+            ..run_record("Abyss", 600.0, 1500)
+        });
+        storage::save_sessions(&data_file, &[session]).unwrap();
+
+        let (result, output) = run_args(&["end-session"], &data_file);
+        result.unwrap();
+        assert!(output.contains("New record! Best FE/hour"));
+        assert!(output.contains("New record! Longest session"));
+        let best = records::load(&records::records_path(&data_file));
+        assert_eq!(best.best_run_fe.unwrap().value, 1500.0);
+
+        let (result, output) = run_args(&["stats"], &data_file);
+        result.unwrap();
+        assert!(output.contains("Best FE/hour"));
+        assert!(output.contains("9,000"));
+        assert!(output.contains("2h 00m"));
+    }
+
+    #[test]
+    fn test_summary_verbosity_levels() {
+        let session = sample_session();
+        let quiet = summary_text(&session, Verbosity::Quiet, 2, &NumberLocale::US);
+        assert!(!quiet.contains("Map:"));
+        assert!(quiet.contains("Total value: 37.00"));
+
+        let verbose = summary_text(&session, Verbosity::Verbose(1), 2, &NumberLocale::US);
+        assert!(verbose.contains("Map: Netherrealm"));
+        assert!(verbose.contains("Flame Core x2 @ 18.50"));
+    }
 }
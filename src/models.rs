@@ -1,11 +1,54 @@
-use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::inventory::{self, InventoryChange, InventoryRow};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DropItem {
     pub name: String,
     pub quantity: u32,
     pub value: f64,
+    /// When the drop was added; absent in files from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<DateTime<Utc>>,
+}
+
+impl DropItem {
+    /// Reject drops that would corrupt session totals: a zero quantity or a
+    /// negative, NaN or infinite value.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.quantity == 0 {
+            return Err("Quantity must be at least 1".to_string());
+        }
+        if !self.value.is_finite() || self.value < 0.0 {
+            return Err(format!(
+                "Value must be a non-negative number (got {})",
+                self.value
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One map run recorded by the live tracker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub map: String,
+    pub duration_secs: f64,
+    /// Flame Elementium gained during the run.
+    pub fe: i64,
+    /// Net items gained during the run.
+    pub items: i64,
+    /// When the run started and ended; absent in files from older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Map tier/level parsed from the zone path, if it had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +59,18 @@ pub struct Session {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub drops: Vec<DropItem>,
+    /// Per-map runs, only present for sessions recorded by the GUI/TUI.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<RunRecord>,
+    /// Bumped on every change, so a writer holding a stale copy can tell.
+    #[serde(default)]
+    pub version: u64,
+    /// Inventory (grouped per item) when the session started and ended,
+    /// if a game log was readable then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_inventory: Option<Vec<InventoryRow>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_inventory: Option<Vec<InventoryRow>>,
 }
 
 impl Session {
@@ -23,6 +78,25 @@ impl Session {
         self.end_time.is_none()
     }
 
+    /// Record a change to the session.
+    pub fn touch(&mut self) {
+        self.version += 1;
+    }
+
+    /// Whether the session is still at `expected` (no expectation always matches).
+    pub fn version_matches(&self, expected: Option<u64>) -> bool {
+        expected.is_none_or(|v| v == self.version)
+    }
+
+    /// What the inventory actually changed by over the session, consumption
+    /// and crafting included; `None` unless both snapshots were taken.
+    pub fn inventory_change(&self) -> Option<Vec<InventoryChange>> {
+        Some(inventory::diff(
+            self.start_inventory.as_deref()?,
+            self.end_inventory.as_deref()?,
+        ))
+    }
+
     pub fn total_value(&self) -> f64 {
         self.drops
             .iter()
@@ -36,6 +110,31 @@ impl Session {
         Some(duration.num_seconds() as f64 / 60.0)
     }
 
+    /// Runs paired with when each started: the recorded start, or for runs
+    /// saved without one an estimate from the session start plus the
+    /// durations of the runs before it.
+    pub fn run_starts(&self) -> Vec<(DateTime<Utc>, &RunRecord)> {
+        let mut offset_ms = 0i64;
+        self.runs
+            .iter()
+            .map(|run| {
+                let estimate = self.start_time + chrono::Duration::milliseconds(offset_ms);
+                offset_ms += (run.duration_secs * 1000.0).round() as i64;
+                (run.started_at.unwrap_or(estimate), run)
+            })
+            .collect()
+    }
+
+    /// The latest thing known to have happened in the session: its start,
+    /// a timestamped drop or the end of a recorded run.
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        self.drops
+            .iter()
+            .filter_map(|d| d.added_at)
+            .chain(self.runs.iter().filter_map(|r| r.ended_at))
+            .fold(self.start_time, DateTime::max)
+    }
+
     pub fn profit_per_minute(&self) -> Option<f64> {
         let minutes = self.duration_minutes()?;
         if minutes <= 0.0 {
@@ -43,4 +142,767 @@ impl Session {
         }
         Some(self.total_value() / minutes)
     }
+
+    /// Human label such as `Netherrealm #3 · 14:05`: the map, this session's
+    /// `sequence` number on it (see `map_sequence_numbers`) and the local
+    /// start time.
+    pub fn display_name(&self, sequence: usize) -> String {
+        format!(
+            "{} #{} · {}",
+            self.map,
+            sequence,
+            self.start_time.with_timezone(&Local).format("%H:%M")
+        )
+    }
+}
+
+/// Clean up a map name as typed: trim it and collapse runs of whitespace
+/// into single spaces, so `" Nether  realm "` becomes `"Nether realm"`.
+pub fn normalize_map(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Key that groups spellings of the same map: normalized and lowercased.
+pub fn map_key(name: &str) -> String {
+    normalize_map(name).to_lowercase()
+}
+
+/// Whether `a` and `b` name the same map, ignoring case and stray spaces.
+pub fn same_map(a: &str, b: &str) -> bool {
+    map_key(a) == map_key(b)
+}
+
+/// Number each session by its place among the sessions on the same map,
+/// oldest first and counting from 1, keyed by session id. Maps match as in
+/// `same_map`, and sessions started at the same moment go by id.
+pub fn map_sequence_numbers(sessions: &[Session]) -> HashMap<&str, usize> {
+    let mut ordered: Vec<&Session> = sessions.iter().collect();
+    ordered.sort_by(|a, b| a.start_time.cmp(&b.start_time).then(a.id.cmp(&b.id)));
+    let mut per_map: HashMap<String, usize> = HashMap::new();
+    ordered
+        .into_iter()
+        .map(|session| {
+            let count = per_map.entry(map_key(&session.map)).or_insert(0);
+            *count += 1;
+            (session.id.as_str(), *count)
+        })
+        .collect()
+}
+
+/// A session together with its derived statistics, for machine-readable output.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary<'a> {
+    #[serde(flatten)]
+    pub session: &'a Session,
+    pub active: bool,
+    pub total_value: f64,
+    pub duration_minutes: Option<f64>,
+    pub profit_per_minute: Option<f64>,
+    /// FE from the runs recorded by the GUI/TUI; left out without runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fe: Option<i64>,
+}
+
+impl Session {
+    /// Bundle this session with its computed totals and rates.
+    pub fn summary(&self) -> SessionSummary<'_> {
+        SessionSummary {
+            session: self,
+            active: self.is_active(),
+            total_value: self.total_value(),
+            duration_minutes: self.duration_minutes(),
+            profit_per_minute: self.profit_per_minute(),
+            fe: (!self.runs.is_empty()).then(|| self.runs.iter().map(|r| r.fe).sum()),
+        }
+    }
+}
+
+/// Criteria for selecting sessions (all set fields must match).
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    pub map: Option<String>,
+    pub active: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Return true if `session` satisfies every criterion set in `filter`.
+///
+/// Map names compare as in `same_map`; `since`/`until` bound the session
+/// start time inclusively.
+pub fn session_matches(session: &Session, filter: &SessionFilter) -> bool {
+    if let Some(ref map) = filter.map {
+        if !same_map(&session.map, map) {
+            return false;
+        }
+    }
+    if let Some(active) = filter.active {
+        if session.is_active() != active {
+            return false;
+        }
+    }
+    if let Some(since) = filter.since {
+        if session.start_time < since {
+            return false;
+        }
+    }
+    if let Some(until) = filter.until {
+        if session.start_time > until {
+            return false;
+        }
+    }
+    true
+}
+
+/// Parse a filter timestamp: RFC 3339 (`2024-05-01T12:00:00Z`) or a plain
+/// date (`2024-05-01`, interpreted as midnight UTC).
+pub fn parse_filter_time(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if let Some(dt) = date.and_hms_opt(0, 0, 0) {
+            return Ok(dt.and_utc());
+        }
+    }
+    Err(format!(
+        "invalid date '{}': expected YYYY-MM-DD or RFC 3339",
+        value
+    ))
+}
+
+/// What `find` looks for: an item name, or a ConfigBaseId.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemQuery {
+    /// The query, lowercased.
+    name: String,
+    /// The query as an id, with the name it resolves to if known.
+    id: Option<(String, Option<String>)>,
+}
+
+impl ItemQuery {
+    /// A query for `text`; an all-digit query is also an id, named via
+    /// `resolve` (which returns `None` for unknown ids).
+    pub fn new(text: &str, resolve: impl Fn(&str) -> Option<String>) -> Self {
+        let text = text.trim();
+        let id = (!text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| (text.to_string(), resolve(text)));
+        ItemQuery {
+            name: text.to_lowercase(),
+            id,
+        }
+    }
+
+    /// Whether an item stored as `name` (with the ConfigBaseId `id`, if
+    /// known) is the one searched for. Names compare case-insensitively;
+    /// ids exactly, including the id in a stored `"Unknown <id>"` name.
+    pub fn matches(&self, name: &str, id: Option<&str>) -> bool {
+        if name.to_lowercase() == self.name {
+            return true;
+        }
+        let Some((query_id, query_name)) = &self.id else {
+            return false;
+        };
+        id == Some(query_id.as_str())
+            || name.strip_prefix("Unknown ").map(str::trim) == Some(query_id.as_str())
+            || query_name
+                .as_deref()
+                .is_some_and(|n| n.to_lowercase() == name.to_lowercase())
+    }
+}
+
+/// A session that holds the searched-for item.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemMatch {
+    pub session_id: String,
+    pub map: String,
+    pub start_time: DateTime<Utc>,
+    /// Total quantity of the item in the session.
+    pub quantity: i64,
+}
+
+/// Sessions containing `query`, in file order.
+///
+/// Drops are matched by name. Sessions recorded by the GUI/TUI keep only
+/// per-run totals of the primary currency (`primary` id and name), so
+/// their runs count only when that currency is searched for.
+pub fn find_item(sessions: &[Session], query: &ItemQuery, primary: (&str, &str)) -> Vec<ItemMatch> {
+    let primary_matches = query.matches(primary.1, Some(primary.0));
+    sessions
+        .iter()
+        .filter_map(|session| {
+            let mut found = false;
+            let mut quantity: i64 = 0;
+            for drop in session
+                .drops
+                .iter()
+                .filter(|d| query.matches(&d.name, None))
+            {
+                found = true;
+                quantity += i64::from(drop.quantity);
+            }
+            if primary_matches {
+                let fe: i64 = session.runs.iter().map(|r| r.fe).sum();
+                if fe > 0 {
+                    found = true;
+                    quantity += fe;
+                }
+            }
+            found.then(|| ItemMatch {
+                session_id: session.id.clone(),
+                map: session.map.clone(),
+                start_time: session.start_time,
+                quantity,
+            })
+        })
+        .collect()
+}
+
+/// Rename drops for which `rename` returns a new name; returns how many
+/// drops changed.
+pub fn rename_drops(sessions: &mut [Session], rename: impl Fn(&str) -> Option<String>) -> usize {
+    let mut changed = 0;
+    for session in sessions.iter_mut() {
+        let before = changed;
+        for drop in session.drops.iter_mut() {
+            if let Some(name) = rename(&drop.name) {
+                if name != drop.name {
+                    drop.name = name;
+                    changed += 1;
+                }
+            }
+        }
+        if changed > before {
+            session.touch();
+        }
+    }
+    changed
+}
+
+/// Warning shown when more than one session is active; commands that
+/// default to "the active session" would otherwise silently pick the first.
+pub fn multiple_active_warning(sessions: &[Session]) -> Option<String> {
+    let active = sessions.iter().filter(|s| s.is_active()).count();
+    (active > 1).then(|| {
+        format!(
+            "{} sessions are active. End the extra ones or run `tli-tracker fix` to end all but the most recent.",
+            active
+        )
+    })
+}
+
+/// End every active session except the most recently started one, at `now`.
+/// Returns the ids of the sessions that were ended.
+pub fn end_extra_active(sessions: &mut [Session], now: DateTime<Utc>) -> Vec<String> {
+    let keep = sessions
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_active())
+        .max_by_key(|(_, s)| s.start_time)
+        .map(|(i, _)| i);
+    let mut ended = Vec::new();
+    for (i, session) in sessions.iter_mut().enumerate() {
+        if session.is_active() && Some(i) != keep {
+            session.end_time = Some(now.max(session.start_time));
+            session.touch();
+            ended.push(session.id.clone());
+        }
+    }
+    ended
+}
+
+/// Ids of the ended sessions that ended more than `older_than_days` days
+/// before `now`. Active sessions are never selected, however old, and a
+/// cutoff too far back to represent selects nothing.
+pub fn prune_ids(sessions: &[Session], older_than_days: i64, now: DateTime<Utc>) -> Vec<String> {
+    let Some(cutoff) =
+        chrono::Duration::try_days(older_than_days).and_then(|age| now.checked_sub_signed(age))
+    else {
+        return Vec::new();
+    };
+    sessions
+        .iter()
+        .filter(|s| s.end_time.is_some_and(|end| end < cutoff))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// When an active session left running should have ended, if at all.
+///
+/// A session is stale once it started at least `max_age` ago and nothing has
+/// happened in it for `max_idle`; it then ends at its last activity, so the
+/// idle hours don't dilute its rate.
+pub fn auto_end_time(
+    session: &Session,
+    max_age: chrono::Duration,
+    max_idle: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if !session.is_active() || now - session.start_time < max_age {
+        return None;
+    }
+    let last = session.last_activity();
+    (now - last >= max_idle).then_some(last)
+}
+
+/// End every stale active session (see [`auto_end_time`]) with a note.
+/// Returns the ids of the sessions that were ended.
+pub fn auto_end_stale(
+    sessions: &mut [Session],
+    max_age: chrono::Duration,
+    max_idle: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut ended = Vec::new();
+    for session in sessions.iter_mut() {
+        let Some(end) = auto_end_time(session, max_age, max_idle, now) else {
+            continue;
+        };
+        session.end_time = Some(end);
+        let note = format!(
+            "Auto-ended: no activity after {}",
+            end.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        session.notes = Some(match session.notes.take() {
+            Some(notes) if !notes.is_empty() => format!("{} | {}", notes, note),
+            _ => note,
+        });
+        session.touch();
+        ended.push(session.id.clone());
+    }
+    ended
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session(map: &str, start_day: u32, ended: bool) -> Session {
+        let start_time = Utc.with_ymd_and_hms(2024, 5, start_day, 12, 0, 0).unwrap();
+        Session {
+            id: format!("{}-{}", map, start_day),
+            map: map.to_string(),
+            notes: None,
+            start_time,
+            end_time: ended.then(|| start_time + chrono::Duration::minutes(30)),
+            drops: Vec::new(),
+            runs: Vec::new(),
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    fn drop_item(quantity: u32, value: f64) -> DropItem {
+        DropItem {
+            name: "Ember".to_string(),
+            quantity,
+            value,
+            added_at: None,
+        }
+    }
+
+    #[test]
+    fn test_drop_validate() {
+        assert!(drop_item(3, 2.5).validate().is_ok());
+        assert!(drop_item(1, 0.0).validate().is_ok());
+        assert!(drop_item(1, f64::NAN).validate().is_err());
+        assert!(drop_item(1, f64::INFINITY).validate().is_err());
+        assert!(drop_item(1, -1.0).validate().is_err());
+        assert_eq!(
+            drop_item(0, 1.0).validate().unwrap_err(),
+            "Quantity must be at least 1"
+        );
+    }
+
+    #[test]
+    fn test_item_query_matches_name_or_id() {
+        let resolve = |id: &str| (id == "6002").then(|| "Ember".to_string());
+        let by_name = ItemQuery::new("ember", resolve);
+        assert!(by_name.matches("Ember", None));
+        assert!(by_name.matches("EMBER", Some("9")));
+        assert!(!by_name.matches("Ember Core", None));
+        assert!(!by_name.matches("Unknown 6002", None));
+
+        let by_id = ItemQuery::new("6002", resolve);
+        assert!(by_id.matches("Ember", None));
+        assert!(by_id.matches("Unknown 6002", None));
+        assert!(by_id.matches("Anything", Some("6002")));
+        assert!(!by_id.matches("Unknown 60021", None));
+
+        let unknown_id = ItemQuery::new("777", resolve);
+        assert!(unknown_id.matches("Unknown 777", None));
+        assert!(!unknown_id.matches("Ember", None));
+    }
+
+    #[test]
+    fn test_find_item_in_drops_and_runs() {
+        let mut manual = session("Netherrealm", 1, true);
+        manual.drops = vec![drop_item(2, 1.0), drop_item(3, 1.0)];
+        manual.drops[1].name = "ember".to_string();
+        let mut tracked = session("Abyss", 2, true);
+        tracked.runs = vec![
+            RunRecord {
+                map: "Abyss".to_string(),
+                duration_secs: 300.0,
+                fe: 120,
+                items: 4,
+                started_at: None,
+                ended_at: None,
+                tier: None,
+            };
+            2
+        ];
+        let empty = session("Rift", 3, true);
+        let sessions = [manual, tracked, empty];
+        let primary = ("100300", "Flame Elementium");
+
+        let found = find_item(&sessions, &ItemQuery::new("Ember", |_| None), primary);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            (found[0].session_id.as_str(), found[0].quantity),
+            ("Netherrealm-1", 5)
+        );
+
+        // Tracked sessions only know their primary currency.
+        let resolve = |_: &str| Some("Flame Elementium".to_string());
+        let found = find_item(&sessions, &ItemQuery::new("100300", resolve), primary);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            (found[0].session_id.as_str(), found[0].quantity),
+            ("Abyss-2", 240)
+        );
+        assert!(find_item(&sessions, &ItemQuery::new("Crystal", |_| None), primary).is_empty());
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = SessionFilter::default();
+        assert!(session_matches(&session("Netherrealm", 1, true), &filter));
+        assert!(session_matches(&session("Netherrealm", 1, false), &filter));
+    }
+
+    #[test]
+    fn test_filter_by_map_is_case_insensitive() {
+        let filter = SessionFilter {
+            map: Some("netherrealm".to_string()),
+            ..Default::default()
+        };
+        assert!(session_matches(&session("Netherrealm", 1, true), &filter));
+        assert!(!session_matches(&session("Abyss", 1, true), &filter));
+    }
+
+    #[test]
+    fn test_filter_by_active() {
+        let active = SessionFilter {
+            active: Some(true),
+            ..Default::default()
+        };
+        let ended = SessionFilter {
+            active: Some(false),
+            ..Default::default()
+        };
+        assert!(session_matches(&session("A", 1, false), &active));
+        assert!(!session_matches(&session("A", 1, true), &active));
+        assert!(session_matches(&session("A", 1, true), &ended));
+        assert!(!session_matches(&session("A", 1, false), &ended));
+    }
+
+    #[test]
+    fn test_filter_by_since_and_until() {
+        let filter = SessionFilter {
+            since: Some(parse_filter_time("2024-05-02").unwrap()),
+            until: Some(parse_filter_time("2024-05-03T23:59:59Z").unwrap()),
+            ..Default::default()
+        };
+        assert!(!session_matches(&session("A", 1, true), &filter));
+        assert!(session_matches(&session("A", 2, true), &filter));
+        assert!(session_matches(&session("A", 3, true), &filter));
+        assert!(!session_matches(&session("A", 4, true), &filter));
+    }
+
+    #[test]
+    fn test_filter_combination() {
+        let filter = SessionFilter {
+            map: Some("Abyss".to_string()),
+            active: Some(false),
+            since: Some(parse_filter_time("2024-05-02").unwrap()),
+            until: None,
+        };
+        assert!(session_matches(&session("Abyss", 3, true), &filter));
+        assert!(!session_matches(&session("Abyss", 3, false), &filter));
+        assert!(!session_matches(&session("Abyss", 1, true), &filter));
+        assert!(!session_matches(&session("Netherrealm", 3, true), &filter));
+    }
+
+    #[test]
+    fn test_parse_filter_time_rejects_garbage() {
+        assert!(parse_filter_time("yesterday").is_err());
+        assert!(parse_filter_time("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn test_rename_drops_counts_changes() {
+        let mut sessions = vec![session("A", 1, true), session("B", 2, false)];
+        for (s, name) in sessions.iter_mut().zip(["Unknown 555", "Flame Core"]) {
+            s.drops.push(DropItem {
+                name: name.to_string(),
+                quantity: 1,
+                value: 1.0,
+                added_at: None,
+            });
+        }
+        let rename = |name: &str| (name == "Unknown 555").then(|| "Ember".to_string());
+
+        assert_eq!(rename_drops(&mut sessions, rename), 1);
+        assert_eq!(sessions[0].drops[0].name, "Ember");
+        assert_eq!(sessions[1].drops[0].name, "Flame Core");
+        assert_eq!(rename_drops(&mut sessions, rename), 0);
+    }
+
+    #[test]
+    fn test_session_without_runs_field_deserializes() {
+        let json = r#"{
+            "id": "old",
+            "map": "Netherrealm",
+            "notes": null,
+            "start_time": "2024-05-01T12:00:00Z",
+            "end_time": null,
+            "drops": []
+        }"#;
+        let session: Session = serde_json::from_str(json).unwrap();
+        assert!(session.runs.is_empty());
+
+        let value = serde_json::to_value(&session).unwrap();
+        assert!(value.get("runs").is_none());
+    }
+
+    #[test]
+    fn test_version_defaults_and_touch() {
+        let json = r#"{"id":"a","map":"Abyss","notes":null,
+            "start_time":"2024-05-01T10:00:00Z","end_time":null,"drops":[]}"#;
+        let mut session: Session = serde_json::from_str(json).unwrap();
+        assert_eq!(session.version, 0);
+        assert!(session.version_matches(None));
+        assert!(session.version_matches(Some(0)));
+
+        session.touch();
+        assert_eq!(session.version, 1);
+        assert!(!session.version_matches(Some(0)));
+    }
+
+    #[test]
+    fn test_session_runs_round_trip() {
+        let mut original = session("Abyss", 1, true);
+        original.runs = vec![
+            RunRecord {
+                map: "Abyss".to_string(),
+                duration_secs: 180.5,
+                fe: 42,
+                items: 7,
+                started_at: None,
+                ended_at: None,
+                tier: None,
+            },
+            RunRecord {
+                map: "Rift".to_string(),
+                duration_secs: 95.0,
+                fe: -3,
+                items: 2,
+                started_at: Some(original.start_time + chrono::Duration::minutes(5)),
+                ended_at: Some(original.start_time + chrono::Duration::minutes(7)),
+                tier: None,
+            },
+        ];
+
+        let json = serde_json::to_string(&original).unwrap();
+        let loaded: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.runs, original.runs);
+        // A recorded start wins over the estimate from earlier durations.
+        let starts: Vec<_> = loaded.run_starts().into_iter().map(|(at, _)| at).collect();
+        assert_eq!(
+            starts,
+            [
+                original.start_time,
+                original.start_time + chrono::Duration::minutes(5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_active_warning() {
+        let mut sessions = vec![session("A", 1, true), session("B", 2, false)];
+        assert_eq!(multiple_active_warning(&sessions), None);
+
+        sessions.push(session("C", 3, false));
+        let warning = multiple_active_warning(&sessions).unwrap();
+        assert!(warning.starts_with("2 sessions are active"));
+    }
+
+    #[test]
+    fn test_end_extra_active_keeps_most_recent() {
+        let mut sessions = vec![
+            session("A", 3, false),
+            session("B", 1, true),
+            session("C", 5, false),
+            session("D", 2, false),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 5, 6, 0, 0, 0).unwrap();
+
+        let ended = end_extra_active(&mut sessions, now);
+        assert_eq!(ended, ["A-3", "D-2"]);
+        assert!(sessions[2].is_active());
+        assert_eq!(sessions[0].end_time, Some(now));
+        assert!(multiple_active_warning(&sessions).is_none());
+
+        assert!(end_extra_active(&mut sessions, now).is_empty());
+    }
+
+    #[test]
+    fn test_prune_ids_keeps_active_and_recent() {
+        // Ended on day 1, ended on day 5, active since day 1.
+        let sessions = vec![
+            session("A", 1, true),
+            session("B", 5, true),
+            session("C", 1, false),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 0, 0, 0).unwrap();
+        assert_eq!(prune_ids(&sessions, 7, now), ["A-1"]);
+        assert_eq!(prune_ids(&sessions, 1, now), ["A-1", "B-5"]);
+        assert!(prune_ids(&sessions, 30, now).is_empty());
+        assert!(prune_ids(&sessions, i64::MAX, now).is_empty());
+        assert!(prune_ids(&sessions, 1 << 40, now).is_empty());
+    }
+
+    #[test]
+    fn test_auto_end_overnight_session() {
+        let at = |day: u32, hour: u32| Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+        let hours = chrono::Duration::hours;
+        let mut overnight = session("Abyss", 1, false);
+        overnight.start_time = at(1, 20);
+        overnight.drops.push(DropItem {
+            added_at: Some(at(1, 23)),
+            ..drop_item(1, 5.0)
+        });
+        // An old drop without a timestamp is no evidence of activity.
+        overnight.drops.push(drop_item(1, 5.0));
+
+        // Still within the allowed age, or recently active: left alone.
+        assert_eq!(
+            auto_end_time(&overnight, hours(8), hours(1), at(2, 3)),
+            None
+        );
+        assert_eq!(
+            auto_end_time(&overnight, hours(8), hours(12), at(2, 9)),
+            None
+        );
+        // Next morning: ends when the last drop came in.
+        assert_eq!(
+            auto_end_time(&overnight, hours(8), hours(1), at(2, 9)),
+            Some(at(1, 23))
+        );
+
+        // Without timestamped drops the start is the last activity.
+        let mut bare = session("Rift", 1, false);
+        bare.start_time = at(1, 20);
+        assert_eq!(
+            auto_end_time(&bare, hours(8), hours(1), at(2, 9)),
+            Some(at(1, 20))
+        );
+        let ended = session("Rift", 1, true);
+        assert_eq!(auto_end_time(&ended, hours(8), hours(1), at(9, 9)), None);
+
+        overnight.notes = Some("late farm".to_string());
+        let mut sessions = vec![overnight, ended];
+        let ids = auto_end_stale(&mut sessions, hours(8), hours(1), at(2, 9));
+        assert_eq!(ids, ["Abyss-1"]);
+        assert_eq!(sessions[0].end_time, Some(at(1, 23)));
+        assert!(sessions[0]
+            .notes
+            .as_deref()
+            .unwrap()
+            .starts_with("late farm | Auto-ended: no activity after "));
+        assert_eq!(sessions[0].version, 1);
+    }
+
+    #[test]
+    fn test_normalize_map() {
+        assert_eq!(normalize_map("Netherrealm"), "Netherrealm");
+        assert_eq!(normalize_map("  Netherrealm \n"), "Netherrealm");
+        assert_eq!(normalize_map("Glacial\t  Abyss"), "Glacial Abyss");
+        assert_eq!(normalize_map("   "), "");
+        assert_eq!(normalize_map("Ölwald  Süd"), "Ölwald Süd");
+        assert!(same_map(" netherrealm", "Netherrealm "));
+        assert!(same_map("Glacial  abyss", "glacial Abyss"));
+        assert!(!same_map("Abyss", "Glacial Abyss"));
+    }
+
+    #[test]
+    fn test_map_sequence_numbers() {
+        let sessions = vec![
+            session("Abyss", 3, true),
+            session("Rift", 1, true),
+            session("abyss", 1, true),
+            session("Abyss", 5, false),
+        ];
+        let numbers = map_sequence_numbers(&sessions);
+        assert_eq!(numbers["abyss-1"], 1);
+        assert_eq!(numbers["Abyss-3"], 2);
+        assert_eq!(numbers["Abyss-5"], 3);
+        assert_eq!(numbers["Rift-1"], 1);
+        // Order in the file doesn't matter.
+        let reversed: Vec<Session> = sessions.iter().rev().cloned().collect();
+        assert_eq!(map_sequence_numbers(&reversed), numbers);
+        assert!(map_sequence_numbers(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_display_name() {
+        let session = session("Netherrealm", 2, true);
+        let time = session.start_time.with_timezone(&Local).format("%H:%M");
+        assert_eq!(
+            session.display_name(3),
+            format!("Netherrealm #3 · {}", time)
+        );
+    }
+
+    #[test]
+    fn test_inventory_change_from_snapshots() {
+        let row = |id: &str, name: &str, quantity: u64| InventoryRow {
+            item_name: name.to_string(),
+            config_base_id: id.to_string(),
+            page: None,
+            slot: None,
+            quantity,
+            slots: 1,
+        };
+        let mut session = session("Netherrealm", 2, true);
+        assert!(session.inventory_change().is_none());
+        session.start_inventory = Some(vec![
+            row("100300", "Flame Elementium", 1000),
+            row("5028", "Ember", 10),
+            row("6002", "Compass", 3),
+        ]);
+        assert!(session.inventory_change().is_none());
+
+        // FE picked up, embers crafted away, a compass consumed, a new drop.
+        session.end_inventory = Some(vec![
+            row("100300", "Flame Elementium", 1250),
+            row("6002", "Compass", 2),
+            row("7001", "Fossil", 4),
+        ]);
+        let changes = session.inventory_change().unwrap();
+        let deltas: Vec<(&str, i64)> = changes
+            .iter()
+            .map(|c| (c.config_base_id.as_str(), c.delta))
+            .collect();
+        assert_eq!(
+            deltas,
+            [("100300", 250), ("5028", -10), ("7001", 4), ("6002", -1)]
+        );
+        assert_eq!(changes[1].item_name, "Ember");
+        assert_eq!(changes[1].after, 0);
+    }
 }
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::storage;
+
+/// How long to wait for the price endpoint before giving up.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cached item prices, keyed by ConfigBaseId or item name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceCache {
+    pub fetched_at: DateTime<Utc>,
+    pub source: String,
+    pub prices: HashMap<String, f64>,
+}
+
+impl PriceCache {
+    /// Price for an item by id, falling back to a case-insensitive name match.
+    pub fn lookup(&self, config_base_id: Option<&str>, name: &str) -> Option<f64> {
+        if let Some(price) = config_base_id.and_then(|id| self.prices.get(id)) {
+            return Some(*price);
+        }
+        self.prices
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, price)| *price)
+    }
+
+    pub fn is_fresh(&self, ttl: chrono::Duration, now: DateTime<Utc>) -> bool {
+        now - self.fetched_at < ttl
+    }
+}
+
+/// Where `prices.json` lives: next to the sessions file.
+pub fn cache_path(data_file: &Path) -> PathBuf {
+    data_file.with_file_name("prices.json")
+}
+
+pub fn load_cache(path: &Path) -> Option<PriceCache> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents)
+        .map_err(|e| warn!(path = %path.display(), error = %e, "ignoring invalid price cache"))
+        .ok()
+}
+
+fn save_cache(path: &Path, cache: &PriceCache) -> Result<()> {
+    let json = serde_json::to_string_pretty(cache)?;
+    storage::write_atomic(path, &json)
+        .map_err(|e| storage::path_context(e, "save price cache to", path).into())
+}
+
+/// Something that returns the raw price JSON.
+pub trait PriceSource {
+    fn name(&self) -> &str;
+    fn fetch(&self) -> Result<String>;
+}
+
+/// A JSON price endpoint fetched over HTTP.
+pub struct HttpSource {
+    pub url: String,
+}
+
+impl PriceSource for HttpSource {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn fetch(&self) -> Result<String> {
+        let body = ureq::get(&self.url)
+            .timeout(HTTP_TIMEOUT)
+            .call()
+            .with_context(|| format!("requesting {}", self.url))?
+            .into_string()?;
+        Ok(body)
+    }
+}
+
+/// Parse a price response.
+///
+/// Accepts either an object mapping id/name to price, or an array of
+/// objects with a `price` and a `config_base_id`, `id` or `name` key.
+pub fn parse_prices(body: &str) -> Result<HashMap<String, f64>> {
+    let value: Value = serde_json::from_str(body).context("price response is not JSON")?;
+    let mut prices = HashMap::new();
+    match value {
+        Value::Object(map) => {
+            for (key, price) in map {
+                if let Some(price) = price.as_f64() {
+                    prices.insert(key, price);
+                }
+            }
+        }
+        Value::Array(entries) => {
+            for entry in entries {
+                let Some(price) = entry.get("price").and_then(Value::as_f64) else {
+                    continue;
+                };
+                let key =
+                    ["config_base_id", "id", "name"]
+                        .iter()
+                        .find_map(|k| match entry.get(*k)? {
+                            Value::String(s) => Some(s.clone()),
+                            Value::Number(n) => Some(n.to_string()),
+                            _ => None,
+                        });
+                if let Some(key) = key {
+                    prices.insert(key, price);
+                }
+            }
+        }
+        _ => return Err(anyhow!("price response must be an object or an array")),
+    }
+    Ok(prices)
+}
+
+/// What `update` did.
+#[derive(Debug)]
+pub enum PriceUpdate {
+    /// The cache was younger than the TTL and was used as-is.
+    Cached(PriceCache),
+    /// New prices were fetched and written to the cache.
+    Fetched(PriceCache),
+    /// Fetching failed; the stale cache is used instead.
+    Fallback(PriceCache, anyhow::Error),
+}
+
+impl PriceUpdate {
+    pub fn cache(&self) -> &PriceCache {
+        match self {
+            PriceUpdate::Cached(c) | PriceUpdate::Fetched(c) | PriceUpdate::Fallback(c, _) => c,
+        }
+    }
+}
+
+/// Refresh the cache at `path` from `source` unless it is younger than
+/// `ttl` (or `force` is set). Falls back to the cached prices when the
+/// fetch fails; errors only if there is nothing cached.
+pub fn update(
+    source: &dyn PriceSource,
+    path: &Path,
+    ttl: chrono::Duration,
+    force: bool,
+    now: DateTime<Utc>,
+) -> Result<PriceUpdate> {
+    let cached = load_cache(path);
+    if let Some(cache) = cached.as_ref() {
+        if !force && cache.is_fresh(ttl, now) {
+            debug!(path = %path.display(), "price cache is fresh");
+            return Ok(PriceUpdate::Cached(cache.clone()));
+        }
+    }
+
+    match source.fetch().and_then(|body| parse_prices(&body)) {
+        Ok(prices) => {
+            let cache = PriceCache {
+                fetched_at: now,
+                source: source.name().to_string(),
+                prices,
+            };
+            save_cache(path, &cache)?;
+            Ok(PriceUpdate::Fetched(cache))
+        }
+        Err(e) => match cached {
+            Some(cache) => {
+                warn!(error = %e, "price fetch failed; using cached prices");
+                Ok(PriceUpdate::Fallback(cache, e))
+            }
+            None => Err(e.context("fetching prices (no cached prices to fall back to)")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct MockSource {
+        body: Option<&'static str>,
+        calls: Cell<usize>,
+    }
+
+    impl MockSource {
+        fn new(body: Option<&'static str>) -> Self {
+            Self {
+                body,
+                calls: Cell::new(0),
+            }
+        }
+    }
+
+    impl PriceSource for MockSource {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn fetch(&self) -> Result<String> {
+            self.calls.set(self.calls.get() + 1);
+            self.body
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("connection refused"))
+        }
+    }
+
+    fn hours(h: i64) -> chrono::Duration {
+        chrono::Duration::hours(h)
+    }
+
+    #[test]
+    fn test_parse_object_and_array_responses() {
+        let prices = parse_prices(r#"{"100300": 1.0, "Flame Core": 18.5, "bad": "x"}"#).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices["Flame Core"], 18.5);
+
+        let prices = parse_prices(
+            r#"[{"config_base_id": "100300", "price": 1.0},
+                {"id": 5028, "price": 3},
+                {"name": "Ember", "price": 0.2},
+                {"name": "No price"}]"#,
+        )
+        .unwrap();
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices["5028"], 3.0);
+        assert_eq!(prices["Ember"], 0.2);
+
+        assert!(parse_prices("42").is_err());
+        assert!(parse_prices("<html>").is_err());
+    }
+
+    #[test]
+    fn test_update_writes_cache_then_reuses_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prices.json");
+        let source = MockSource::new(Some(r#"{"100300": 1.5, "Flame Core": 18.5}"#));
+        let now = Utc::now();
+
+        let first = update(&source, &path, hours(1), false, now).unwrap();
+        assert!(matches!(first, PriceUpdate::Fetched(_)));
+        let on_disk = load_cache(&path).unwrap();
+        assert_eq!(on_disk.prices["100300"], 1.5);
+        assert_eq!(on_disk.source, "mock");
+
+        let second = update(
+            &source,
+            &path,
+            hours(1),
+            false,
+            now + chrono::Duration::minutes(30),
+        )
+        .unwrap();
+        assert!(matches!(second, PriceUpdate::Cached(_)));
+        assert_eq!(source.calls.get(), 1);
+
+        let forced = update(&source, &path, hours(1), true, now).unwrap();
+        assert!(matches!(forced, PriceUpdate::Fetched(_)));
+        assert_eq!(source.calls.get(), 2);
+    }
+
+    #[test]
+    fn test_update_falls_back_to_stale_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prices.json");
+        let now = Utc::now();
+        update(
+            &MockSource::new(Some(r#"{"Ember": 0.2}"#)),
+            &path,
+            hours(1),
+            false,
+            now,
+        )
+        .unwrap();
+
+        let offline = MockSource::new(None);
+        let result = update(&offline, &path, hours(1), false, now + hours(2)).unwrap();
+        assert!(matches!(result, PriceUpdate::Fallback(_, _)));
+        assert_eq!(result.cache().lookup(None, "ember"), Some(0.2));
+    }
+
+    #[test]
+    fn test_update_without_cache_reports_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prices.json");
+        let err = update(&MockSource::new(None), &path, hours(1), false, Utc::now()).unwrap_err();
+        assert!(format!("{err:#}").contains("connection refused"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_lookup_prefers_id_over_name() {
+        let cache = PriceCache {
+            fetched_at: Utc::now(),
+            source: "test".to_string(),
+            prices: HashMap::from([
+                ("100300".to_string(), 1.0),
+                ("Flame Elementium".to_string(), 2.0),
+            ]),
+        };
+        assert_eq!(cache.lookup(Some("100300"), "Flame Elementium"), Some(1.0));
+        assert_eq!(cache.lookup(Some("999"), "flame elementium"), Some(2.0));
+        assert_eq!(cache.lookup(None, "Unknown"), None);
+    }
+}
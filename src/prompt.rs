@@ -0,0 +1,245 @@
+use std::io::{self, IsTerminal};
+
+use dialoguer::{Completion, Input};
+
+use crate::log_parser;
+use crate::models::DropItem;
+
+/// Parse a drop quantity: a whole number of at least 1.
+pub fn parse_quantity(input: &str) -> Result<u32, String> {
+    match input.trim().parse::<u32>() {
+        Ok(0) => Err("Quantity must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(_) => Err(format!("'{}' is not a whole number", input.trim())),
+    }
+}
+
+/// Parse a drop value: a finite, non-negative number.
+pub fn parse_value(input: &str) -> Result<f64, String> {
+    match input.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() && v >= 0.0 => Ok(v),
+        Ok(_) => Err("Value must be a non-negative number".to_string()),
+        Err(_) => Err(format!("'{}' is not a number", input.trim())),
+    }
+}
+
+/// Complete `input` against `names` (case-insensitive prefix match).
+///
+/// A single match completes to that name; several matches complete to
+/// their longest common prefix when it extends the input.
+pub fn complete_name(input: &str, names: &[&str]) -> Option<String> {
+    if input.is_empty() {
+        return None;
+    }
+    let needle = input.to_lowercase();
+    let matches: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|n| n.to_lowercase().starts_with(&needle))
+        .collect();
+    let first = *matches.first()?;
+    let prefix_len = matches[1..]
+        .iter()
+        .map(|other| {
+            first
+                .chars()
+                .zip(other.chars())
+                .take_while(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+                .map(|(a, _)| a.len_utf8())
+                .sum()
+        })
+        .min()
+        .unwrap_or(first.len());
+    let prefix = &first[..prefix_len];
+    (prefix.chars().count() > input.chars().count()).then(|| prefix.to_string())
+}
+
+/// Parse a pasted drop list, one `name,quantity,value` (or `name,value`)
+/// per line. Blank lines, `#` comments and a `name,quantity,value` header
+/// are skipped.
+///
+/// Any invalid line fails the whole list, reported with its 1-based
+/// line number.
+pub fn parse_drop_list(input: &str) -> Result<Vec<DropItem>, String> {
+    let mut drops = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.eq_ignore_ascii_case("name,quantity,value")
+        {
+            continue;
+        }
+        let drop = parse_drop_line(line).map_err(|e| format!("line {}: {}", idx + 1, e))?;
+        drops.push(drop);
+    }
+    Ok(drops)
+}
+
+fn parse_drop_line(line: &str) -> Result<DropItem, String> {
+    let fields: Vec<&str> = line.split(',').collect();
+    let (name, quantity, value) = match fields.as_slice() {
+        [name, quantity, value] => (*name, parse_quantity(quantity)?, *value),
+        [name, value] => (*name, 1, *value),
+        _ => return Err("expected name,quantity,value".to_string()),
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("item name is empty".to_string());
+    }
+    Ok(DropItem {
+        name: name.to_string(),
+        quantity,
+        value: parse_value(value)?,
+        added_at: None,
+    })
+}
+
+struct ItemCompletion {
+    names: Vec<&'static str>,
+}
+
+impl Completion for ItemCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        complete_name(input, &self.names)
+    }
+}
+
+/// Prompt for whichever of name, quantity and value were not given on the
+/// command line. Invalid numbers are rejected and asked for again.
+///
+/// `known_price` supplies a default value for the chosen item, if any.
+pub fn prompt_drop(
+    name: Option<String>,
+    quantity: Option<u32>,
+    value: Option<f64>,
+    known_price: impl Fn(&str) -> Option<f64>,
+) -> io::Result<(String, u32, f64)> {
+    if !io::stdin().is_terminal() {
+        return Err(io::Error::other(
+            "--name and --value are required when stdin is not a terminal",
+        ));
+    }
+
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let completion = ItemCompletion {
+                names: log_parser::item_names(),
+            };
+            Input::<String>::new()
+                .with_prompt("Item name (Tab to complete)")
+                .completion_with(&completion)
+                .validate_with(|s: &String| {
+                    if s.trim().is_empty() {
+                        Err("Name cannot be empty")
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact_text()
+                .map_err(io::Error::other)?
+                .trim()
+                .to_string()
+        }
+    };
+    let quantity = match quantity {
+        Some(q) => q,
+        None => ask("Quantity", Some("1"), parse_quantity)?,
+    };
+    let value = match value {
+        Some(v) => v,
+        None => {
+            let default = known_price(&name).map(|p| p.to_string());
+            ask("Value (per item)", default.as_deref(), parse_value)?
+        }
+    };
+    Ok((name, quantity, value))
+}
+
+/// Ask until `parse` accepts the answer.
+fn ask<T>(
+    prompt: &str,
+    default: Option<&str>,
+    parse: fn(&str) -> Result<T, String>,
+) -> io::Result<T> {
+    let mut input = Input::<String>::new()
+        .with_prompt(prompt)
+        .validate_with(|s: &String| parse(s).map(|_| ()));
+    if let Some(default) = default {
+        input = input.default(default.to_string());
+    }
+    let answer = input.interact_text().map_err(io::Error::other)?;
+    parse(&answer).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("3"), Ok(3));
+        assert_eq!(parse_quantity(" 12 "), Ok(12));
+        assert!(parse_quantity("0").is_err());
+        assert!(parse_quantity("-1").is_err());
+        assert!(parse_quantity("1.5").is_err());
+        assert!(parse_quantity("abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("18.5"), Ok(18.5));
+        assert_eq!(parse_value("0"), Ok(0.0));
+        assert!(parse_value("-2").is_err());
+        assert!(parse_value("NaN").is_err());
+        assert!(parse_value("inf").is_err());
+        assert!(parse_value("").is_err());
+    }
+
+    #[test]
+    fn test_parse_drop_list() {
+        let input = "name,quantity,value\n\
+                     # after the boss\n\
+                     Flame Core, 2, 18.5\n\
+                     \n\
+                     Ember,3\n";
+        let drops = parse_drop_list(input).unwrap();
+        assert_eq!(drops.len(), 2);
+        assert_eq!(drops[0].name, "Flame Core");
+        assert_eq!(drops[0].quantity, 2);
+        assert_eq!(drops[0].value, 18.5);
+        assert_eq!((drops[1].name.as_str(), drops[1].quantity), ("Ember", 1));
+        assert_eq!(drops[1].value, 3.0);
+    }
+
+    #[test]
+    fn test_parse_drop_list_reports_bad_line() {
+        let err = parse_drop_list("Flame Core,2,18.5\nEmber,1,lots\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        assert!(parse_drop_list("just a name")
+            .unwrap_err()
+            .starts_with("line 1:"));
+        assert!(parse_drop_list(",2,3").unwrap_err().contains("empty"));
+    }
+
+    #[test]
+    fn test_complete_name() {
+        let names = ["Flame Core", "Flame Elementium", "Frost Shard"];
+        assert_eq!(complete_name("fr", &names).as_deref(), Some("Frost Shard"));
+        assert_eq!(complete_name("fl", &names).as_deref(), Some("Flame "));
+        assert_eq!(complete_name("Flame ", &names), None);
+        assert_eq!(complete_name("x", &names), None);
+        assert_eq!(complete_name("", &names), None);
+    }
+
+    #[test]
+    fn test_item_db_names_are_completable() {
+        let names = log_parser::item_names();
+        assert!(names.contains(&"Flame Elementium"));
+        assert_eq!(
+            complete_name("flame eleme", &names).as_deref(),
+            Some("Flame Elementium")
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::log_parser::{self, LogEvent, LootSummary};
+use crate::log_source::{FileLogSource, LogEncoding, LogSource};
+use crate::storage;
+
+/// Copy the loot baseline already in the log to `out`, then append every
+/// new chunk the game writes, polling every `interval` until the process
+/// is interrupted.
+pub fn run(log: &Path, out: &Path, interval: Duration) -> io::Result<()> {
+    let mut source = FileLogSource::new(log);
+    let mut file = File::create(out)?;
+    record(&mut source, &mut file, interval, None)
+}
+
+/// Write the baseline excerpt from the first read of `source`, then poll it
+/// `polls` more times (forever if `None`), appending what it returns.
+fn record(
+    source: &mut dyn LogSource,
+    out: &mut dyn Write,
+    interval: Duration,
+    polls: Option<usize>,
+) -> io::Result<()> {
+    let existing = source.read_new()?;
+    out.write_all(log_parser::baseline_excerpt(&existing).as_bytes())?;
+    out.flush()?;
+    let mut done = 0;
+    while polls.is_none_or(|n| done < n) {
+        thread::sleep(interval);
+        let chunk = source.read_new()?;
+        if !chunk.is_empty() {
+            out.write_all(chunk.as_bytes())?;
+            out.flush()?;
+        }
+        done += 1;
+    }
+    Ok(())
+}
+
+/// What a recorded log excerpt reconstructs to.
+#[derive(Debug, Serialize)]
+pub struct Replay {
+    pub lines: usize,
+    pub events: usize,
+    /// Maps entered, in order.
+    pub maps: Vec<String>,
+    pub loot: LootSummary,
+}
+
+/// Feed a recorded excerpt back through the parser.
+pub fn replay(path: &Path, loot_contexts: &[String]) -> io::Result<Replay> {
+    let bytes = fs::read(path).map_err(|e| storage::path_context(e, "read log excerpt", path))?;
+    let (text, _) = LogEncoding::detect(&bytes).decode(&bytes);
+    let mut events = 0;
+    let mut maps = Vec::new();
+    for line in text.lines() {
+        match log_parser::parse_line(line) {
+            Some(LogEvent::Map(m)) => {
+                events += 1;
+                maps.push(m.name().to_string());
+            }
+            Some(_) => events += 1,
+            None => {}
+        }
+    }
+    Ok(Replay {
+        lines: text.lines().count(),
+        events,
+        maps,
+        loot: log_parser::parse_loot_from_log(path, loot_contexts)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use std::collections::VecDeque;
+
+    /// Hands out one prepared chunk per read.
+    struct Chunks(VecDeque<&'static str>);
+
+    impl LogSource for Chunks {
+        fn name(&self) -> String {
+            "chunks".to_string()
+        }
+
+        fn read_new(&mut self) -> io::Result<String> {
+            Ok(self.0.pop_front().unwrap_or_default().to_string())
+        }
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let mut source = Chunks(VecDeque::from([
+            "BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 400\n\
+             ItemChange@ ProtoName=ResetItemsLayout end\n\
+             BagMgr@:InitBagData PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 500\n",
+            "",
+            "ItemChange@ ProtoName=PickItems start\n\
+             BagMgr@:Modfy BagItem PageId = 102 SlotId = 0 ConfigBaseId = 100300 Num = 550\n\
+             ItemChange@ ProtoName=PickItems end\n",
+        ]));
+        let mut recorded = Vec::new();
+        record(&mut source, &mut recorded, Duration::ZERO, Some(2)).unwrap();
+        let text = String::from_utf8(recorded).unwrap();
+        assert!(text.starts_with("ItemChange@ ProtoName=ResetItemsLayout end"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("excerpt.log");
+        fs::write(&path, text).unwrap();
+        let replay = replay(&path, &Settings::default().loot_contexts).unwrap();
+        assert_eq!(replay.lines, 5);
+        assert_eq!(replay.events, 5);
+        assert_eq!(replay.loot.items.len(), 1);
+        assert_eq!(replay.loot.items[0].delta, 50);
+        assert_eq!(replay.loot.items[0].current, 550);
+    }
+}
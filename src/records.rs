@@ -0,0 +1,311 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::models::Session;
+use crate::storage;
+use crate::viewmodel::{self, NumberLocale};
+
+/// Sessions with less run time than this are too short to set an FE/hour record.
+const MIN_RATE_SECS: f64 = 60.0;
+
+/// One personal best and where it was set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub value: f64,
+    pub session_id: String,
+    pub map: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Lifetime bests, stored in `records.json` next to the sessions file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Records {
+    pub best_fe_per_hour: Option<Record>,
+    pub best_run_fe: Option<Record>,
+    pub longest_session: Option<Record>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    FePerHour,
+    RunFe,
+    LongestSession,
+}
+
+impl RecordKind {
+    pub const ALL: [RecordKind; 3] = [
+        RecordKind::FePerHour,
+        RecordKind::RunFe,
+        RecordKind::LongestSession,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordKind::FePerHour => "Best FE/hour",
+            RecordKind::RunFe => "Best run FE",
+            RecordKind::LongestSession => "Longest session",
+        }
+    }
+
+    pub fn format_value(self, value: f64, locale: &NumberLocale) -> String {
+        match self {
+            RecordKind::FePerHour | RecordKind::RunFe => {
+                viewmodel::format_count(value.round() as i64, locale)
+            }
+            RecordKind::LongestSession => {
+                let secs = value.max(0.0) as u64;
+                format!("{}h {:02}m", secs / 3600, secs / 60 % 60)
+            }
+        }
+    }
+}
+
+/// Whether `candidate` sets a new record over `current`.
+///
+/// The first value ever seen is a record; after that only a strictly greater
+/// value wins, so a tie keeps the record with whoever set it first.
+pub fn beats(current: Option<&Record>, candidate: f64) -> bool {
+    match current {
+        None => candidate > 0.0,
+        Some(record) => candidate > record.value,
+    }
+}
+
+impl Records {
+    pub fn get(&self, kind: RecordKind) -> Option<&Record> {
+        match kind {
+            RecordKind::FePerHour => self.best_fe_per_hour.as_ref(),
+            RecordKind::RunFe => self.best_run_fe.as_ref(),
+            RecordKind::LongestSession => self.longest_session.as_ref(),
+        }
+    }
+
+    fn slot(&mut self, kind: RecordKind) -> &mut Option<Record> {
+        match kind {
+            RecordKind::FePerHour => &mut self.best_fe_per_hour,
+            RecordKind::RunFe => &mut self.best_run_fe,
+            RecordKind::LongestSession => &mut self.longest_session,
+        }
+    }
+
+    /// Fold an ended session into the records; returns the records it broke.
+    ///
+    /// Active sessions are ignored. FE records only come from sessions with
+    /// runs recorded by the GUI/TUI.
+    pub fn update(&mut self, session: &Session) -> Vec<RecordKind> {
+        let Some(end) = session.end_time else {
+            return Vec::new();
+        };
+        let mut candidates = Vec::new();
+
+        let run_secs: f64 = session.runs.iter().map(|r| r.duration_secs).sum();
+        if run_secs >= MIN_RATE_SECS {
+            let fe: i64 = session.runs.iter().map(|r| r.fe).sum();
+            candidates.push((
+                RecordKind::FePerHour,
+                fe as f64 / run_secs * 3600.0,
+                session.map.clone(),
+            ));
+        }
+        // The first of several equally good runs holds the record.
+        let best_run = session
+            .runs
+            .iter()
+            .reduce(|best, run| if run.fe > best.fe { run } else { best });
+        if let Some(run) = best_run {
+            candidates.push((RecordKind::RunFe, run.fe as f64, run.map.clone()));
+        }
+        let secs = (end - session.start_time).num_milliseconds() as f64 / 1000.0;
+        candidates.push((RecordKind::LongestSession, secs, session.map.clone()));
+
+        let mut broken = Vec::new();
+        for (kind, value, map) in candidates {
+            if beats(self.get(kind), value) {
+                *self.slot(kind) = Some(Record {
+                    value,
+                    session_id: session.id.clone(),
+                    map,
+                    at: end,
+                });
+                broken.push(kind);
+            }
+        }
+        broken
+    }
+
+    /// Fold every session in; returns true if any record changed.
+    pub fn update_all(&mut self, sessions: &[Session]) -> bool {
+        let mut changed = false;
+        for session in sessions {
+            changed |= !self.update(session).is_empty();
+        }
+        changed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        RecordKind::ALL.iter().all(|&kind| self.get(kind).is_none())
+    }
+}
+
+/// Plain-text record lines for `Stats`.
+pub fn records_table(records: &Records, locale: &NumberLocale) -> String {
+    let mut out = format!("{:<24} {:>10}  {:<16} {}\n", "Record", "Best", "Map", "Set");
+    for kind in RecordKind::ALL {
+        if let Some(r) = records.get(kind) {
+            out.push_str(&format!(
+                "{:<24} {:>10}  {:<16} {}\n",
+                kind.label(),
+                kind.format_value(r.value, locale),
+                r.map,
+                r.at.with_timezone(&Local).format("%Y-%m-%d")
+            ));
+        }
+    }
+    out
+}
+
+/// Where `records.json` lives: next to the sessions file.
+pub fn records_path(data_file: &Path) -> PathBuf {
+    data_file.with_file_name("records.json")
+}
+
+/// Load records from `path`; a missing or unreadable file starts afresh.
+pub fn load(path: &Path) -> Records {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!(path = %path.display(), error = %e, "invalid records file; starting afresh");
+            Records::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Records::default(),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to read records; starting afresh");
+            Records::default()
+        }
+    }
+}
+
+pub fn save(path: &Path, records: &Records) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(records).map_err(io::Error::other)?;
+    fs::write(path, json).map_err(|e| storage::path_context(e, "save records to", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RunRecord;
+    use chrono::Duration;
+
+    fn ended_session(id: &str, minutes: i64, runs: &[(&str, f64, i64)]) -> Session {
+        let start: DateTime<Utc> = "2026-10-16T10:00:00Z".parse().unwrap();
+        Session {
+            id: id.to_string(),
+            map: "Abyss".to_string(),
+            notes: None,
+            start_time: start,
+            end_time: Some(start + Duration::minutes(minutes)),
+            drops: Vec::new(),
+            runs: runs
+                .iter()
+                .map(|&(map, duration_secs, fe)| RunRecord {
+                    map: map.to_string(),
+                    duration_secs,
+                    fe,
+                    items: 0,
+                    started_at: None,
+                    ended_at: None,
+                    tier: None,
+                })
+                .collect(),
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    fn record(value: f64) -> Record {
+        Record {
+            value,
+            session_id: "old".to_string(),
+            map: "Abyss".to_string(),
+            at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_beats_first_record_and_ties() {
+        assert!(beats(None, 10.0));
+        assert!(!beats(None, 0.0));
+        assert!(beats(Some(&record(10.0)), 10.5));
+        assert!(!beats(Some(&record(10.0)), 10.0));
+        assert!(!beats(Some(&record(10.0)), 9.0));
+    }
+
+    #[test]
+    fn test_first_session_sets_every_record() {
+        let mut records = Records::default();
+        let session = ended_session("a", 60, &[("Abyss", 600.0, 100), ("Rift", 600.0, 300)]);
+        let broken = records.update(&session);
+        assert_eq!(broken, RecordKind::ALL.to_vec());
+
+        let rate = records.best_fe_per_hour.as_ref().unwrap();
+        assert_eq!(rate.value, 1200.0);
+        let run = records.best_run_fe.as_ref().unwrap();
+        assert_eq!((run.value, run.map.as_str()), (300.0, "Rift"));
+        assert_eq!(records.longest_session.as_ref().unwrap().value, 3600.0);
+    }
+
+    #[test]
+    fn test_tie_keeps_existing_holder() {
+        let mut records = Records::default();
+        records.update(&ended_session("a", 60, &[("Abyss", 600.0, 100)]));
+        let broken = records.update(&ended_session("b", 60, &[("Abyss", 600.0, 100)]));
+        assert!(broken.is_empty());
+        for kind in RecordKind::ALL {
+            assert_eq!(records.get(kind).unwrap().session_id, "a");
+        }
+    }
+
+    #[test]
+    fn test_only_beaten_records_change() {
+        let mut records = Records::default();
+        records.update(&ended_session("a", 60, &[("Abyss", 600.0, 100)]));
+        let broken = records.update(&ended_session("b", 90, &[("Abyss", 600.0, 50)]));
+        assert_eq!(broken, vec![RecordKind::LongestSession]);
+        assert_eq!(records.best_run_fe.as_ref().unwrap().session_id, "a");
+        assert_eq!(records.longest_session.as_ref().unwrap().session_id, "b");
+    }
+
+    #[test]
+    fn test_active_and_short_sessions() {
+        let mut records = Records::default();
+        let mut active = ended_session("a", 60, &[("Abyss", 600.0, 100)]);
+        active.end_time = None;
+        assert!(records.update(&active).is_empty());
+
+        // Too little run time for a rate, but the run and length still count.
+        let short = ended_session("b", 1, &[("Abyss", 30.0, 500)]);
+        assert_eq!(
+            records.update(&short),
+            vec![RecordKind::RunFe, RecordKind::LongestSession]
+        );
+        assert!(records.best_fe_per_hour.is_none());
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = records_path(&dir.path().join("sessions.json"));
+        assert_eq!(load(&path), Records::default());
+
+        let mut records = Records::default();
+        records.update(&ended_session("a", 60, &[("Abyss", 600.0, 100)]));
+        save(&path, &records).unwrap();
+        assert_eq!(load(&path), records);
+    }
+}
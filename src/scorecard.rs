@@ -0,0 +1,273 @@
+use std::path::Path;
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use chrono::{DateTime, Utc};
+use image::{Rgba, RgbaImage};
+
+use crate::log_parser;
+use crate::models::Session;
+use crate::viewmodel::{format_clock, format_count, format_number, NumberLocale};
+
+/// Scorecard image size in pixels.
+pub const WIDTH: u32 = 640;
+pub const HEIGHT: u32 = 360;
+
+/// How many drops the "top items" list shows.
+const TOP_ITEMS: usize = 5;
+
+const BACKGROUND: Rgba<u8> = Rgba([12, 12, 12, 255]);
+const BRIGHT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const DIM: Rgba<u8> = Rgba([130, 130, 130, 255]);
+const RULE: Rgba<u8> = Rgba([45, 45, 45, 255]);
+
+/// Everything a scorecard shows, independent of how it is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scorecard {
+    pub title: String,
+    pub subtitle: String,
+    /// Headline stats as (label, value), drawn as boxes left to right.
+    pub stats: Vec<(String, String)>,
+    /// Most valuable drops as (name, total value).
+    pub top_items: Vec<(String, String)>,
+}
+
+impl Scorecard {
+    /// Collect the scorecard of `session`; an active session is measured up
+    /// to `now`. `primary` is the ConfigBaseId recorded in its runs; numbers
+    /// are written for `locale`, values to `precision` decimals.
+    pub fn from_session(
+        session: &Session,
+        primary: &str,
+        precision: usize,
+        locale: &NumberLocale,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let value = |v: f64| format_number(v, precision, locale);
+        let end = session.end_time.unwrap_or(now);
+        let secs = (end - session.start_time).num_seconds().max(0) as f64;
+        let currency = log_parser::currency_label(primary);
+        let gained: i64 = session.runs.iter().map(|r| r.fe).sum();
+        let per_hour = if secs < 1.0 {
+            0
+        } else {
+            (gained as f64 / secs * 3600.0).round() as i64
+        };
+
+        let mut drops: Vec<_> = session.drops.iter().collect();
+        drops.sort_by(|a, b| {
+            let value = |d: &crate::models::DropItem| d.value * d.quantity as f64;
+            value(b).total_cmp(&value(a))
+        });
+        let top_items = drops
+            .into_iter()
+            .take(TOP_ITEMS)
+            .map(|d| {
+                (
+                    format!("{} x{}", d.name, d.quantity),
+                    value(d.value * d.quantity as f64),
+                )
+            })
+            .collect();
+
+        Self {
+            title: session.map.clone(),
+            subtitle: format!(
+                "{}{}",
+                session.start_time.format("%Y-%m-%d %H:%M UTC"),
+                if session.is_active() {
+                    "  (active)"
+                } else {
+                    ""
+                }
+            ),
+            stats: vec![
+                ("TIME".to_string(), format_clock(secs)),
+                (currency.to_uppercase(), format_count(gained, locale)),
+                (
+                    format!("{}/HR", currency.to_uppercase()),
+                    format_count(per_hour, locale),
+                ),
+                ("VALUE".to_string(), value(session.total_value())),
+            ],
+            top_items,
+        }
+    }
+
+    /// Draw the scorecard onto a `WIDTH` x `HEIGHT` image.
+    pub fn render(&self) -> RgbaImage {
+        let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+            .expect("bundled font is valid");
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+
+        draw_text(&mut img, &font, &self.title, 24.0, 28.0, 30.0, BRIGHT);
+        draw_text(&mut img, &font, &self.subtitle, 24.0, 66.0, 14.0, DIM);
+        fill_rect(&mut img, 24, 92, WIDTH - 48, 1, RULE);
+
+        let box_width = (WIDTH - 48) / self.stats.len().max(1) as u32;
+        for (i, (label, value)) in self.stats.iter().enumerate() {
+            let x = 24.0 + (i as u32 * box_width) as f32;
+            draw_text(&mut img, &font, label, x, 108.0, 13.0, DIM);
+            draw_text(&mut img, &font, value, x, 126.0, 26.0, BRIGHT);
+        }
+        fill_rect(&mut img, 24, 172, WIDTH - 48, 1, RULE);
+
+        draw_text(&mut img, &font, "TOP ITEMS", 24.0, 186.0, 13.0, DIM);
+        if self.top_items.is_empty() {
+            draw_text(&mut img, &font, "No drops recorded", 24.0, 210.0, 16.0, DIM);
+        }
+        for (i, (name, value)) in self.top_items.iter().enumerate() {
+            let y = 210.0 + i as f32 * 26.0;
+            draw_text(&mut img, &font, name, 24.0, y, 16.0, BRIGHT);
+            let width = text_width(&font, value, 16.0);
+            draw_text(
+                &mut img,
+                &font,
+                value,
+                WIDTH as f32 - 24.0 - width,
+                y,
+                16.0,
+                BRIGHT,
+            );
+        }
+        img
+    }
+
+    /// Render and write the scorecard as a PNG.
+    pub fn save_png(&self, path: &Path) -> image::ImageResult<()> {
+        self.render()
+            .save_with_format(path, image::ImageFormat::Png)
+    }
+}
+
+fn fill_rect(img: &mut RgbaImage, x: u32, y: u32, w: u32, h: u32, color: Rgba<u8>) {
+    for py in y..(y + h).min(img.height()) {
+        for px in x..(x + w).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn text_width(font: &FontRef<'_>, text: &str, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    text.chars()
+        .map(|c| scaled.h_advance(font.glyph_id(c)))
+        .sum()
+}
+
+/// Draw `text` with its top-left corner at (`x`, `y`), blending glyph
+/// coverage over the existing pixels. Text past the right edge is clipped.
+fn draw_text(
+    img: &mut RgbaImage,
+    font: &FontRef<'_>,
+    text: &str,
+    x: f32,
+    y: f32,
+    size: f32,
+    color: Rgba<u8>,
+) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let mut caret = x;
+    for c in text.chars() {
+        let glyph = font
+            .glyph_id(c)
+            .with_scale_and_position(scale, ab_glyph::point(caret, y + scaled.ascent()));
+        caret += scaled.h_advance(glyph.id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= img.width() as i32 || py >= img.height() as i32 {
+                return;
+            }
+            let pixel = img.get_pixel_mut(px as u32, py as u32);
+            for ch in 0..3 {
+                let under = pixel.0[ch] as f32;
+                pixel.0[ch] = (under + (color.0[ch] as f32 - under) * coverage).round() as u8;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::FLAME_ELEMENTIUM_ID;
+    use crate::models::{DropItem, RunRecord};
+
+    fn sample() -> Session {
+        let start: DateTime<Utc> = "2024-05-01T10:00:00Z".parse().unwrap();
+        Session {
+            id: "abc".to_string(),
+            map: "Netherrealm".to_string(),
+            notes: None,
+            start_time: start,
+            end_time: Some(start + chrono::Duration::minutes(30)),
+            drops: (1..=7)
+                .map(|i| DropItem {
+                    name: format!("Item {i}"),
+                    quantity: i,
+                    value: 1.5,
+                    added_at: None,
+                })
+                .collect(),
+            runs: vec![RunRecord {
+                map: "Netherrealm".to_string(),
+                duration_secs: 1800.0,
+                fe: 1200,
+                items: 5,
+                started_at: None,
+                ended_at: None,
+                tier: None,
+            }],
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    fn sample_card(locale: &NumberLocale) -> Scorecard {
+        Scorecard::from_session(&sample(), FLAME_ELEMENTIUM_ID, 2, locale, Utc::now())
+    }
+
+    #[test]
+    fn test_scorecard_data() {
+        let card = sample_card(&NumberLocale::US);
+        assert_eq!(card.title, "Netherrealm");
+        assert_eq!(card.subtitle, "2024-05-01 10:00 UTC");
+        let stats: Vec<_> = card.stats.iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(stats, ["30:00", "1,200", "2,400", "42.00"]);
+        assert_eq!(card.stats[1].0, "FE");
+        // Highest total value first, capped at five.
+        assert_eq!(card.top_items.len(), TOP_ITEMS);
+        assert_eq!(
+            card.top_items[0],
+            ("Item 7 x7".to_string(), "10.50".to_string())
+        );
+
+        // One number style across the card.
+        let stats: Vec<_> = sample_card(&NumberLocale::EU)
+            .stats
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(stats, ["30:00", "1.200", "2.400", "42,00"]);
+    }
+
+    #[test]
+    fn test_scorecard_png_has_expected_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("card.png");
+        let card = sample_card(&NumberLocale::US);
+        card.save_png(&path).unwrap();
+
+        let img = image::open(&path).unwrap();
+        assert_eq!((img.width(), img.height()), (WIDTH, HEIGHT));
+        // Something other than the background was drawn.
+        let rgba = img.to_rgba8();
+        assert!(rgba.pixels().any(|p| *p != BACKGROUND));
+    }
+}
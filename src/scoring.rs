@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::log_parser::LootSummary;
+
+/// How a loot total is worked out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreMode {
+    /// Net number of items.
+    #[default]
+    Count,
+    /// Net items times their price; the primary currency is worth 1 unless
+    /// priced, other unpriced items are worth nothing.
+    Priced,
+    /// Net items times a per-item weight; unweighted items weigh 1.
+    Weighted,
+}
+
+impl ScoreMode {
+    pub const ALL: [ScoreMode; 3] = [ScoreMode::Count, ScoreMode::Priced, ScoreMode::Weighted];
+
+    /// Name used in `settings.json` and on the command line.
+    pub fn key(self) -> &'static str {
+        match self {
+            ScoreMode::Count => "count",
+            ScoreMode::Priced => "priced",
+            ScoreMode::Weighted => "weighted",
+        }
+    }
+
+    /// Label for the total in tables and the GUI.
+    pub fn label(self) -> &'static str {
+        match self {
+            ScoreMode::Count => "Items",
+            ScoreMode::Priced => "Value",
+            ScoreMode::Weighted => "Score",
+        }
+    }
+}
+
+impl std::str::FromStr for ScoreMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ScoreMode::ALL
+            .into_iter()
+            .find(|mode| mode.key() == s.trim())
+            .ok_or_else(|| {
+                let keys: Vec<_> = ScoreMode::ALL.iter().map(|m| m.key()).collect();
+                format!(
+                    "unknown score mode {:?} (expected one of {})",
+                    s,
+                    keys.join(", ")
+                )
+            })
+    }
+}
+
+/// What [`score`] needs: the mode, the primary currency and the per-item
+/// prices and weights, by ConfigBaseId.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoringConfig<'a> {
+    pub mode: ScoreMode,
+    pub primary: &'a str,
+    pub prices: &'a BTreeMap<String, f64>,
+    pub weights: &'a BTreeMap<String, f64>,
+}
+
+impl ScoringConfig<'_> {
+    /// What `n` of the item `config_base_id` add to the total.
+    fn item_score(&self, config_base_id: &str, n: i64) -> f64 {
+        let factor = match self.mode {
+            ScoreMode::Count => 1.0,
+            ScoreMode::Priced => match self.prices.get(config_base_id) {
+                Some(&price) => price,
+                None if config_base_id == self.primary => 1.0,
+                None => 0.0,
+            },
+            ScoreMode::Weighted => self.weights.get(config_base_id).copied().unwrap_or(1.0),
+        };
+        n as f64 * factor
+    }
+}
+
+/// Total of the net item changes `deltas` (id, amount) under `config`.
+pub fn score_deltas<'a>(
+    deltas: impl IntoIterator<Item = (&'a str, i64)>,
+    config: &ScoringConfig,
+) -> f64 {
+    deltas
+        .into_iter()
+        .map(|(id, n)| config.item_score(id, n))
+        .sum()
+}
+
+/// Total of the loot in `summary` under `config`.
+pub fn score(summary: &LootSummary, config: &ScoringConfig) -> f64 {
+    score_deltas(
+        summary
+            .items
+            .iter()
+            .map(|i| (i.config_base_id.as_str(), i.delta)),
+        config,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::{ItemDelta, ParseAnomalies};
+
+    fn summary() -> LootSummary {
+        let item = |id: &str, delta| ItemDelta {
+            config_base_id: id.to_string(),
+            item_name: crate::log_parser::item_name(id),
+            delta,
+            current: 0,
+        };
+        LootSummary {
+            items: vec![item("100300", 120), item("6002", 3), item("6003", -1)],
+            total_events: 3,
+            baseline_found: true,
+            events_seen: true,
+            last_sort_at: None,
+            sorts_seen: 1,
+            anomalies: ParseAnomalies::default(),
+            game_start: None,
+        }
+    }
+
+    #[test]
+    fn test_score_modes_over_same_loot() {
+        let prices = BTreeMap::from([("6002".to_string(), 10.0), ("6003".to_string(), 4.0)]);
+        let weights = BTreeMap::from([("100300".to_string(), 0.5), ("6002".to_string(), 2.0)]);
+        let config = |mode| ScoringConfig {
+            mode,
+            primary: "100300",
+            prices: &prices,
+            weights: &weights,
+        };
+        let loot = summary();
+        assert_eq!(score(&loot, &config(ScoreMode::Count)), 122.0);
+        // Flame Elementium is the primary currency: 120 * 1 + 3 * 10 + -1 * 4.
+        assert_eq!(score(&loot, &config(ScoreMode::Priced)), 146.0);
+        // A set price for the primary currency wins.
+        let priced = BTreeMap::from([("100300".to_string(), 0.5), ("6002".to_string(), 10.0)]);
+        let own_price = ScoringConfig {
+            prices: &priced,
+            ..config(ScoreMode::Priced)
+        };
+        assert_eq!(score(&loot, &own_price), 90.0);
+        // 120 * 0.5 + 3 * 2 + -1 * 1 (unweighted).
+        assert_eq!(score(&loot, &config(ScoreMode::Weighted)), 65.0);
+        assert_eq!(score_deltas([], &config(ScoreMode::Priced)), 0.0);
+    }
+
+    #[test]
+    fn test_score_mode_keys_round_trip() {
+        for mode in ScoreMode::ALL {
+            assert_eq!(mode.key().parse::<ScoreMode>(), Ok(mode));
+        }
+        assert!("value".parse::<ScoreMode>().is_err());
+    }
+}
@@ -0,0 +1,574 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::log_parser::FLAME_ELEMENTIUM_ID;
+use crate::scoring::{ScoreMode, ScoringConfig};
+use crate::storage;
+use crate::tracker::POLL_INTERVAL;
+use crate::viewmodel::{ItemDisplay, NumberLocale, StatKind, Tab};
+
+/// User preferences stored in `settings.json` next to the sessions file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// ConfigBaseId of the headline currency (Flame Elementium by default).
+    pub primary_currency: String,
+    /// Group digits of large counts, e.g. `1,234,567`.
+    pub group_digits: bool,
+    pub thousands_separator: char,
+    /// Decimal mark; unset means `,` when the thousands separator is `.`
+    /// and `.` otherwise, as before it could be set.
+    pub decimal_separator: Option<char>,
+    /// Shorten large numbers in the session stat boxes (`123.5k`, `1.5m`).
+    pub abbreviate_numbers: bool,
+    /// Decimals shown on rates such as FE/hour.
+    pub rate_precision: usize,
+    /// Decimals shown on drop values and session totals.
+    pub value_precision: usize,
+    /// Completed runs averaged by the recent-pace stat.
+    pub recent_runs: usize,
+    /// Warn when the recent pace stays below this many per hour.
+    pub pace_floor: Option<f64>,
+    /// Consecutive completed runs the pace must stay below the floor.
+    pub pace_alert_runs: usize,
+    /// Primary currency per hour to colour the session rate against.
+    pub target_per_hour: Option<f64>,
+    /// Width of the FE-per-run histogram buckets.
+    pub histogram_bucket: u64,
+    /// Daily FE goal per map name.
+    pub map_goals: BTreeMap<String, i64>,
+    /// End an active CLI session left running this many hours (0 turns it
+    /// off) once it has been idle for `auto_end_idle_mins`.
+    pub auto_end_hours: u64,
+    pub auto_end_idle_mins: u64,
+    /// Ended sessions shorter than this are left out of stats (0 keeps all).
+    pub min_session_secs: u64,
+    /// Read the game log from this URL instead of a local file.
+    pub log_url: Option<String>,
+    /// Seconds between log polls in the GUI/TUI.
+    pub poll_interval_secs: u64,
+    /// ConfigBaseIds hidden from loot tables.
+    pub ignored_items: BTreeSet<String>,
+    /// Whether ignored items still count toward item totals.
+    pub count_ignored: bool,
+    /// ConfigBaseIds always listed first in loot tables.
+    pub pinned_items: BTreeSet<String>,
+    /// Whether loot tables show item names, ids or both.
+    pub item_display: ItemDisplay,
+    /// The user's own names per ConfigBaseId, shown instead of the official
+    /// name in loot and inventory tables.
+    pub item_aliases: BTreeMap<String, String>,
+    /// Prices set by hand per ConfigBaseId; these win over the price cache.
+    pub item_prices: BTreeMap<String, f64>,
+    /// How loot totals are worked out: item count, priced value or weighted score.
+    pub score_mode: ScoreMode,
+    /// Per-ConfigBaseId weights for the weighted score (others weigh 1).
+    pub item_weights: BTreeMap<String, f64>,
+    /// Slots per bag page id, for the inventory-fullness warning.
+    pub page_capacities: BTreeMap<u32, usize>,
+    /// Warn when a page with a known capacity is at least this full (percent).
+    pub bag_warn_percent: u8,
+    /// Remind to sort the inventory when the last sort is this many minutes
+    /// old (0 turns the reminder off).
+    pub sort_reminder_mins: u64,
+    /// Item-change contexts (log `ProtoName`s) whose deltas count as loot.
+    pub loot_contexts: Vec<String>,
+    /// Stat boxes in the session bar, in display order.
+    pub stat_boxes: Vec<StatKind>,
+    /// Tab the GUI was last showing, to reopen on.
+    pub gui_tab: Tab,
+}
+
+impl Settings {
+    /// How to write numbers: the digit-group separator, if grouping is on,
+    /// and the decimal mark.
+    pub fn number_locale(&self) -> NumberLocale {
+        NumberLocale {
+            group: self.group_digits.then_some(self.thousands_separator),
+            decimal: self
+                .decimal_separator
+                .unwrap_or(if self.thousands_separator == '.' {
+                    ','
+                } else {
+                    '.'
+                }),
+        }
+    }
+
+    /// Switch to the separators of `locale`, turning grouping on or off
+    /// to match.
+    pub fn set_number_locale(&mut self, locale: NumberLocale) {
+        self.group_digits = locale.group.is_some();
+        if let Some(group) = locale.group {
+            self.thousands_separator = group;
+        }
+        self.decimal_separator = Some(locale.decimal);
+    }
+
+    /// `(name, value)` pairs for display; map goals are shown by `goal`, and
+    /// the GUI's last tab is window state rather than a preference.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "primary_currency",
+                format!(
+                    "{} ({})",
+                    self.primary_currency,
+                    crate::log_parser::item_name(&self.primary_currency)
+                ),
+            ),
+            ("group_digits", self.group_digits.to_string()),
+            (
+                "thousands_separator",
+                format!("{:?}", self.thousands_separator),
+            ),
+            (
+                "decimal_separator",
+                format!("{:?}", self.number_locale().decimal),
+            ),
+            ("abbreviate_numbers", self.abbreviate_numbers.to_string()),
+            ("rate_precision", self.rate_precision.to_string()),
+            ("value_precision", self.value_precision.to_string()),
+            ("recent_runs", self.recent_runs.to_string()),
+            (
+                "pace_floor",
+                self.pace_floor
+                    .map_or_else(|| "off".to_string(), |floor| floor.to_string()),
+            ),
+            ("pace_alert_runs", self.pace_alert_runs.to_string()),
+            (
+                "target_per_hour",
+                self.target_per_hour
+                    .map_or_else(|| "off".to_string(), |target| target.to_string()),
+            ),
+            ("histogram_bucket", self.histogram_bucket.to_string()),
+            (
+                "auto_end_hours",
+                if self.auto_end_hours == 0 {
+                    "off".to_string()
+                } else {
+                    self.auto_end_hours.to_string()
+                },
+            ),
+            ("auto_end_idle_mins", self.auto_end_idle_mins.to_string()),
+            ("min_session_secs", self.min_session_secs.to_string()),
+            (
+                "log_url",
+                self.log_url
+                    .clone()
+                    .unwrap_or_else(|| "(local file)".to_string()),
+            ),
+            ("poll_interval_secs", self.poll_interval_secs.to_string()),
+            ("ignored_items", id_list(&self.ignored_items)),
+            ("count_ignored", self.count_ignored.to_string()),
+            ("pinned_items", id_list(&self.pinned_items)),
+            ("item_display", self.item_display.key().to_string()),
+            ("item_aliases", id_map(&self.item_aliases)),
+            ("item_prices", id_map(&self.item_prices)),
+            ("score_mode", self.score_mode.key().to_string()),
+            ("item_weights", id_map(&self.item_weights)),
+            (
+                "page_capacities",
+                if self.page_capacities.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    self.page_capacities
+                        .iter()
+                        .map(|(page, slots)| format!("{}={}", page, slots))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                },
+            ),
+            ("bag_warn_percent", self.bag_warn_percent.to_string()),
+            ("sort_reminder_mins", self.sort_reminder_mins.to_string()),
+            (
+                "loot_contexts",
+                if self.loot_contexts.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    self.loot_contexts.join(", ")
+                },
+            ),
+            (
+                "stat_boxes",
+                if self.stat_boxes.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    self.stat_boxes
+                        .iter()
+                        .map(|kind| kind.key())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                },
+            ),
+        ]
+    }
+
+    pub fn is_ignored(&self, config_base_id: &str) -> bool {
+        self.ignored_items.contains(config_base_id)
+    }
+
+    /// Whether `config_base_id` counts toward item totals.
+    pub fn counts_item(&self, config_base_id: &str) -> bool {
+        self.count_ignored || !self.is_ignored(config_base_id)
+    }
+
+    /// Hand-set price for the item called `name` (case-insensitive).
+    pub fn price_override(&self, name: &str) -> Option<f64> {
+        self.item_prices
+            .iter()
+            .find(|(id, _)| crate::log_parser::item_name(id).eq_ignore_ascii_case(name.trim()))
+            .map(|(_, &price)| price)
+    }
+
+    /// The scoring mode with the primary currency and the hand-set prices
+    /// and item weights.
+    pub fn scoring(&self) -> ScoringConfig<'_> {
+        ScoringConfig {
+            mode: self.score_mode,
+            primary: &self.primary_currency,
+            prices: &self.item_prices,
+            weights: &self.item_weights,
+        }
+    }
+
+    /// `(max_age, max_idle)` for `models::auto_end_stale`, if auto-end is on.
+    pub fn auto_end(&self) -> Option<(chrono::Duration, chrono::Duration)> {
+        let duration = |secs: u64| {
+            i64::try_from(secs)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .unwrap_or(chrono::Duration::MAX)
+        };
+        (self.auto_end_hours > 0).then(|| {
+            (
+                duration(self.auto_end_hours.saturating_mul(3600)),
+                duration(self.auto_end_idle_mins.saturating_mul(60)),
+            )
+        })
+    }
+
+    /// Time between log polls; never less than a second.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs.max(1))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            primary_currency: FLAME_ELEMENTIUM_ID.to_string(),
+            group_digits: true,
+            thousands_separator: ',',
+            decimal_separator: None,
+            abbreviate_numbers: false,
+            rate_precision: 0,
+            value_precision: 2,
+            recent_runs: 3,
+            pace_floor: None,
+            pace_alert_runs: 2,
+            target_per_hour: None,
+            histogram_bucket: 100,
+            map_goals: BTreeMap::new(),
+            auto_end_hours: 0,
+            auto_end_idle_mins: 60,
+            min_session_secs: 0,
+            log_url: None,
+            poll_interval_secs: POLL_INTERVAL.as_secs(),
+            ignored_items: BTreeSet::new(),
+            count_ignored: false,
+            pinned_items: BTreeSet::new(),
+            item_display: ItemDisplay::default(),
+            item_aliases: BTreeMap::new(),
+            item_prices: BTreeMap::new(),
+            score_mode: ScoreMode::default(),
+            item_weights: BTreeMap::new(),
+            page_capacities: BTreeMap::new(),
+            bag_warn_percent: 90,
+            sort_reminder_mins: 60,
+            loot_contexts: crate::log_parser::default_loot_contexts(),
+            stat_boxes: StatKind::ALL.to_vec(),
+            gui_tab: Tab::default(),
+        }
+    }
+}
+
+/// `id (name), ...` for display, or `(none)`.
+fn id_list(ids: &BTreeSet<String>) -> String {
+    if ids.is_empty() {
+        return "(none)".to_string();
+    }
+    ids.iter()
+        .map(|id| format!("{} ({})", id, crate::log_parser::item_name(id)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Like [`id_list`], with each id's value: `ID (name) = value`.
+fn id_map<V: Display>(values: &BTreeMap<String, V>) -> String {
+    if values.is_empty() {
+        return "(none)".to_string();
+    }
+    values
+        .iter()
+        .map(|(id, value)| format!("{} ({}) = {}", id, crate::log_parser::item_name(id), value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a `PAGE=SLOTS` bag page capacity, as given to `settings --page-capacity`.
+pub fn parse_page_capacity(arg: &str) -> Result<(u32, usize), String> {
+    let (page, slots) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected PAGE=SLOTS, got {:?}", arg))?;
+    let page = page
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid page id {:?}", page))?;
+    let slots = slots
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid slot count {:?}", slots))?;
+    Ok((page, slots))
+}
+
+/// Parse an `ID=WEIGHT` item weight, as given to `settings --item-weight`.
+pub fn parse_item_weight(arg: &str) -> Result<(String, f64), String> {
+    let (id, weight) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected ID=WEIGHT, got {:?}", arg))?;
+    let weight: f64 = weight
+        .trim()
+        .parse()
+        .ok()
+        .filter(|w: &f64| w.is_finite())
+        .ok_or_else(|| format!("invalid weight {:?}", weight))?;
+    Ok((id.trim().to_string(), weight))
+}
+
+/// Parse an `ID=NAME` item alias, as given to `settings --item-alias`.
+pub fn parse_item_alias(arg: &str) -> Result<(String, String), String> {
+    let (id, alias) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected ID=NAME, got {:?}", arg))?;
+    let alias = alias.trim();
+    if alias.is_empty() {
+        return Err(format!("empty alias in {:?}", arg));
+    }
+    Ok((id.trim().to_string(), alias.to_string()))
+}
+
+/// Whether `sep` can separate digit groups without being misread as part
+/// of the number.
+pub fn is_valid_separator(sep: char) -> bool {
+    !(sep.is_ascii_digit() || sep == '-' || sep == '+')
+}
+
+/// Parse a `--number-locale` preset name.
+pub fn parse_number_locale(name: &str) -> Result<NumberLocale, String> {
+    NumberLocale::preset(name).ok_or_else(|| {
+        let names: Vec<_> = NumberLocale::PRESETS.iter().map(|(key, _)| *key).collect();
+        format!(
+            "unknown number locale {:?} (expected one of {})",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+/// Check a log URL as typed by the user; an empty string means the local file.
+pub fn parse_log_url(url: &str) -> Result<Option<String>, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        Ok(None)
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(Some(url.to_string()))
+    } else {
+        Err(format!(
+            "Invalid log URL {:?}; expected http:// or https://",
+            url
+        ))
+    }
+}
+
+pub fn settings_path(data_file: &Path) -> PathBuf {
+    data_file.with_file_name("settings.json")
+}
+
+/// Load settings from `path`; a missing or unreadable file gives defaults.
+pub fn load(path: &Path) -> Settings {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!(path = %path.display(), error = %e, "invalid settings file; using defaults");
+            Settings::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Settings::default(),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to read settings; using defaults");
+            Settings::default()
+        }
+    }
+}
+
+pub fn save(path: &Path, settings: &Settings) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(settings).map_err(io::Error::other)?;
+    fs::write(path, json).map_err(|e| storage::path_context(e, "save settings to", path))
+}
+
+/// Settings for the GUI/TUI, read from the default data directory.
+pub fn load_default() -> Settings {
+    crate::storage::data_file_path()
+        .map(|data_file| load(&settings_path(&data_file)))
+        .unwrap_or_default()
+}
+
+/// Save settings to the default data directory, where `load_default` reads them.
+pub fn save_default(settings: &Settings) -> io::Result<()> {
+    let data_file = crate::storage::data_file_path()?;
+    save(&settings_path(&data_file), settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_file_gives_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = load(&dir.path().join("settings.json"));
+        assert_eq!(settings.primary_currency, FLAME_ELEMENTIUM_ID);
+        assert_eq!(settings.number_locale(), NumberLocale::US);
+    }
+
+    #[test]
+    fn test_older_file_gets_new_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, r#"{"primary_currency":"5028"}"#).unwrap();
+        let settings = load(&path);
+        assert_eq!(settings.primary_currency, "5028");
+        assert!(settings.group_digits);
+        assert_eq!(settings.thousands_separator, ',');
+        assert_eq!(settings.recent_runs, 3);
+        assert_eq!(settings.pace_floor, None);
+        assert_eq!(settings.min_session_secs, 0);
+        assert_eq!(settings.poll_interval(), POLL_INTERVAL);
+        assert!(settings.ignored_items.is_empty());
+        assert!(!settings.count_ignored);
+    }
+
+    #[test]
+    fn test_number_locale_from_separators() {
+        let mut settings = Settings {
+            thousands_separator: '.',
+            ..Settings::default()
+        };
+        // Files from before the decimal mark was configurable.
+        assert_eq!(settings.number_locale(), NumberLocale::EU);
+        settings.set_number_locale(NumberLocale::preset("ch").unwrap());
+        assert_eq!(settings.number_locale().group, Some('\''));
+        assert_eq!(settings.number_locale().decimal, '.');
+        settings.set_number_locale(NumberLocale::PLAIN);
+        assert!(!settings.group_digits);
+        assert_eq!(settings.number_locale(), NumberLocale::PLAIN);
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(
+            &path,
+            r#"{"recent_runs":7,"theme":"light","pinned":["5028"]}"#,
+        )
+        .unwrap();
+        let settings = load(&path);
+        assert_eq!(settings.recent_runs, 7);
+        assert_eq!(settings.primary_currency, FLAME_ELEMENTIUM_ID);
+    }
+
+    #[test]
+    fn test_validation_helpers() {
+        assert!(is_valid_separator('.'));
+        assert!(is_valid_separator(' '));
+        assert!(!is_valid_separator('7'));
+        assert!(!is_valid_separator('-'));
+        assert_eq!(parse_number_locale(" EU"), Ok(NumberLocale::EU));
+        assert!(parse_number_locale("de_DE").is_err());
+        assert_eq!(parse_log_url("  "), Ok(None));
+        assert_eq!(
+            parse_log_url(" http://pc:8000/UE_game.log "),
+            Ok(Some("http://pc:8000/UE_game.log".to_string()))
+        );
+        assert!(parse_log_url("pc:8000/UE_game.log").is_err());
+        assert_eq!(parse_page_capacity("102=60"), Ok((102, 60)));
+        assert!(parse_page_capacity("102").is_err());
+        assert!(parse_page_capacity("x=60").is_err());
+        assert_eq!(
+            parse_item_weight(" 6002 = 2.5"),
+            Ok(("6002".to_string(), 2.5))
+        );
+        assert!(parse_item_weight("6002").is_err());
+        assert!(parse_item_weight("6002=inf").is_err());
+        assert_eq!(
+            parse_item_alias("5028 = that one shard"),
+            Ok(("5028".to_string(), "that one shard".to_string()))
+        );
+        assert!(parse_item_alias("5028").is_err());
+        assert!(parse_item_alias("5028= ").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let settings = Settings {
+            primary_currency: "5028".to_string(),
+            group_digits: false,
+            thousands_separator: '.',
+            decimal_separator: Some(','),
+            abbreviate_numbers: true,
+            rate_precision: 1,
+            value_precision: 0,
+            recent_runs: 5,
+            pace_floor: Some(1500.0),
+            pace_alert_runs: 4,
+            target_per_hour: Some(2400.0),
+            histogram_bucket: 250,
+            map_goals: BTreeMap::from([("Abyss".to_string(), 2000)]),
+            auto_end_hours: 8,
+            auto_end_idle_mins: 90,
+            min_session_secs: 30,
+            log_url: Some("http://gaming-pc:8000/UE_game.log".to_string()),
+            poll_interval_secs: 10,
+            ignored_items: BTreeSet::from(["200100".to_string()]),
+            count_ignored: true,
+            pinned_items: BTreeSet::from(["6002".to_string()]),
+            item_display: ItemDisplay::Name,
+            item_aliases: BTreeMap::from([("5028".to_string(), "that one shard".to_string())]),
+            item_prices: BTreeMap::from([("6002".to_string(), 1.5)]),
+            score_mode: ScoreMode::Weighted,
+            item_weights: BTreeMap::from([("6002".to_string(), 2.0)]),
+            page_capacities: BTreeMap::from([(102, 60)]),
+            bag_warn_percent: 80,
+            sort_reminder_mins: 0,
+            loot_contexts: vec!["PickItems".to_string(), "ChestRewards".to_string()],
+            stat_boxes: vec![StatKind::Time, StatKind::Primary],
+            gui_tab: Tab::Runs,
+        };
+        save(&path, &settings).unwrap();
+        assert_eq!(load(&path), settings);
+        assert_eq!(settings.number_locale(), NumberLocale::EU.ungrouped());
+        assert_eq!(settings.poll_interval(), Duration::from_secs(10));
+        let name = crate::log_parser::item_name("6002").to_uppercase();
+        assert_eq!(settings.price_override(&name), Some(1.5));
+        assert_eq!(settings.price_override("Flame Core"), None);
+    }
+}
@@ -0,0 +1,900 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::models::{self, Session};
+use crate::viewmodel::{format_number, NumberLocale};
+
+/// Totals for one map across every recorded run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MapStats {
+    pub map: String,
+    /// Tier label (see [`tier_label`]) when broken down by tier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    pub runs: usize,
+    pub duration_secs: f64,
+    pub fe: i64,
+    pub items: i64,
+    pub fe_per_hour: f64,
+}
+
+impl MapStats {
+    /// The map name, with the tier when broken down by tier.
+    pub fn label(&self) -> String {
+        match &self.tier {
+            Some(tier) => format!("{} {}", self.map, tier),
+            None => self.map.clone(),
+        }
+    }
+}
+
+/// How a run's tier is shown: `T200`, or `(unknown tier)` for runs recorded
+/// without one.
+pub fn tier_label(tier: Option<u32>) -> String {
+    match tier {
+        Some(tier) => format!("T{}", tier),
+        None => "(unknown tier)".to_string(),
+    }
+}
+
+/// Whether `session` counts towards aggregates: ended sessions shorter than
+/// `min_secs` are left out, active ones always count.
+pub fn long_enough(session: &Session, min_secs: u64) -> bool {
+    match session.end_time {
+        Some(end) => (end - session.start_time).num_seconds() >= min_secs as i64,
+        None => true,
+    }
+}
+
+/// Aggregate the recorded runs of `sessions` per map, or per map and tier
+/// when `by_tier` is set, best FE/hour first.
+///
+/// Spellings of a map that differ only in case or spacing are grouped under
+/// the first one seen; runs without a tier share one "unknown tier" group
+/// per map. Sessions without run data (CLI sessions) and sessions shorter
+/// than `min_secs` are ignored.
+pub fn map_stats(sessions: &[Session], min_secs: u64, by_tier: bool) -> Vec<MapStats> {
+    let mut by_map: HashMap<(String, Option<Option<u32>>), MapStats> = HashMap::new();
+    let counted = sessions.iter().filter(|s| long_enough(s, min_secs));
+    for run in counted.flat_map(|s| &s.runs) {
+        let tier = by_tier.then_some(run.tier);
+        let entry = by_map
+            .entry((models::map_key(&run.map), tier))
+            .or_insert_with(|| MapStats {
+                map: models::normalize_map(&run.map),
+                tier: tier.map(tier_label),
+                runs: 0,
+                duration_secs: 0.0,
+                fe: 0,
+                items: 0,
+                fe_per_hour: 0.0,
+            });
+        entry.runs += 1;
+        entry.duration_secs += run.duration_secs;
+        entry.fe += run.fe;
+        entry.items += run.items;
+    }
+
+    let mut stats: Vec<MapStats> = by_map
+        .into_values()
+        .map(|mut s| {
+            if s.duration_secs >= 1.0 {
+                s.fe_per_hour = s.fe as f64 / s.duration_secs * 3600.0;
+            }
+            s
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.fe_per_hour
+            .total_cmp(&a.fe_per_hour)
+            .then_with(|| a.map.cmp(&b.map))
+            .then_with(|| a.tier.cmp(&b.tier))
+    });
+    stats
+}
+
+/// The map with the best FE/hour among those run at least `min_runs` times.
+pub fn recommend(stats: &[MapStats], min_runs: usize) -> Option<&MapStats> {
+    // `stats` is sorted best first.
+    stats.iter().find(|s| s.runs >= min_runs)
+}
+
+/// Plain-text table for `Stats`, with FE/hour to `precision` decimals
+/// written with the decimal mark of `locale` (ungrouped, to fit the column).
+pub fn stats_table(stats: &[MapStats], precision: usize, locale: &NumberLocale) -> String {
+    let mut out = format!(
+        "{:<24} {:>5} {:>9} {:>8} {:>8}\n",
+        "Map", "Runs", "Time", "FE", "FE/hr"
+    );
+    for s in stats {
+        out.push_str(&format!(
+            "{:<24} {:>5} {:>9} {:>8} {:>8}\n",
+            s.label(),
+            s.runs,
+            format_hms(s.duration_secs),
+            s.fe,
+            format_number(s.fe_per_hour, precision, &locale.ungrouped())
+        ));
+    }
+    out
+}
+
+/// `secs` as `h:mm:ss`.
+fn format_hms(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Width in characters of the longest bar in `comparison_chart`.
+const CHART_WIDTH: usize = 30;
+
+/// One session's totals, for comparing sessions side by side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionComparison {
+    pub id: String,
+    pub map: String,
+    pub active: bool,
+    pub duration_secs: f64,
+    pub fe: i64,
+    pub fe_per_hour: f64,
+    pub total_value: f64,
+}
+
+/// The sessions asked for, in the order given, and the ids not found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub sessions: Vec<SessionComparison>,
+    pub missing: Vec<String>,
+}
+
+/// Compare the sessions `ids`; active sessions are measured up to `now`.
+///
+/// FE comes from the runs recorded by the GUI/TUI, so CLI sessions show 0.
+pub fn compare_sessions(sessions: &[Session], ids: &[String], now: DateTime<Utc>) -> Comparison {
+    let mut comparison = Comparison {
+        sessions: Vec::new(),
+        missing: Vec::new(),
+    };
+    for id in ids {
+        let Some(session) = sessions.iter().find(|s| &s.id == id) else {
+            comparison.missing.push(id.clone());
+            continue;
+        };
+        let end = session.end_time.unwrap_or(now);
+        let duration_secs = (end - session.start_time).num_milliseconds().max(0) as f64 / 1000.0;
+        let fe: i64 = session.runs.iter().map(|r| r.fe).sum();
+        comparison.sessions.push(SessionComparison {
+            id: session.id.clone(),
+            map: session.map.clone(),
+            active: session.is_active(),
+            duration_secs,
+            fe,
+            fe_per_hour: fe_rate(fe, duration_secs),
+            total_value: session.total_value(),
+        });
+    }
+    comparison
+}
+
+/// Several sessions of one farming setup pooled into a single rate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CombinedRate {
+    /// Sessions pooled: those with recorded runs.
+    pub sessions: usize,
+    pub runs: usize,
+    /// Time spent in maps, summed over every run.
+    pub duration_secs: f64,
+    pub fe: i64,
+    /// Total FE over total farming time.
+    pub fe_per_hour: f64,
+    /// Plain average of each session's own FE/hour, for contrast: short
+    /// sessions weigh as much as long ones here.
+    pub mean_fe_per_hour: f64,
+    pub missing: Vec<String>,
+}
+
+/// FE/hour over the recorded runs of `secs` seconds that gained `fe`; 0
+/// for less than a second of runs.
+fn fe_rate(fe: i64, secs: f64) -> f64 {
+    if secs >= 1.0 {
+        fe as f64 / secs * 3600.0
+    } else {
+        0.0
+    }
+}
+
+/// Pool the runs of the sessions `ids` into one FE/hour (total FE over
+/// total run time), rather than averaging each session's rate.
+///
+/// Sessions without run data (CLI sessions) add nothing and are not
+/// counted; an id given twice is pooled once, and ids not found are listed
+/// in `missing`.
+pub fn combine_sessions(sessions: &[Session], ids: &[String]) -> CombinedRate {
+    let mut combined = CombinedRate {
+        sessions: 0,
+        runs: 0,
+        duration_secs: 0.0,
+        fe: 0,
+        fe_per_hour: 0.0,
+        mean_fe_per_hour: 0.0,
+        missing: Vec::new(),
+    };
+    let mut rates = Vec::new();
+    let mut seen = HashSet::new();
+    for id in ids.iter().filter(|id| seen.insert(id.as_str())) {
+        let Some(session) = sessions.iter().find(|s| &s.id == id) else {
+            combined.missing.push(id.clone());
+            continue;
+        };
+        if session.runs.is_empty() {
+            continue;
+        }
+        let secs: f64 = session.runs.iter().map(|r| r.duration_secs).sum();
+        let fe: i64 = session.runs.iter().map(|r| r.fe).sum();
+        combined.sessions += 1;
+        combined.runs += session.runs.len();
+        combined.duration_secs += secs;
+        combined.fe += fe;
+        if secs >= 1.0 {
+            rates.push(fe_rate(fe, secs));
+        }
+    }
+    combined.fe_per_hour = fe_rate(combined.fe, combined.duration_secs);
+    if !rates.is_empty() {
+        combined.mean_fe_per_hour = rates.iter().sum::<f64>() / rates.len() as f64;
+    }
+    combined
+}
+
+/// Plain-text report for `Combine`, rates to `precision` decimals.
+pub fn combined_report(combined: &CombinedRate, precision: usize, locale: &NumberLocale) -> String {
+    format!(
+        "Sessions:     {} ({} runs)\n\
+         Farming time: {}\n\
+         FE:           {}\n\
+         FE/hr:        {} (mean of session rates: {})\n",
+        combined.sessions,
+        combined.runs,
+        format_hms(combined.duration_secs),
+        format_number(combined.fe as f64, 0, locale),
+        format_number(combined.fe_per_hour, precision, locale),
+        format_number(combined.mean_fe_per_hour, precision, locale)
+    )
+}
+
+/// FE/hour of each compared session as a horizontal bar chart, labelled to
+/// `precision` decimals like [`stats_table`].
+pub fn comparison_chart(
+    sessions: &[SessionComparison],
+    precision: usize,
+    locale: &NumberLocale,
+) -> String {
+    let max = sessions.iter().map(|s| s.fe_per_hour).fold(0.0, f64::max);
+    let mut out = String::new();
+    for s in sessions {
+        let bar = if max > 0.0 {
+            (s.fe_per_hour / max * CHART_WIDTH as f64).round() as usize
+        } else {
+            0
+        };
+        out.push_str(&format!(
+            "{:<8} {:<16} {:<w$} {:>8} FE/hr{}\n",
+            s.id.get(..8).unwrap_or(&s.id),
+            s.map,
+            "█".repeat(bar),
+            format_number(s.fe_per_hour, precision, &locale.ungrouped()),
+            if s.active { " (active)" } else { "" },
+            w = CHART_WIDTH
+        ));
+    }
+    out
+}
+
+/// Runs whose FE falls in `[start, start + size)`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramBucket {
+    pub start: i64,
+    pub size: i64,
+    pub runs: usize,
+}
+
+/// Count `values` (FE per run) in buckets of `bucket_size` (at least 1),
+/// from the bucket holding the smallest value to the one holding the
+/// largest. Empty buckets in between are kept so the shape is honest.
+pub fn histogram(values: &[i64], bucket_size: i64) -> Vec<HistogramBucket> {
+    let size = bucket_size.max(1);
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let first = min.div_euclid(size);
+    let last = max.div_euclid(size);
+    let mut buckets: Vec<HistogramBucket> = (first..=last)
+        .map(|i| HistogramBucket {
+            start: i * size,
+            size,
+            runs: 0,
+        })
+        .collect();
+    for &v in values {
+        buckets[(v.div_euclid(size) - first) as usize].runs += 1;
+    }
+    buckets
+}
+
+/// FE of every recorded run in `sessions`, optionally only on `map`.
+/// Sessions shorter than `min_secs` are ignored.
+pub fn run_fe(sessions: &[Session], map: Option<&str>, min_secs: u64) -> Vec<i64> {
+    sessions
+        .iter()
+        .filter(|s| long_enough(s, min_secs))
+        .flat_map(|s| &s.runs)
+        .filter(|run| map.is_none_or(|m| models::same_map(&run.map, m)))
+        .map(|run| run.fe)
+        .collect()
+}
+
+/// `buckets` as a horizontal bar chart, one line per bucket.
+pub fn histogram_chart(buckets: &[HistogramBucket]) -> String {
+    let max = buckets.iter().map(|b| b.runs).max().unwrap_or(0);
+    let mut out = String::new();
+    for b in buckets {
+        let bar = if max > 0 {
+            (b.runs as f64 / max as f64 * CHART_WIDTH as f64).round() as usize
+        } else {
+            0
+        };
+        out.push_str(&format!(
+            "{:>8} – {:<8} {:<w$} {:>4}\n",
+            b.start,
+            b.start + b.size - 1,
+            "█".repeat(bar),
+            b.runs,
+            w = CHART_WIDTH
+        ));
+    }
+    out
+}
+
+/// Today's FE on one map measured against its daily goal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GoalProgress {
+    pub map: String,
+    pub goal: i64,
+    pub today: i64,
+    pub met: bool,
+}
+
+/// FE gained on `map` (ignoring case and spacing) during `day` in the time
+/// zone `tz`.
+///
+/// Each run counts towards the day its estimated start falls on (see
+/// [`Session::run_starts`]). Sessions shorter than `min_secs` are ignored.
+pub fn fe_on_day<Tz: TimeZone>(
+    sessions: &[Session],
+    map: &str,
+    day: NaiveDate,
+    tz: &Tz,
+    min_secs: u64,
+) -> i64 {
+    sessions
+        .iter()
+        .filter(|s| long_enough(s, min_secs))
+        .flat_map(|s| s.run_starts())
+        .filter(|(start, run)| {
+            models::same_map(&run.map, map) && start.with_timezone(tz).date_naive() == day
+        })
+        .map(|(_, run)| run.fe)
+        .sum()
+}
+
+/// Progress towards each goal in `goals` (map → daily FE) on `day`.
+pub fn goal_progress<Tz: TimeZone>(
+    sessions: &[Session],
+    goals: &BTreeMap<String, i64>,
+    day: NaiveDate,
+    tz: &Tz,
+    min_secs: u64,
+) -> Vec<GoalProgress> {
+    goals
+        .iter()
+        .map(|(map, &goal)| {
+            let today = fe_on_day(sessions, map, day, tz, min_secs);
+            GoalProgress {
+                map: map.clone(),
+                goal,
+                today,
+                met: today >= goal,
+            }
+        })
+        .collect()
+}
+
+/// Plain-text goal lines for `Stats` and `Goal`.
+pub fn goals_table(progress: &[GoalProgress]) -> String {
+    let mut out = format!(
+        "{:<24} {:>8} {:>8} {:>5}\n",
+        "Goal (today)", "FE", "Goal", "%"
+    );
+    for p in progress {
+        let pct = if p.goal > 0 {
+            p.today as f64 / p.goal as f64 * 100.0
+        } else {
+            100.0
+        };
+        out.push_str(&format!(
+            "{:<24} {:>8} {:>8} {:>4.0}%{}\n",
+            p.map,
+            p.today,
+            p.goal,
+            pct,
+            if p.met { "  met" } else { "" }
+        ));
+    }
+    out
+}
+
+/// One item's share of a session's drop value, or the "Other" slice.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BreakdownSlice {
+    pub name: String,
+    pub value: f64,
+    /// Fraction of the session's total value, 0 to 1.
+    pub share: f64,
+}
+
+/// A session's drop value by item: the `top` most valuable items, then one
+/// "Other" slice for the rest. Drops of the same item are summed and
+/// worthless items left out, so a session without value has no slices.
+pub fn value_breakdown(session: &Session, top: usize) -> Vec<BreakdownSlice> {
+    let mut by_item: HashMap<&str, f64> = HashMap::new();
+    for drop in &session.drops {
+        *by_item.entry(drop.name.as_str()).or_default() += drop.value * drop.quantity as f64;
+    }
+    let mut items: Vec<(&str, f64)> = by_item.into_iter().filter(|&(_, v)| v > 0.0).collect();
+    let total: f64 = items.iter().map(|(_, v)| v).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    items.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+    let slice = |name: &str, value: f64| BreakdownSlice {
+        name: name.to_string(),
+        value,
+        share: value / total,
+    };
+    let mut slices: Vec<BreakdownSlice> = items
+        .iter()
+        .take(top)
+        .map(|&(name, v)| slice(name, v))
+        .collect();
+    let other: f64 = items.iter().skip(top).map(|(_, v)| v).sum();
+    if other > 0.0 {
+        slices.push(slice("Other", other));
+    }
+    slices
+}
+
+/// `slices` as horizontal bars scaled to the whole (a full bar is 100%),
+/// with each slice's value to `precision` decimals and its percentage.
+pub fn breakdown_chart(
+    slices: &[BreakdownSlice],
+    precision: usize,
+    locale: &NumberLocale,
+) -> String {
+    let mut out = String::new();
+    for s in slices {
+        out.push_str(&format!(
+            "{:<24} {:<w$} {:>10} {:>5.1}%\n",
+            s.name,
+            "█".repeat((s.share * CHART_WIDTH as f64).round() as usize),
+            format_number(s.value, precision, locale),
+            s.share * 100.0,
+            w = CHART_WIDTH
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RunRecord;
+    use chrono::{DateTime, FixedOffset, Utc};
+
+    fn session_with_runs(runs: &[(&str, f64, i64)]) -> Session {
+        Session {
+            id: "s".to_string(),
+            map: "Abyss".to_string(),
+            notes: None,
+            start_time: Utc::now(),
+            end_time: Some(Utc::now()),
+            drops: Vec::new(),
+            runs: runs
+                .iter()
+                .map(|&(map, duration_secs, fe)| RunRecord {
+                    map: map.to_string(),
+                    duration_secs,
+                    fe,
+                    items: fe,
+                    started_at: None,
+                    ended_at: None,
+                    tier: None,
+                })
+                .collect(),
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_sessions() {
+        let now: DateTime<Utc> = "2026-10-16T12:00:00Z".parse().unwrap();
+        let mut ended = session_with_runs(&[("Abyss", 600.0, 300), ("Rift", 600.0, 200)]);
+        ended.id = "ended".to_string();
+        ended.start_time = now - chrono::Duration::hours(3);
+        ended.end_time = Some(now - chrono::Duration::hours(1));
+        let mut active = session_with_runs(&[("Rift", 600.0, 600)]);
+        active.id = "active".to_string();
+        active.start_time = now - chrono::Duration::minutes(30);
+        active.end_time = None;
+        let sessions = [ended, active];
+
+        let ids = ["active", "gone", "ended"].map(String::from);
+        let comparison = compare_sessions(&sessions, &ids, now);
+        assert_eq!(comparison.missing, vec!["gone"]);
+        let rates: Vec<_> = comparison
+            .sessions
+            .iter()
+            .map(|s| {
+                (
+                    s.id.as_str(),
+                    s.active,
+                    s.duration_secs,
+                    s.fe,
+                    s.fe_per_hour,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rates,
+            vec![
+                ("active", true, 1800.0, 600, 1200.0),
+                ("ended", false, 7200.0, 500, 250.0)
+            ]
+        );
+
+        let chart = comparison_chart(&comparison.sessions, 0, &NumberLocale::US);
+        let lines: Vec<_> = chart.lines().collect();
+        assert!(lines[0].contains(&"█".repeat(CHART_WIDTH)));
+        assert!(lines[0].ends_with("1200 FE/hr (active)"));
+        assert_eq!(lines[1].matches('█').count(), 6);
+        assert!(
+            comparison_chart(&comparison.sessions, 1, &NumberLocale::EU).contains(" 250,0 FE/hr")
+        );
+    }
+
+    #[test]
+    fn test_combined_rate_differs_from_mean_of_rates() {
+        // A short lucky session and a long ordinary one.
+        let mut short = session_with_runs(&[("Rift", 600.0, 500)]);
+        short.id = "short".to_string();
+        let mut long = session_with_runs(&[("Rift", 1800.0, 500), ("Rift", 1200.0, 500)]);
+        long.id = "long".to_string();
+        let mut cli = session_with_runs(&[]);
+        cli.id = "cli".to_string();
+        let sessions = [short, long, cli];
+
+        let ids = ["short", "long", "cli", "gone"].map(String::from);
+        let combined = combine_sessions(&sessions, &ids);
+        assert_eq!(combined.sessions, 2);
+        assert_eq!(combined.runs, 3);
+        assert_eq!(combined.duration_secs, 3600.0);
+        assert_eq!(combined.fe, 1500);
+        // 1500 FE over one hour, where the per-session rates (3000 and
+        // 1200) average to 2100.
+        assert_eq!(combined.fe_per_hour, 1500.0);
+        assert_eq!(combined.mean_fe_per_hour, 2100.0);
+        assert_eq!(combined.missing, vec!["gone"]);
+
+        let report = combined_report(&combined, 0, &NumberLocale::US);
+        assert!(report.contains("Farming time: 1:00:00\n"), "{report}");
+        assert!(
+            report.contains("FE/hr:        1,500 (mean of session rates: 2,100)"),
+            "{report}"
+        );
+
+        let none = combine_sessions(&sessions, &["cli".to_string()]);
+        assert_eq!(none.sessions, 0);
+        assert_eq!((none.fe_per_hour, none.mean_fe_per_hour), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_combine_sessions_pools_repeated_ids_once() {
+        let mut short = session_with_runs(&[("Rift", 600.0, 500)]);
+        short.id = "short".to_string();
+        let mut long = session_with_runs(&[("Rift", 3000.0, 1000)]);
+        long.id = "long".to_string();
+        let sessions = [short, long];
+
+        let ids = ["short", "long", "short"].map(String::from);
+        let combined = combine_sessions(&sessions, &ids);
+        assert_eq!((combined.sessions, combined.runs), (2, 2));
+        assert_eq!(combined.fe, 1500);
+        assert_eq!(combined.fe_per_hour, 1500.0);
+        assert_eq!(combined.mean_fe_per_hour, 2100.0);
+    }
+
+    #[test]
+    fn test_map_stats_aggregates_across_sessions() {
+        let sessions = vec![
+            session_with_runs(&[("Abyss", 600.0, 100), ("Rift", 300.0, 100)]),
+            session_with_runs(&[("Abyss", 600.0, 50)]),
+            session_with_runs(&[]),
+        ];
+        let stats = map_stats(&sessions, 0, false);
+        assert_eq!(stats.len(), 2);
+
+        assert_eq!(stats[0].map, "Rift");
+        assert_eq!(stats[0].fe_per_hour, 1200.0);
+
+        assert_eq!(stats[1].map, "Abyss");
+        assert_eq!(stats[1].runs, 2);
+        assert_eq!(stats[1].duration_secs, 1200.0);
+        assert_eq!(stats[1].fe, 150);
+        assert_eq!(stats[1].fe_per_hour, 450.0);
+    }
+
+    #[test]
+    fn test_map_stats_by_tier() {
+        let mut session = session_with_runs(&[
+            ("Abyss", 600.0, 100),
+            ("Abyss", 600.0, 300),
+            ("abyss", 600.0, 200),
+            ("Abyss", 600.0, 50),
+        ]);
+        for (run, tier) in session
+            .runs
+            .iter_mut()
+            .zip([Some(200), Some(300), Some(200)])
+        {
+            run.tier = tier;
+        }
+        let sessions = [session];
+
+        let stats = map_stats(&sessions, 0, true);
+        let rows: Vec<_> = stats.iter().map(|s| (s.label(), s.runs, s.fe)).collect();
+        assert_eq!(
+            rows,
+            [
+                ("Abyss T300".to_string(), 1, 300),
+                ("Abyss T200".to_string(), 2, 300),
+                ("Abyss (unknown tier)".to_string(), 1, 50),
+            ]
+        );
+        assert!(stats_table(&stats, 0, &NumberLocale::US).contains("Abyss T300 "));
+
+        // Without the breakdown the tiers fold into one map.
+        let stats = map_stats(&sessions, 0, false);
+        assert_eq!((stats.len(), stats[0].tier.as_deref()), (1, None));
+        assert_eq!(stats[0].label(), "Abyss");
+    }
+
+    #[test]
+    fn test_map_spellings_group_together() {
+        let sessions = vec![
+            session_with_runs(&[("Glacial Abyss", 600.0, 100)]),
+            session_with_runs(&[(" glacial  abyss", 600.0, 50)]),
+        ];
+        let stats = map_stats(&sessions, 0, false);
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].map.as_str(), stats[0].runs), ("Glacial Abyss", 2));
+        assert_eq!(stats[0].fe, 150);
+        let today = sessions[0].start_time.date_naive();
+        assert_eq!(fe_on_day(&sessions, "GLACIAL ABYSS ", today, &Utc, 0), 150);
+    }
+
+    #[test]
+    fn test_short_sessions_left_out_of_aggregates() {
+        let mut aborted = session_at("2024-05-01T10:00:00Z", &[("Rift", 5.0, 400)]);
+        aborted.end_time = Some(aborted.start_time + chrono::Duration::seconds(10));
+        let mut full = session_at("2024-05-01T11:00:00Z", &[("Abyss", 600.0, 100)]);
+        full.end_time = Some(full.start_time + chrono::Duration::minutes(15));
+        let mut active = session_at("2024-05-01T12:00:00Z", &[("Abyss", 60.0, 10)]);
+        active.end_time = None;
+        let sessions = vec![aborted, full, active];
+
+        assert_eq!(map_stats(&sessions, 0, false).len(), 2);
+        let stats = map_stats(&sessions, 60, false);
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].map.as_str(), stats[0].runs), ("Abyss", 2));
+
+        let may1 = day("2024-05-01");
+        assert_eq!(fe_on_day(&sessions, "Rift", may1, &Utc, 0), 400);
+        assert_eq!(fe_on_day(&sessions, "Rift", may1, &Utc, 60), 0);
+    }
+
+    #[test]
+    fn test_recommend_respects_min_runs() {
+        let sessions = vec![session_with_runs(&[
+            ("Rift", 300.0, 100),
+            ("Abyss", 600.0, 50),
+            ("Abyss", 600.0, 50),
+        ])];
+        let stats = map_stats(&sessions, 0, false);
+        assert_eq!(recommend(&stats, 1).unwrap().map, "Rift");
+        assert_eq!(recommend(&stats, 2).unwrap().map, "Abyss");
+        assert!(recommend(&stats, 3).is_none());
+    }
+
+    #[test]
+    fn test_stats_table_rows() {
+        let stats = map_stats(&[session_with_runs(&[("Rift", 3725.0, 10)])], 0, false);
+        let table = stats_table(&stats, 0, &NumberLocale::US);
+        let row = table.lines().nth(1).unwrap();
+        assert!(row.starts_with("Rift"));
+        assert!(row.contains("1:02:05"));
+        assert!(row.ends_with(" 10"));
+        let table = stats_table(&stats, 2, &NumberLocale::US);
+        assert!(table.lines().nth(1).unwrap().ends_with(" 9.66"));
+    }
+
+    fn session_at(start: &str, runs: &[(&str, f64, i64)]) -> Session {
+        let mut session = session_with_runs(runs);
+        session.start_time = start.parse::<DateTime<Utc>>().unwrap();
+        session
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn test_fe_on_day_splits_at_local_midnight() {
+        // Runs start at 23:30 and 00:30 UTC.
+        let sessions = vec![session_at(
+            "2024-05-01T23:30:00Z",
+            &[("Abyss", 3600.0, 100), ("Abyss", 600.0, 40)],
+        )];
+        assert_eq!(
+            fe_on_day(&sessions, "Abyss", day("2024-05-01"), &Utc, 0),
+            100
+        );
+        assert_eq!(
+            fe_on_day(&sessions, "abyss", day("2024-05-02"), &Utc, 0),
+            40
+        );
+
+        // At UTC+9 both runs fall on May 2nd.
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(
+            fe_on_day(&sessions, "Abyss", day("2024-05-02"), &tokyo, 0),
+            140
+        );
+        assert_eq!(
+            fe_on_day(&sessions, "Abyss", day("2024-05-01"), &tokyo, 0),
+            0
+        );
+
+        // At UTC-5 both fall on May 1st.
+        let east_us = FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(
+            fe_on_day(&sessions, "Abyss", day("2024-05-01"), &east_us, 0),
+            140
+        );
+    }
+
+    #[test]
+    fn test_goal_progress_met_and_unmet() {
+        let sessions = vec![
+            session_at(
+                "2024-05-01T10:00:00Z",
+                &[("Abyss", 600.0, 300), ("Rift", 600.0, 50)],
+            ),
+            session_at("2024-04-30T10:00:00Z", &[("Rift", 600.0, 500)]),
+        ];
+        let goals = BTreeMap::from([
+            ("Abyss".to_string(), 300),
+            ("Rift".to_string(), 200),
+            ("Unvisited".to_string(), 100),
+        ]);
+        let progress = goal_progress(&sessions, &goals, day("2024-05-01"), &Utc, 0);
+        let summary: Vec<_> = progress
+            .iter()
+            .map(|p| (p.map.as_str(), p.today, p.met))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Abyss", 300, true),
+                ("Rift", 50, false),
+                ("Unvisited", 0, false)
+            ]
+        );
+
+        let table = goals_table(&progress);
+        assert!(table.lines().nth(1).unwrap().ends_with("100%  met"));
+        assert!(table.lines().nth(2).unwrap().ends_with("25%"));
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        assert!(histogram(&[], 100).is_empty());
+
+        let single = histogram(&[250], 100);
+        assert_eq!(
+            single,
+            [HistogramBucket {
+                start: 200,
+                size: 100,
+                runs: 1
+            }]
+        );
+
+        // A gap bucket is kept; losses land below zero.
+        let buckets = histogram(&[-20, 10, 99, 100, 320], 100);
+        let counts: Vec<_> = buckets.iter().map(|b| (b.start, b.runs)).collect();
+        assert_eq!(counts, [(-100, 1), (0, 2), (100, 1), (200, 0), (300, 1)]);
+        // A zero size is treated as 1.
+        assert_eq!(histogram(&[3, 3], 0).len(), 1);
+
+        let chart = histogram_chart(&buckets);
+        assert_eq!(chart.lines().count(), 5);
+        assert!(chart.lines().nth(1).unwrap().ends_with("   2"));
+    }
+
+    #[test]
+    fn test_run_fe_filters_by_map() {
+        let sessions = [session_at(
+            "2024-05-01T10:00:00Z",
+            &[
+                ("Abyss", 600.0, 300),
+                ("Rift", 600.0, 50),
+                ("abyss", 60.0, 10),
+            ],
+        )];
+        assert_eq!(run_fe(&sessions, None, 0), [300, 50, 10]);
+        assert_eq!(run_fe(&sessions, Some("ABYSS"), 0), [300, 10]);
+    }
+
+    #[test]
+    fn test_value_breakdown_top_plus_other() {
+        let drop = |name: &str, quantity: u32, value: f64| models::DropItem {
+            name: name.to_string(),
+            quantity,
+            value,
+            added_at: None,
+        };
+        let mut session = session_with_runs(&[]);
+        session.drops = vec![
+            drop("Flame Core", 2, 30.0),
+            drop("Ember", 10, 1.0),
+            drop("Flame Core", 1, 40.0),
+            drop("Fossil", 5, 2.0),
+            drop("Junk", 3, 0.0),
+            drop("Shard", 1, 10.0),
+        ];
+
+        let slices = value_breakdown(&session, 2);
+        let summary: Vec<(&str, f64)> = slices.iter().map(|s| (s.name.as_str(), s.value)).collect();
+        assert_eq!(
+            summary,
+            [("Flame Core", 100.0), ("Ember", 10.0), ("Other", 20.0)]
+        );
+        assert!((slices[0].share - 100.0 / 130.0).abs() < 1e-9);
+        assert!((slices.iter().map(|s| s.share).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // Everything fits: no "Other" slice.
+        assert_eq!(value_breakdown(&session, 10).len(), 4);
+
+        let chart = breakdown_chart(&slices, 0, &NumberLocale::US);
+        assert!(chart.lines().next().unwrap().ends_with(" 100  76.9%"));
+
+        // Nothing of value: no slices.
+        session.drops = vec![drop("Junk", 3, 0.0)];
+        assert!(value_breakdown(&session, 5).is_empty());
+        session.drops.clear();
+        assert!(value_breakdown(&session, 5).is_empty());
+    }
+}
@@ -1,60 +1,311 @@
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use serde_json::json;
+use tracing::{debug, warn};
 
 use crate::models::Session;
 
-pub fn data_file_path() -> io::Result<PathBuf> {
+/// Data directory chosen with `--data-dir`/`TLI_DATA_DIR`, if any.
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Game log to use instead of searching the Steam libraries, if any.
+static GAME_LOG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// `e` with the operation and path that failed, keeping its kind, e.g.
+/// "Failed to read game log /path/UE_game.log: No such file or directory".
+pub fn path_context(e: io::Error, action: &str, path: &Path) -> io::Error {
+    io::Error::new(
+        e.kind(),
+        format!("Failed to {} {}: {}", action, path.display(), e),
+    )
+}
+
+/// Sessions file inside `dir`, creating the directory if needed.
+pub fn data_file_in(dir: &Path) -> io::Result<PathBuf> {
+    if dir.exists() && !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Data directory {} is not a directory", dir.display()),
+        ));
+    }
+    fs::create_dir_all(dir).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to create data directory {} ({})", dir.display(), e),
+        )
+    })?;
+    Ok(dir.join("sessions.json"))
+}
+
+/// Use `dir` instead of the platform data directory for the rest of the
+/// process, so the GUI, TUI, settings and price cache all follow it.
+pub fn use_data_dir(dir: &Path) -> io::Result<PathBuf> {
+    let data_file = data_file_in(dir)?;
+    debug!(path = %dir.display(), "using data directory override");
+    let _ = DATA_DIR_OVERRIDE.set(dir.to_path_buf());
+    Ok(data_file)
+}
+
+/// The platform data directory (`~/.local/share/tli-tracker` on Linux).
+pub fn default_data_dir() -> io::Result<PathBuf> {
     let proj_dirs = ProjectDirs::from("com", "tli", "tli-tracker")
-        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Failed to resolve data directory"))?;
-    let data_dir = proj_dirs.data_local_dir();
-    fs::create_dir_all(data_dir)?;
+        .ok_or_else(|| io::Error::other("Failed to resolve data directory"))?;
+    Ok(proj_dirs.data_local_dir().to_path_buf())
+}
+
+pub fn data_file_path() -> io::Result<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Ok(dir.join("sessions.json"));
+    }
+    let data_dir = default_data_dir()?;
+    // A missing directory that cannot be created is reported by the first
+    // write; reading commands simply find no sessions.
+    if let Err(e) = fs::create_dir_all(&data_dir) {
+        warn!(path = %data_dir.display(), error = %e, "failed to create data directory");
+    }
     Ok(data_dir.join("sessions.json"))
 }
 
-pub fn ensure_data_file() -> io::Result<PathBuf> {
-    let path = data_file_path()?;
-    if !path.exists() {
-        let mut file = fs::File::create(&path)?;
-        let initial = json!({ "sessions": [] });
-        file.write_all(initial.to_string().as_bytes())?;
+// ── Profiles ──────────────────────────────────────────────────────────
+
+/// Subdirectory of a data directory that holds named profiles.
+const PROFILES_DIR: &str = "profiles";
+
+/// Check a `--profile` name: letters, digits, `-`, `_` and `.`, not
+/// starting with a dot, so it is always a single plain directory name.
+pub fn parse_profile_name(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "invalid profile name {:?}: use letters, digits, '-', '_' or '.'",
+            name
+        ))
+    }
+}
+
+/// Directory of the store for `profile` under the data directory `base`;
+/// without a profile this is `base` itself.
+pub fn store_dir(base: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => base.join(PROFILES_DIR).join(name),
+        None => base.to_path_buf(),
+    }
+}
+
+/// Inverse of [`store_dir`]: the data directory a sessions file belongs to.
+pub fn store_root(data_file: &Path, profile: Option<&str>) -> PathBuf {
+    let dir = data_file.parent().unwrap_or(Path::new("."));
+    match profile {
+        Some(_) => dir
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(dir)
+            .to_path_buf(),
+        None => dir.to_path_buf(),
+    }
+}
+
+/// Names of the profiles under the data directory `base`, sorted.
+pub fn list_profiles(base: &Path) -> io::Result<Vec<String>> {
+    let dir = base.join(PROFILES_DIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(path_context(e, "list profiles in", &dir)),
+    };
+    let list_error = |e: io::Error| path_context(e, "list profiles in", &dir);
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(list_error)?;
+        if entry.file_type().map_err(list_error)?.is_dir() {
+            if let Some(name) = entry.file_name().to_str() {
+                if parse_profile_name(name).is_ok() {
+                    names.push(name.to_string());
+                }
+            }
+        }
     }
-    Ok(path)
+    names.sort();
+    Ok(names)
 }
 
-pub fn load_sessions() -> io::Result<Vec<Session>> {
-    let path = ensure_data_file()?;
-    let mut file = fs::File::open(path)?;
+/// Create the sessions file at `path` with an empty list if it is missing,
+/// and check that it can be written.
+///
+/// Fails with a message naming the path when the file or its directory is
+/// read-only, so mutating commands stop before touching anything.
+pub fn ensure_data_file(path: &Path) -> io::Result<()> {
+    let result = if path.exists() {
+        // Opening for writing without truncating probes permissions only.
+        fs::OpenOptions::new().write(true).open(path).map(drop)
+    } else {
+        debug!(path = %path.display(), "creating data file");
+        fs::File::create(path).and_then(|mut file| {
+            let initial = json!({ "sessions": [] });
+            file.write_all(initial.to_string().as_bytes())
+        })
+    };
+    result.map_err(|e| not_writable(path, e))
+}
+
+/// Wrap a write failure with the path and a hint, keeping the error kind.
+fn not_writable(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(
+        e.kind(),
+        format!(
+            "Data file {} is not writable ({}). Check the permissions of {} \
+             or run from a writable location; read-only commands (list, \
+             summary, export) still work.",
+            path.display(),
+            e,
+            path.parent().unwrap_or(path).display()
+        ),
+    )
+}
+
+/// Exclusive advisory lock on a sessions file, released when dropped.
+///
+/// Hold it across a load-modify-save cycle so two writers (the CLI and a
+/// running GUI, say) cannot interleave and lose each other's changes.
+#[derive(Debug)]
+pub struct SessionsLock {
+    _file: fs::File,
+}
+
+/// `sessions.json.lock` next to the sessions file. The lock lives on a
+/// separate file because saving rewrites the sessions file itself.
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Wait for and take the write lock on the sessions file at `path`.
+pub fn lock_sessions(path: &Path) -> io::Result<SessionsLock> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(path))
+        .map_err(|e| not_writable(path, e))?;
+    file.lock()
+        .map_err(|e| path_context(e, "lock", &lock_path(path)))?;
+    debug!(path = %path.display(), "locked sessions file");
+    Ok(SessionsLock { _file: file })
+}
+
+/// Load all sessions; a missing file means no sessions yet.
+///
+/// Reads both the `{"sessions": [...]}` store format and the bare array
+/// written by [`export_sessions`].
+pub fn load_sessions(path: &Path) -> io::Result<Vec<Session>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
     let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
+    fs::File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|e| path_context(e, "read sessions file", path))?;
 
-    let value: serde_json::Value = serde_json::from_str(&contents)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+        warn!(path = %path.display(), error = %e, "sessions file is not valid JSON");
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Sessions file {} is not valid JSON: {}", path.display(), e),
+        )
+    })?;
 
-    let sessions_value = value.get("sessions").cloned().unwrap_or_else(|| json!([]));
-    let sessions: Vec<Session> = serde_json::from_value(sessions_value)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    // Exports are a bare array; accept those as well as the wrapped form.
+    let sessions_value = match value {
+        serde_json::Value::Array(_) => value,
+        _ => value.get("sessions").cloned().unwrap_or_else(|| json!([])),
+    };
+    let sessions: Vec<Session> = serde_json::from_value(sessions_value).map_err(|e| {
+        warn!(path = %path.display(), error = %e, "sessions file has an unexpected shape");
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Sessions file {} has an unexpected shape: {}",
+                path.display(),
+                e
+            ),
+        )
+    })?;
 
+    debug!(count = sessions.len(), "loaded sessions");
     Ok(sessions)
 }
 
-pub fn save_sessions(sessions: &[Session]) -> io::Result<()> {
-    let path = ensure_data_file()?;
+pub fn save_sessions(path: &Path, sessions: &[Session]) -> io::Result<()> {
+    ensure_data_file(path)?;
+    debug!(count = sessions.len(), path = %path.display(), "saving sessions");
     let wrapper = json!({ "sessions": sessions });
     let pretty = serde_json::to_string_pretty(&wrapper)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    fs::write(path, pretty)?;
+    // Readers that don't take the lock never see a half-written file.
+    write_atomic(path, &pretty).map_err(|e| not_writable(path, e))
+}
+
+/// Write `contents` to a uniquely named temporary file beside `path`, sync
+/// it to disk and rename it over `path`, so a failed write leaves whatever
+/// was at `path` untouched. A file being replaced keeps its permissions.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    // Removed again on drop if anything below fails.
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(contents.as_bytes())?;
+    if let Ok(meta) = fs::metadata(path) {
+        tmp.as_file().set_permissions(meta.permissions())?;
+    }
+    tmp.as_file().sync_all()?;
+    tmp.persist(path).map_err(|e| e.error)?;
     Ok(())
 }
 
+/// Copy the sessions file to `sessions.json.<timestamp>.bak` beside it,
+/// before a destructive change. Returns the backup's path.
+pub fn backup_sessions(path: &Path, now: DateTime<Utc>) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.bak", now.format("%Y%m%d-%H%M%S")));
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup).map_err(|e| path_context(e, "back up", path))?;
+    debug!(backup = %backup.display(), "backed up sessions");
+    Ok(backup)
+}
+
+/// Write `sessions` as a JSON array to `path`, replacing it atomically.
 pub fn export_sessions<P: AsRef<Path>>(sessions: &[Session], path: P) -> io::Result<()> {
     let pretty = serde_json::to_string_pretty(&sessions)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    fs::write(path, pretty)?;
-    Ok(())
+    write_atomic(path.as_ref(), &pretty)
+        .map_err(|e| path_context(e, "export sessions to", path.as_ref()))
+}
+
+/// Like [`export_sessions`], with each session's derived totals alongside
+/// its fields (see [`Session::summary`]).
+pub fn export_sessions_with_summary<P: AsRef<Path>>(
+    sessions: &[Session],
+    path: P,
+) -> io::Result<()> {
+    let summaries: Vec<_> = sessions.iter().map(Session::summary).collect();
+    let pretty = serde_json::to_string_pretty(&summaries)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_atomic(path.as_ref(), &pretty)
+        .map_err(|e| path_context(e, "export sessions to", path.as_ref()))
 }
 
 /// Relative path from a Steam library root to the UE_game.log file.
@@ -84,30 +335,107 @@ fn steam_roots() -> Option<Vec<PathBuf>> {
 /// Several well-known Steam library root locations are probed, and any
 /// additional libraries listed in `libraryfolders.vdf` are also searched.
 pub fn detect_game_log() -> Option<PathBuf> {
-    let roots = steam_roots()?;
+    detect_game_logs().into_iter().next()
+}
 
-    // Check default Steam roots first
-    for root in &roots {
-        let candidate = root.join(TLI_LOG_RELATIVE);
-        if candidate.is_file() {
-            return Some(candidate);
-        }
+/// Make [`detect_game_logs`] return `path` for the rest of the process,
+/// e.g. to point the GUI at a generated demo log.
+pub fn use_game_log(path: &Path) {
+    debug!(path = %path.display(), "using game log override");
+    let _ = GAME_LOG_OVERRIDE.set(path.to_path_buf());
+}
+
+/// Every existing game log under the Steam roots and libraries, default
+/// roots first, with paths to the same file collapsed (see [`dedupe_logs`]).
+/// Just the log set with [`use_game_log`] if there is one.
+pub fn detect_game_logs() -> Vec<PathBuf> {
+    if let Some(path) = GAME_LOG_OVERRIDE.get() {
+        return vec![path.clone()];
     }
+    let Some(roots) = steam_roots() else {
+        return Vec::new();
+    };
+
+    // Check default Steam roots first
+    let mut candidates: Vec<PathBuf> = roots.iter().map(|r| r.join(TLI_LOG_RELATIVE)).collect();
 
-    // Search additional library folders referenced in libraryfolders.vdf
+    // Then additional library folders referenced in libraryfolders.vdf
     for root in &roots {
         let library_file = root.join("steamapps/libraryfolders.vdf");
         if let Some(paths) = parse_library_folders(&library_file) {
-            for lib_path in paths {
-                let candidate = lib_path.join(TLI_LOG_RELATIVE);
-                if candidate.is_file() {
-                    return Some(candidate);
-                }
+            candidates.extend(paths.iter().map(|lib| lib.join(TLI_LOG_RELATIVE)));
+        }
+    }
+
+    candidates.retain(|c| c.is_file());
+    dedupe_logs(candidates)
+}
+
+/// Drop logs that are the same file as an earlier one, keeping the order.
+///
+/// Paths are the same file when they canonicalize to the same path (symlinks,
+/// bind mounts seen twice through the library list) or when they have the
+/// same size and content (a copy synced to a network drive). Parsing both
+/// would count every drop twice.
+pub fn dedupe_logs(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    struct Kept {
+        canonical: PathBuf,
+        len: u64,
+        hash: Option<u64>,
+    }
+
+    let mut kept: Vec<Kept> = Vec::new();
+    let mut unique = Vec::new();
+    for path in paths {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if kept.iter().any(|k| k.canonical == canonical) {
+            debug!(path = %path.display(), "skipping log: same file as an earlier path");
+            continue;
+        }
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let mut hash = None;
+        let mut duplicate = false;
+        for other in kept.iter_mut().filter(|k| k.len == len) {
+            let Some(this_hash) = hash.or_else(|| content_hash(&path)) else {
+                break;
+            };
+            hash = Some(this_hash);
+            if other.hash.is_none() {
+                other.hash = content_hash(&other.canonical);
+            }
+            if other.hash == Some(this_hash) {
+                duplicate = true;
+                break;
             }
         }
+        if duplicate {
+            debug!(path = %path.display(), "skipping log: same content as an earlier path");
+            continue;
+        }
+        kept.push(Kept {
+            canonical,
+            len,
+            hash,
+        });
+        unique.push(path);
     }
+    unique
+}
 
-    None
+/// Hash of a file's bytes; only used to compare logs of equal size.
+fn content_hash(path: &Path) -> Option<u64> {
+    use std::hash::{DefaultHasher, Hasher};
+
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).ok()?;
+        if n == 0 {
+            return Some(hasher.finish());
+        }
+        hasher.write(&buf[..n]);
+    }
 }
 
 /// Minimal parser for Steam's `libraryfolders.vdf` to extract library paths.
@@ -121,12 +449,217 @@ fn parse_library_folders(vdf_path: &Path) -> Option<Vec<PathBuf>> {
         if let Some(rest) = trimmed.strip_prefix("\"path\"") {
             // The value is the next quoted string in the remainder of the line.
             let rest = rest.trim();
-            if rest.starts_with('"') {
-                if let Some(end) = rest[1..].find('"') {
-                    paths.push(PathBuf::from(&rest[1..1 + end]));
+            if let Some(quoted) = rest.strip_prefix('"') {
+                if let Some(end) = quoted.find('"') {
+                    paths.push(PathBuf::from(&quoted[..end]));
                 }
             }
         }
     }
     if paths.is_empty() { None } else { Some(paths) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_sessions_serializes_writers() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        let held = lock_sessions(&path).unwrap();
+        let acquired = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let _lock = lock_sessions(&path).unwrap();
+                acquired.store(true, Ordering::SeqCst);
+            });
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            assert!(!acquired.load(Ordering::SeqCst));
+            drop(held);
+        });
+        assert!(acquired.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_backup_sessions_copies_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        save_sessions(&path, &[]).unwrap();
+        let now = "2026-10-16T10:20:30Z".parse().unwrap();
+        let backup = backup_sessions(&path, now).unwrap();
+        assert_eq!(backup, dir.path().join("sessions.json.20261016-102030.bak"));
+        assert_eq!(
+            fs::read_to_string(&backup).unwrap(),
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dedupe_logs_same_content_and_same_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let copy = dir.path().join("synced/UE_game.log");
+        let other = dir.path().join("other/UE_game.log");
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::create_dir_all(other.parent().unwrap()).unwrap();
+        fs::write(&log, "BagMgr@:InitBagData 1 2 3\n").unwrap();
+        fs::write(&copy, "BagMgr@:InitBagData 1 2 3\n").unwrap();
+        // Same size, different bytes: a different log.
+        fs::write(&other, "BagMgr@:InitBagData 1 2 4\n").unwrap();
+        let dotted = dir.path().join("synced/../UE_game.log");
+
+        let unique = dedupe_logs(vec![log.clone(), dotted, copy, other.clone()]);
+        assert_eq!(unique, vec![log, other]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dedupe_logs_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let link = dir.path().join("link.log");
+        fs::write(&log, "x").unwrap();
+        std::os::unix::fs::symlink(&log, &link).unwrap();
+        assert_eq!(dedupe_logs(vec![link.clone(), log]), vec![link]);
+    }
+
+    #[test]
+    fn test_missing_file_loads_empty_without_creating() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        assert!(load_sessions(&path).unwrap().is_empty());
+        assert!(!path.exists());
+
+        ensure_data_file(&path).unwrap();
+        assert!(path.exists());
+        assert!(load_sessions(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_data_file_in_creates_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("profiles/league");
+        let path = data_file_in(&nested).unwrap();
+        assert_eq!(path, nested.join("sessions.json"));
+        assert!(nested.is_dir());
+
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let err = data_file_in(&file).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("not a directory"));
+    }
+
+    #[test]
+    fn test_profile_names() {
+        for name in ["league-x", "SS7_hardcore", "v1.2"] {
+            assert_eq!(parse_profile_name(name).unwrap(), name);
+        }
+        for name in ["", ".", "..", ".hidden", "a/b", "a\\b", "with space"] {
+            assert!(parse_profile_name(name).is_err(), "{name:?} accepted");
+        }
+    }
+
+    #[test]
+    fn test_profile_dirs_round_trip_and_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        assert!(list_profiles(base).unwrap().is_empty());
+
+        let store = store_dir(base, Some("league-x"));
+        assert_eq!(store, base.join("profiles/league-x"));
+        let data_file = data_file_in(&store).unwrap();
+        assert_eq!(store_root(&data_file, Some("league-x")), base);
+        assert_eq!(store_root(&base.join("sessions.json"), None), base);
+        assert_eq!(store_dir(base, None), base);
+
+        data_file_in(&store_dir(base, Some("alpha"))).unwrap();
+        fs::write(base.join("profiles/stray.txt"), "").unwrap();
+        assert_eq!(list_profiles(base).unwrap(), ["alpha", "league-x"]);
+    }
+
+    #[test]
+    fn test_unwritable_path_gives_friendly_error() {
+        let dir = tempfile::tempdir().unwrap();
+        // A regular file where the data directory should be.
+        let blocker = dir.path().join("blocker");
+        fs::write(&blocker, "").unwrap();
+        let path = blocker.join("sessions.json");
+
+        let err = ensure_data_file(&path).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&path.display().to_string()), "{message}");
+        assert!(message.contains("not writable"), "{message}");
+
+        let err = save_sessions(&path, &[]).unwrap_err();
+        assert!(err.to_string().contains("not writable"));
+    }
+
+    #[test]
+    fn test_export_replaces_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let export = dir.path().join("export.json");
+        fs::write(&export, "previous export").unwrap();
+        // A file of the user's that looks like a temporary one is left alone.
+        let lookalike = dir.path().join("export.json.tmp");
+        fs::write(&lookalike, "mine").unwrap();
+
+        export_sessions(&[], &export).unwrap();
+        assert_eq!(fs::read_to_string(&export).unwrap(), "[]");
+        assert_eq!(fs::read_to_string(&lookalike).unwrap(), "mine");
+
+        // The rename fails: what was there survives and nothing is left over.
+        let blocked = dir.path().join("blocked.json");
+        fs::create_dir(&blocked).unwrap();
+        let err = export_sessions(&[], &blocked).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to export sessions to"));
+        assert!(blocked.is_dir());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_load_sessions_accepts_wrapped_and_bare_array() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = Session {
+            id: "abc".to_string(),
+            map: "Netherrealm".to_string(),
+            notes: None,
+            start_time: Utc::now(),
+            end_time: None,
+            drops: Vec::new(),
+            runs: Vec::new(),
+            version: 3,
+            start_inventory: None,
+            end_inventory: None,
+        };
+        let wrapped = dir.path().join("sessions.json");
+        save_sessions(&wrapped, std::slice::from_ref(&session)).unwrap();
+        let exported = dir.path().join("export.json");
+        export_sessions(std::slice::from_ref(&session), &exported).unwrap();
+        assert!(fs::read_to_string(&exported).unwrap().starts_with('['));
+
+        let as_json = |path: &Path| serde_json::to_value(load_sessions(path).unwrap()).unwrap();
+        assert_eq!(as_json(&wrapped)[0]["id"], "abc");
+        assert_eq!(as_json(&wrapped), as_json(&exported));
+    }
+
+    #[test]
+    fn test_read_errors_name_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+        fs::write(&path, "{ not json").unwrap();
+        let message = load_sessions(&path).unwrap_err().to_string();
+        assert!(message.starts_with("Sessions file "), "{message}");
+        assert!(message.contains(&path.display().to_string()), "{message}");
+
+        let missing = dir.path().join("missing.json");
+        let err = backup_sessions(&missing, Utc::now()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err
+            .to_string()
+            .starts_with(&format!("Failed to back up {}: ", missing.display())));
+    }
+}
@@ -0,0 +1,1047 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, debug_span, info, warn};
+use uuid::Uuid;
+
+use crate::inventory::{self, InventoryRow};
+use crate::log_parser::{self, BagEvent, LootSummary};
+use crate::log_source::{FileLogSource, HttpLogSource, LogSource, TailedLog};
+use crate::models::{RunRecord, Session};
+use crate::records::{self, Records};
+use crate::settings::{self, Settings};
+use crate::storage;
+
+/// Default interval between log re-parses (see `Settings::poll_interval`).
+pub const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Loot deltas as of the previous poll, to tell new pickups from old ones.
+#[derive(Debug, Default)]
+struct LootBaseline {
+    deltas: HashMap<String, i64>,
+    sorts_seen: usize,
+    game_start: Option<String>,
+}
+
+impl LootBaseline {
+    fn from_summary(loot: &LootSummary) -> Self {
+        Self {
+            deltas: loot
+                .items
+                .iter()
+                .map(|i| (i.config_base_id.clone(), i.delta))
+                .collect(),
+            sorts_seen: loot.sorts_seen,
+            game_start: loot.game_start.clone(),
+        }
+    }
+
+    /// Move to `loot` and return what was gained since the previous poll.
+    ///
+    /// An inventory sort in between makes the parser measure deltas from
+    /// the new sort, so everything it reports is new; diffing against the
+    /// old deltas would count the re-layout as a huge loss or gain. A game
+    /// relaunch likewise restarts the deltas from the login inventory; the
+    /// session carries on with what it had gained before.
+    fn advance(&mut self, loot: &LootSummary) -> Vec<(String, i64)> {
+        let next = Self::from_summary(loot);
+        if next.game_start != self.game_start {
+            info!("game relaunched; continuing session from the new login inventory");
+            self.deltas.clear();
+        } else if next.sorts_seen != self.sorts_seen {
+            debug!(
+                sorts = next.sorts_seen,
+                "inventory sorted; rebaselining session loot"
+            );
+            self.deltas.clear();
+        }
+        let mut gains: Vec<(String, i64)> = next
+            .deltas
+            .iter()
+            .map(|(cid, &delta)| {
+                (
+                    cid.clone(),
+                    delta - self.deltas.get(cid).copied().unwrap_or(0),
+                )
+            })
+            .filter(|&(_, diff)| diff != 0)
+            .collect();
+        gains.sort();
+        *self = next;
+        gains
+    }
+}
+
+/// How the local game log changed since the previous poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogChange {
+    /// Same file as before, or still no log.
+    Unchanged,
+    /// A log appeared where there was none (e.g. the game started).
+    Found,
+    /// The log file is gone; detection starts over.
+    Lost,
+    /// A different file now sits at the log path (e.g. after a reinstall).
+    Replaced,
+}
+
+impl LogChange {
+    /// Whether a file watcher needs setting up again.
+    pub fn rearm(self) -> bool {
+        matches!(self, LogChange::Found | LogChange::Replaced)
+    }
+}
+
+/// Where the game log is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LogTarget {
+    Url(String),
+    File(PathBuf),
+}
+
+impl LogTarget {
+    fn open(&self) -> TailedLog {
+        let source: Box<dyn LogSource> = match self {
+            LogTarget::Url(url) => Box::new(HttpLogSource::new(url)),
+            LogTarget::File(path) => Box::new(FileLogSource::new(path)),
+        };
+        TailedLog::new(source)
+    }
+}
+
+/// Tells one file from another at the same path, so a deleted and recreated
+/// log is noticed.
+#[cfg(unix)]
+fn file_identity(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| m.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(path: &Path) -> Option<u64> {
+    let created = std::fs::metadata(path).ok()?.created().ok()?;
+    let since_epoch = created.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_nanos() as u64)
+}
+
+/// Check the log at `path` is still the file it was (`identity`), and
+/// `detect` one when there is none.
+fn track_log_path(
+    path: &mut Option<PathBuf>,
+    identity: &mut Option<u64>,
+    detect: impl FnOnce() -> Option<PathBuf>,
+) -> LogChange {
+    if let Some(current) = path.as_deref() {
+        match file_identity(current) {
+            None => {
+                debug!(path = %current.display(), "game log disappeared");
+                *path = None;
+                *identity = None;
+                return LogChange::Lost;
+            }
+            Some(id) if Some(id) != *identity => {
+                debug!(path = %current.display(), "game log was replaced");
+                *identity = Some(id);
+                return LogChange::Replaced;
+            }
+            Some(_) => return LogChange::Unchanged,
+        }
+    }
+    match detect() {
+        Some(found) => {
+            debug!(path = %found.display(), "game log detected");
+            *identity = file_identity(&found);
+            *path = Some(found);
+            LogChange::Found
+        }
+        None => LogChange::Unchanged,
+    }
+}
+
+/// Status-bar text for where the log is read from.
+fn log_status(log_url: Option<&str>, log_path: Option<&Path>) -> String {
+    match (log_url, log_path) {
+        (Some(url), _) => format!("Remote log: {}", url),
+        (None, Some(p)) => format!("Log found: {}", p.display()),
+        (None, None) => {
+            "UE_game.log not found – start Torchlight Infinite with logging enabled".to_string()
+        }
+    }
+}
+
+/// A gap between polls longer than this many poll intervals is taken to be
+/// the machine sleeping.
+pub const SLEEP_GAP_FACTOR: u32 = 5;
+
+/// The stretch to leave out of session time when two polls at `prev` and
+/// `now` are too far apart to be explained by anything but sleep.
+///
+/// The first `interval` after `prev` is kept as normal polling time. A log
+/// last written inside the gap, more than an interval before `now`, shows
+/// the game was running, so the gap was a slow poll rather than sleep.
+pub fn sleep_gap(
+    prev: DateTime<Utc>,
+    now: DateTime<Utc>,
+    interval: Duration,
+    log_written: Option<DateTime<Utc>>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let interval = chrono::Duration::from_std(interval).ok()?;
+    if now - prev <= interval * SLEEP_GAP_FACTOR as i32 {
+        return None;
+    }
+    let gap = (prev + interval, now);
+    match log_written {
+        Some(at) if at > gap.0 && at < now - interval => None,
+        _ => Some(gap),
+    }
+}
+
+/// When the file at `path` was last written.
+fn modified_at(path: &Path) -> Option<DateTime<Utc>> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.into())
+}
+
+/// Seconds of the gap `(from, to)` that fall after `start`.
+fn overlap_secs(gap: (DateTime<Utc>, DateTime<Utc>), start: DateTime<Utc>) -> f64 {
+    wall_secs(gap.0.max(start), gap.1)
+}
+
+// ── Per-map run tracking ──────────────────────────────────────────────
+
+/// Seconds from `start` to `end` by the wall clock; never negative.
+pub fn wall_secs(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    (end - start).num_milliseconds().max(0) as f64 / 1000.0
+}
+
+#[derive(Debug, Clone)]
+pub struct MapRun {
+    pub map_name: String,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    pub loot_gained: HashMap<String, i64>,
+    /// Time the machine slept during the run, left out of its duration.
+    pub paused_secs: f64,
+    /// Map tier/level from the zone path, if it had one.
+    pub tier: Option<u32>,
+}
+
+impl MapRun {
+    /// Wall-clock length of the run; an open run counts up to now.
+    pub fn duration_secs(&self) -> f64 {
+        self.duration_secs_at(Utc::now())
+    }
+
+    /// Length of the run without sleep, with an open run ending at `now`.
+    pub fn duration_secs_at(&self, now: DateTime<Utc>) -> f64 {
+        (wall_secs(self.start, self.end.unwrap_or(now)) - self.paused_secs).max(0.0)
+    }
+
+    pub fn total_items(&self) -> i64 {
+        self.loot_gained.values().sum()
+    }
+
+    /// Amount of the currency `config_base_id` gained during this run.
+    pub fn currency(&self, config_base_id: &str) -> i64 {
+        self.loot_gained.get(config_base_id).copied().unwrap_or(0)
+    }
+
+    /// The saved form of this run; a run still open ends at `end`.
+    fn record(&self, primary: &str, end: DateTime<Utc>) -> RunRecord {
+        RunRecord {
+            map: self.map_name.clone(),
+            duration_secs: self.duration_secs_at(end),
+            fe: self.currency(primary),
+            items: self.total_items(),
+            started_at: Some(self.start),
+            ended_at: Some(self.end.unwrap_or(end)),
+            tier: self.tier,
+        }
+    }
+}
+
+// ── Session ───────────────────────────────────────────────────────────
+
+/// A stopped session's totals, frozen at the moment it ended so they do not
+/// drift with the clock afterwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalizedSession {
+    pub ended_at: DateTime<Utc>,
+    /// Session length at the end, sleep excluded.
+    pub elapsed_secs: f64,
+    /// Primary currency gained.
+    pub primary: i64,
+    pub runs: usize,
+}
+
+impl FinalizedSession {
+    /// Primary currency per hour over the whole session; 0 under a second.
+    pub fn primary_per_hour(&self) -> f64 {
+        if self.elapsed_secs < 1.0 {
+            0.0
+        } else {
+            self.primary as f64 / self.elapsed_secs * 3600.0
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackerSession {
+    pub start_wall: DateTime<Utc>,
+    pub cumulative_loot: HashMap<String, i64>,
+    pub runs: Vec<MapRun>,
+    /// Time the machine slept during the session, left out of elapsed time.
+    pub paused_secs: f64,
+    /// Inventory when the session started, if one had been read.
+    pub start_inventory: Option<Vec<InventoryRow>>,
+}
+
+impl Default for TrackerSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackerSession {
+    pub fn new() -> Self {
+        Self {
+            start_wall: Utc::now(),
+            cumulative_loot: HashMap::new(),
+            runs: Vec::new(),
+            paused_secs: 0.0,
+            start_inventory: None,
+        }
+    }
+
+    /// Session length by the wall clock from `start_wall` to `now`, so it
+    /// stays meaningful across restarts and is not tied to this process.
+    /// Time spent asleep is left out.
+    pub fn elapsed_secs(&self, now: DateTime<Utc>) -> f64 {
+        (wall_secs(self.start_wall, now) - self.paused_secs).max(0.0)
+    }
+
+    /// Leave a sleep `gap` (see [`sleep_gap`]) out of the session and the
+    /// open run, counting only the part after each one started.
+    pub fn exclude_gap(&mut self, gap: (DateTime<Utc>, DateTime<Utc>)) {
+        self.paused_secs += overlap_secs(gap, self.start_wall);
+        if let Some(run) = self.runs.last_mut().filter(|r| r.end.is_none()) {
+            run.paused_secs += overlap_secs(gap, run.start);
+        }
+    }
+
+    /// The run in progress, if the current map has not been left yet.
+    pub fn open_run(&self) -> Option<&MapRun> {
+        self.runs.last().filter(|r| r.end.is_none())
+    }
+
+    /// Close the open run (if any) and start one on `map` at `tier`.
+    fn enter_map(&mut self, map: &str, tier: Option<u32>) {
+        if let Some(run) = self.runs.last() {
+            if run.end.is_none() && run.map_name == map && run.tier == tier {
+                return;
+            }
+        }
+        let now = Utc::now();
+        if let Some(run) = self.runs.last_mut() {
+            run.end.get_or_insert(now);
+        }
+        self.runs.push(MapRun {
+            map_name: map.to_string(),
+            start: now,
+            end: None,
+            loot_gained: HashMap::new(),
+            paused_secs: 0.0,
+            tier,
+        });
+    }
+
+    /// Add a loot change to the session and the open run.
+    fn add_loot(&mut self, config_base_id: &str, diff: i64) {
+        *self
+            .cumulative_loot
+            .entry(config_base_id.to_string())
+            .or_insert(0) += diff;
+        if let Some(run) = self.runs.last_mut().filter(|r| r.end.is_none()) {
+            *run.loot_gained
+                .entry(config_base_id.to_string())
+                .or_insert(0) += diff;
+        }
+    }
+
+    /// Build the persisted form of this session, ending it at `end`.
+    ///
+    /// The session's map is the one with the most run time; drops are left
+    /// empty since the tracker has no prices. Run `fe` records the
+    /// `primary` currency.
+    pub fn to_record(
+        &self,
+        primary: &str,
+        fallback_map: Option<&str>,
+        end: DateTime<Utc>,
+    ) -> Session {
+        let runs: Vec<RunRecord> = self.runs.iter().map(|r| r.record(primary, end)).collect();
+        let mut time_per_map: HashMap<&str, f64> = HashMap::new();
+        for run in &runs {
+            *time_per_map.entry(&run.map).or_insert(0.0) += run.duration_secs;
+        }
+        let map = time_per_map
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(a.0)))
+            .map(|(map, _)| map)
+            .or(fallback_map)
+            .unwrap_or("Unknown")
+            .to_string();
+        Session {
+            id: Uuid::new_v4().to_string(),
+            map,
+            notes: Some("Recorded by tracker".to_string()),
+            start_time: self.start_wall,
+            end_time: Some(end),
+            drops: Vec::new(),
+            runs,
+            version: 0,
+            start_inventory: self.start_inventory.clone(),
+            end_inventory: None,
+        }
+    }
+
+    /// Freeze the session's totals for `primary` as of `end`.
+    pub fn finalize(&self, primary: &str, end: DateTime<Utc>) -> FinalizedSession {
+        FinalizedSession {
+            ended_at: end,
+            elapsed_secs: self.elapsed_secs(end),
+            primary: self.currency(primary),
+            runs: self.runs.len(),
+        }
+    }
+
+    /// Total amount of the currency `config_base_id` gained this session.
+    pub fn currency(&self, config_base_id: &str) -> i64 {
+        self.cumulative_loot
+            .get(config_base_id)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+// ── Live tracker state ────────────────────────────────────────────────
+
+/// Sessions from the default sessions file; empty if it can't be read.
+fn load_saved_sessions() -> Vec<Session> {
+    storage::data_file_path()
+        .and_then(|path| storage::load_sessions(&path))
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "failed to load saved sessions");
+            Vec::new()
+        })
+}
+
+/// Log-driven tracking state shared by the GUI and TUI frontends.
+pub struct Tracker {
+    pub log_path: Option<PathBuf>,
+    pub log_status: String,
+    /// `file_identity` of `log_path` when it was found.
+    log_identity: Option<u64>,
+    /// The log being read and where from; reopened when that changes.
+    log: Option<(LogTarget, TailedLog)>,
+
+    // Current parsed data
+    pub loot: Option<LootSummary>,
+    pub inventory: Vec<BagEvent>,
+    pub current_map: Option<String>,
+    pub current_tier: Option<u32>,
+
+    // Session
+    pub session: Option<TrackerSession>,
+    /// Totals of the most recently stopped session.
+    pub last_session: Option<FinalizedSession>,
+
+    /// Display and tracking preferences (headline currency, formatting).
+    pub settings: Settings,
+    /// Lifetime bests, updated when a session stops.
+    pub records: Records,
+    /// Sessions in the shared sessions file, as last loaded.
+    pub saved_sessions: Vec<Session>,
+
+    // Previous loot state for delta tracking
+    prev_loot: LootBaseline,
+    /// Wall-clock time of the previous poll, for sleep detection.
+    last_poll_at: Option<DateTime<Utc>>,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        let settings = settings::load_default();
+        let log_path = storage::detect_game_log();
+        let log_status = log_status(settings.log_url.as_deref(), log_path.as_deref());
+
+        Self {
+            log_identity: log_path.as_deref().and_then(file_identity),
+            log_path,
+            log_status,
+            log: None,
+            loot: None,
+            inventory: Vec::new(),
+            current_map: None,
+            current_tier: None,
+            session: None,
+            last_session: None,
+            settings,
+            records: storage::data_file_path()
+                .map(|path| records::load(&records::records_path(&path)))
+                .unwrap_or_default(),
+            saved_sessions: load_saved_sessions(),
+            prev_loot: LootBaseline::default(),
+            last_poll_at: None,
+        }
+    }
+
+    /// Re-read the sessions file, picking up drops added from the CLI.
+    pub fn reload_saved_sessions(&mut self) {
+        self.saved_sessions = load_saved_sessions();
+    }
+
+    /// Re-parse the log and fold new loot into the active session.
+    ///
+    /// Returns how the local log file changed, so the caller can (re)arm
+    /// or drop any file watcher.
+    pub fn poll(&mut self) -> LogChange {
+        let _span = debug_span!("poll_log").entered();
+        let now = Utc::now();
+        if let (Some(prev), Some(session)) = (self.last_poll_at, &mut self.session) {
+            let log_written = match (&self.settings.log_url, &self.log_path) {
+                (None, Some(path)) => modified_at(path),
+                _ => None,
+            };
+            if let Some(gap) = sleep_gap(prev, now, self.settings.poll_interval(), log_written) {
+                info!(
+                    secs = wall_secs(gap.0, gap.1),
+                    "long gap between polls; assuming sleep and pausing session time"
+                );
+                session.exclude_gap(gap);
+            }
+        }
+        let mut change = LogChange::Unchanged;
+        if self.settings.log_url.is_none() {
+            change = track_log_path(
+                &mut self.log_path,
+                &mut self.log_identity,
+                storage::detect_game_log,
+            );
+            if change == LogChange::Lost {
+                // Maybe it moved, e.g. to another Steam library.
+                if track_log_path(
+                    &mut self.log_path,
+                    &mut self.log_identity,
+                    storage::detect_game_log,
+                ) == LogChange::Found
+                {
+                    change = LogChange::Replaced;
+                }
+            }
+            if change != LogChange::Unchanged {
+                self.log_status = log_status(None, self.log_path.as_deref());
+            }
+        }
+
+        let target = self.log_target();
+        if change != LogChange::Unchanged || self.log.as_ref().map(|(t, _)| t) != target.as_ref() {
+            self.log = target.map(|t| {
+                let log = t.open();
+                (t, log)
+            });
+        }
+        if let Some((_, log)) = &mut self.log {
+            let contexts = &self.settings.loot_contexts;
+            match log
+                .read()
+                .map(|text| log_parser::snapshot_from_text(text, contexts))
+            {
+                Ok(snapshot) => {
+                    // Track deltas for session
+                    if let Some(ref mut session) = self.session {
+                        for (cid, diff) in self.prev_loot.advance(&snapshot.loot) {
+                            session.add_loot(&cid, diff);
+                        }
+                    }
+
+                    self.loot = Some(snapshot.loot);
+                    self.inventory = snapshot.inventory;
+                    if snapshot.map.is_some() {
+                        self.current_map = snapshot.map;
+                        self.current_tier = snapshot.map_tier;
+                    }
+                }
+                Err(e) => {
+                    warn!(source = %log.name(), error = %e, "failed to parse game log");
+                    self.log_status = format!("Error reading log: {}", e);
+                }
+            }
+
+            if let (Some(session), Some(map)) = (&mut self.session, &self.current_map) {
+                session.enter_map(map, self.current_tier);
+            }
+        }
+
+        // After the read, so a slow one doesn't count towards the next gap.
+        self.last_poll_at = Some(Utc::now());
+        change
+    }
+
+    /// Where this poll reads the game log from: the remote URL in settings,
+    /// otherwise the detected local file.
+    fn log_target(&self) -> Option<LogTarget> {
+        match (&self.settings.log_url, &self.log_path) {
+            (Some(url), _) => Some(LogTarget::Url(url.clone())),
+            (None, Some(path)) => Some(LogTarget::File(path.clone())),
+            (None, None) => None,
+        }
+    }
+
+    /// Switch between a remote log (`Some(url)`) and the local file.
+    pub fn set_log_url(&mut self, url: Option<String>) {
+        self.settings.log_url = url;
+        self.log_status = log_status(self.settings.log_url.as_deref(), self.log_path.as_deref());
+    }
+
+    /// Warning for bag pages that are nearly full, if any.
+    pub fn bag_alert(&self) -> Option<String> {
+        let pages = inventory::page_fullness(&self.inventory, &self.settings.page_capacities);
+        inventory::fullness_warning(&pages, self.settings.bag_warn_percent)
+    }
+
+    /// Persist `self.settings` after an edit in the GUI.
+    pub fn save_settings(&self) {
+        if let Err(e) = settings::save_default(&self.settings) {
+            warn!(error = %e, "failed to save settings");
+        }
+    }
+
+    /// Whether there is a log to read, local or remote.
+    pub fn has_log(&self) -> bool {
+        self.settings.log_url.is_some() || self.log_path.is_some()
+    }
+
+    pub fn start_session(&mut self) {
+        let mut session = TrackerSession::new();
+
+        // Snapshot current loot state
+        if let Some(ref loot) = self.loot {
+            self.prev_loot = LootBaseline::from_summary(loot);
+        }
+        session.cumulative_loot.clear();
+        session.start_inventory = inventory::session_snapshot(&self.inventory);
+        if let Some(ref map) = self.current_map {
+            session.enter_map(map, self.current_tier);
+        }
+
+        self.session = Some(session);
+    }
+
+    /// End the session and append it to the sessions file.
+    pub fn stop_session(&mut self) {
+        if let Some(session) = self.session.take() {
+            let end = Utc::now();
+            self.last_session = Some(session.finalize(&self.settings.primary_currency, end));
+            let mut record = session.to_record(
+                &self.settings.primary_currency,
+                self.current_map.as_deref(),
+                end,
+            );
+            record.end_inventory = inventory::session_snapshot(&self.inventory);
+            let saved = storage::data_file_path().and_then(|path| {
+                let _lock = storage::lock_sessions(&path)?;
+                let mut sessions = storage::load_sessions(&path)?;
+                sessions.push(record.clone());
+                storage::save_sessions(&path, &sessions)?;
+                Ok((path, sessions))
+            });
+            match saved {
+                Ok((path, sessions)) => {
+                    self.saved_sessions = sessions;
+                    let broken = self.records.update(&record);
+                    if !broken.is_empty() {
+                        info!(records = ?broken, "new personal best");
+                        if let Err(e) = records::save(&records::records_path(&path), &self.records)
+                        {
+                            warn!(error = %e, "failed to save records");
+                        }
+                    }
+                }
+                Err(e) => warn!(error = %e, "failed to save tracker session"),
+            }
+        }
+        self.prev_loot = LootBaseline::default();
+    }
+
+    /// Start a session if none is running, otherwise stop the current one.
+    pub fn toggle_session(&mut self) {
+        if self.session.is_some() {
+            self.stop_session();
+        } else {
+            self.start_session();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::FLAME_ELEMENTIUM_ID;
+    use std::fs;
+
+    #[test]
+    fn test_log_path_redetection() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("UE_game.log");
+        let detect = || log.exists().then(|| log.clone());
+        let (mut path, mut identity) = (None, None);
+
+        // The game isn't running yet.
+        assert_eq!(
+            track_log_path(&mut path, &mut identity, detect),
+            LogChange::Unchanged
+        );
+        assert_eq!(path, None);
+
+        // It starts and writes its log: found once, then left alone.
+        fs::write(&log, "one\n").unwrap();
+        let change = track_log_path(&mut path, &mut identity, detect);
+        assert_eq!(change, LogChange::Found);
+        assert!(change.rearm());
+        assert_eq!(path.as_deref(), Some(log.as_path()));
+        fs::write(&log, "one\ntwo\n").unwrap();
+        assert_eq!(
+            track_log_path(&mut path, &mut identity, || panic!("no need to detect")),
+            LogChange::Unchanged
+        );
+
+        // Reinstalled: a new file takes the old one's place.
+        let fresh = dir.path().join("fresh.log");
+        fs::write(&fresh, "").unwrap();
+        fs::rename(&fresh, &log).unwrap();
+        assert_eq!(
+            track_log_path(&mut path, &mut identity, detect),
+            LogChange::Replaced
+        );
+
+        // Deleted: detection starts over.
+        fs::remove_file(&log).unwrap();
+        let change = track_log_path(&mut path, &mut identity, detect);
+        assert_eq!(change, LogChange::Lost);
+        assert!(!change.rearm());
+        assert_eq!((path.clone(), identity), (None, None));
+        fs::write(&log, "").unwrap();
+        assert_eq!(
+            track_log_path(&mut path, &mut identity, detect),
+            LogChange::Found
+        );
+    }
+
+    #[test]
+    fn test_enter_map_closes_previous_run() {
+        let mut session = TrackerSession::new();
+        session.enter_map("Abyss", None);
+        session.enter_map("Abyss", None);
+        assert_eq!(session.runs.len(), 1);
+
+        session.enter_map("Rift", None);
+        assert_eq!(session.runs.len(), 2);
+        assert!(session.runs[0].end.is_some());
+        assert!(session.runs[1].end.is_none());
+
+        // The same map at another tier is a new run.
+        session.enter_map("Rift", Some(300));
+        assert_eq!(session.runs.len(), 3);
+        let record = session.to_record(FLAME_ELEMENTIUM_ID, None, Utc::now());
+        assert_eq!(record.runs[2].tier, Some(300));
+    }
+
+    #[test]
+    fn test_loot_goes_to_session_and_open_run() {
+        let mut session = TrackerSession::new();
+        session.add_loot(FLAME_ELEMENTIUM_ID, 5);
+        session.enter_map("Abyss", None);
+        session.add_loot(FLAME_ELEMENTIUM_ID, 10);
+        session.add_loot("200", 2);
+        session.enter_map("Rift", None);
+        session.add_loot(FLAME_ELEMENTIUM_ID, 1);
+
+        assert_eq!(session.currency(FLAME_ELEMENTIUM_ID), 16);
+        assert_eq!(session.runs[0].currency(FLAME_ELEMENTIUM_ID), 10);
+        assert_eq!(session.runs[0].currency("200"), 2);
+        assert_eq!(session.runs[0].total_items(), 12);
+        assert_eq!(session.runs[1].currency(FLAME_ELEMENTIUM_ID), 1);
+    }
+
+    fn loot(sorts_seen: usize, deltas: &[(&str, i64)]) -> LootSummary {
+        LootSummary {
+            items: deltas
+                .iter()
+                .map(|&(cid, delta)| log_parser::ItemDelta {
+                    config_base_id: cid.to_string(),
+                    item_name: log_parser::item_name(cid),
+                    delta,
+                    current: 0,
+                })
+                .collect(),
+            total_events: 0,
+            baseline_found: sorts_seen > 0,
+            events_seen: true,
+            last_sort_at: None,
+            sorts_seen,
+            anomalies: Default::default(),
+            game_start: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_between_polls_rebaselines_session_loot() {
+        let mut session = TrackerSession::new();
+        let mut baseline = LootBaseline::from_summary(&loot(1, &[(FLAME_ELEMENTIUM_ID, 100)]));
+
+        let poll = loot(1, &[(FLAME_ELEMENTIUM_ID, 130), ("200100", 2)]);
+        for (cid, diff) in baseline.advance(&poll) {
+            session.add_loot(&cid, diff);
+        }
+        assert_eq!(session.cumulative_loot[FLAME_ELEMENTIUM_ID], 30);
+
+        // Sorted: the parser now counts from zero again, and only the 5 FE
+        // picked up after the sort are new.
+        let poll = loot(2, &[(FLAME_ELEMENTIUM_ID, 5)]);
+        for (cid, diff) in baseline.advance(&poll) {
+            session.add_loot(&cid, diff);
+        }
+        assert_eq!(session.cumulative_loot[FLAME_ELEMENTIUM_ID], 35);
+        assert_eq!(session.cumulative_loot["200100"], 2);
+
+        // Nothing new.
+        assert!(baseline.advance(&poll).is_empty());
+    }
+
+    #[test]
+    fn test_game_relaunch_continues_session() {
+        let mut session = TrackerSession::new();
+        let mut baseline = LootBaseline::from_summary(&loot(0, &[(FLAME_ELEMENTIUM_ID, 50)]));
+        let poll = loot(0, &[(FLAME_ELEMENTIUM_ID, 80)]);
+        for (cid, diff) in baseline.advance(&poll) {
+            session.add_loot(&cid, diff);
+        }
+
+        // Relaunched with no sort in either log: counting from the new login
+        // inventory, the 10 FE are new rather than a 70 FE loss.
+        let mut poll = loot(0, &[(FLAME_ELEMENTIUM_ID, 10)]);
+        poll.game_start = Some("Log file open, 10/16/26 11:00:00".to_string());
+        for (cid, diff) in baseline.advance(&poll) {
+            session.add_loot(&cid, diff);
+        }
+        assert_eq!(session.cumulative_loot[FLAME_ELEMENTIUM_ID], 40);
+        assert!(baseline.advance(&poll).is_empty());
+    }
+
+    #[test]
+    fn test_to_record_picks_longest_map() {
+        let mut session = TrackerSession::new();
+        let start = session.start_wall;
+        for (map, secs) in [("Abyss", 60), ("Rift", 200), ("Abyss", 100)] {
+            session.runs.push(MapRun {
+                map_name: map.to_string(),
+                start,
+                end: Some(start + chrono::Duration::seconds(secs)),
+                loot_gained: HashMap::from([(FLAME_ELEMENTIUM_ID.to_string(), 3)]),
+                paused_secs: 0.0,
+                tier: None,
+            });
+        }
+        let end = session.start_wall + chrono::Duration::minutes(6);
+
+        let record = session.to_record(FLAME_ELEMENTIUM_ID, Some("Hideout"), end);
+        assert_eq!(record.map, "Rift");
+        assert_eq!(record.start_time, session.start_wall);
+        assert_eq!(record.end_time, Some(end));
+        assert!(record.drops.is_empty());
+        assert_eq!(record.runs.len(), 3);
+        assert_eq!(record.runs[1].duration_secs, 200.0);
+        assert_eq!(record.runs[1].fe, 3);
+
+        let other = session.to_record("200", None, end);
+        assert_eq!(other.runs[1].fe, 0);
+    }
+
+    #[test]
+    fn test_elapsed_is_wall_clock_difference() {
+        let mut session = TrackerSession::new();
+        session.start_wall = "2024-05-01T10:00:00Z".parse().unwrap();
+        let now = "2024-05-01T11:30:15.500Z".parse().unwrap();
+        assert_eq!(session.elapsed_secs(now), 5415.5);
+        // A clock set back before the start never goes negative.
+        assert_eq!(
+            session.elapsed_secs(session.start_wall - chrono::Duration::hours(1)),
+            0.0
+        );
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        format!("2024-05-01T{time}Z").parse().unwrap()
+    }
+
+    #[test]
+    fn test_sleep_gap_detection() {
+        // Regular and slightly late polls are not sleep.
+        assert_eq!(
+            sleep_gap(at("10:00:00"), at("10:00:03"), POLL_INTERVAL, None),
+            None
+        );
+        assert_eq!(
+            sleep_gap(at("10:00:00"), at("10:00:15"), POLL_INTERVAL, None),
+            None
+        );
+        // Past 5 intervals, everything after the first interval is excluded.
+        assert_eq!(
+            sleep_gap(at("10:00:00"), at("10:00:16"), POLL_INTERVAL, None),
+            Some((at("10:00:03"), at("10:00:16")))
+        );
+        assert_eq!(
+            sleep_gap(at("10:00:00"), at("11:00:00"), POLL_INTERVAL, None),
+            Some((at("10:00:03"), at("11:00:00")))
+        );
+        // The log was written during the gap: the game was running.
+        assert_eq!(
+            sleep_gap(
+                at("10:00:00"),
+                at("11:00:00"),
+                POLL_INTERVAL,
+                Some(at("10:30:00"))
+            ),
+            None
+        );
+        // Written before the gap, or as the game woke up: still sleep.
+        for written in ["09:59:00", "10:59:58"] {
+            assert_eq!(
+                sleep_gap(
+                    at("10:00:00"),
+                    at("11:00:00"),
+                    POLL_INTERVAL,
+                    Some(at(written))
+                ),
+                Some((at("10:00:03"), at("11:00:00")))
+            );
+        }
+        // A clock set backwards is never sleep.
+        assert_eq!(
+            sleep_gap(at("10:00:00"), at("09:00:00"), POLL_INTERVAL, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_exclude_gap_from_session_and_open_run() {
+        let mut session = TrackerSession::new();
+        session.start_wall = at("10:00:00");
+        session.runs.push(MapRun {
+            map_name: "Abyss".to_string(),
+            start: at("10:00:00"),
+            end: Some(at("10:10:00")),
+            loot_gained: HashMap::new(),
+            paused_secs: 0.0,
+            tier: None,
+        });
+        session.runs.push(MapRun {
+            map_name: "Rift".to_string(),
+            start: at("10:10:00"),
+            end: None,
+            loot_gained: HashMap::new(),
+            paused_secs: 0.0,
+            tier: None,
+        });
+
+        // Last poll at 10:20:00, woke up at 11:20:00.
+        let gap = sleep_gap(at("10:20:00"), at("11:20:00"), POLL_INTERVAL, None).unwrap();
+        session.exclude_gap(gap);
+        assert_eq!(session.paused_secs, 3597.0);
+        assert_eq!(session.elapsed_secs(at("11:20:00")), 1203.0);
+        assert_eq!(session.runs[0].paused_secs, 0.0);
+        assert_eq!(session.runs[1].duration_secs_at(at("11:20:00")), 603.0);
+    }
+
+    #[test]
+    fn test_exclude_gap_only_counts_time_after_start() {
+        // The session started while the previous poll was long ago.
+        let mut session = TrackerSession::new();
+        session.start_wall = at("10:30:00");
+        let gap = sleep_gap(at("10:00:00"), at("10:31:00"), POLL_INTERVAL, None).unwrap();
+        session.exclude_gap(gap);
+        assert_eq!(session.paused_secs, 60.0);
+        assert_eq!(session.elapsed_secs(at("10:31:00")), 0.0);
+    }
+
+    #[test]
+    fn test_open_run_is_recorded_up_to_session_end() {
+        let mut session = TrackerSession::new();
+        session.start_wall = "2024-05-01T10:00:00Z".parse().unwrap();
+        session.runs.push(MapRun {
+            map_name: "Abyss".to_string(),
+            start: session.start_wall,
+            end: None,
+            loot_gained: HashMap::new(),
+            paused_secs: 0.0,
+            tier: None,
+        });
+        let end = session.start_wall + chrono::Duration::minutes(5);
+        assert_eq!(session.runs[0].duration_secs_at(end), 300.0);
+        let record = session.to_record(FLAME_ELEMENTIUM_ID, None, end);
+        assert_eq!(record.runs[0].duration_secs, 300.0);
+        assert_eq!(record.runs[0].started_at, Some(session.start_wall));
+        assert_eq!(record.runs[0].ended_at, Some(end));
+    }
+
+    #[test]
+    fn test_run_record_keeps_wall_times_and_excludes_pauses() {
+        let run = MapRun {
+            map_name: "Abyss".to_string(),
+            start: at("10:00:00"),
+            end: Some(at("10:10:00")),
+            loot_gained: HashMap::new(),
+            paused_secs: 120.0,
+            tier: None,
+        };
+        let record = run.record(FLAME_ELEMENTIUM_ID, at("11:00:00"));
+        assert_eq!(record.started_at, Some(at("10:00:00")));
+        assert_eq!(record.ended_at, Some(at("10:10:00")));
+        assert_eq!(record.duration_secs, 480.0);
+    }
+
+    #[test]
+    fn test_to_record_without_runs_uses_fallback_map() {
+        let session = TrackerSession::new();
+        let record = session.to_record(FLAME_ELEMENTIUM_ID, Some("Hideout"), Utc::now());
+        assert_eq!(record.map, "Hideout");
+        assert!(record.runs.is_empty());
+        let record = session.to_record(FLAME_ELEMENTIUM_ID, None, Utc::now());
+        assert_eq!(record.map, "Unknown");
+    }
+
+    #[test]
+    fn test_finalized_rate_does_not_drift() {
+        let mut session = TrackerSession::new();
+        session.start_wall = at("10:00:00");
+        session.add_loot(FLAME_ELEMENTIUM_ID, 500);
+        let finalized = session.finalize(FLAME_ELEMENTIUM_ID, at("10:30:00"));
+        assert_eq!(finalized.elapsed_secs, 1800.0);
+        assert_eq!(finalized.primary_per_hour(), 1000.0);
+
+        // The live session keeps ageing; the snapshot does not.
+        assert_eq!(session.elapsed_secs(at("11:00:00")), 3600.0);
+        session.add_loot(FLAME_ELEMENTIUM_ID, 100);
+        assert_eq!(finalized.primary_per_hour(), 1000.0);
+        assert_eq!(finalized.primary, 500);
+    }
+}
@@ -0,0 +1,390 @@
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::log_parser;
+use crate::tracker::Tracker;
+use crate::viewmodel::{self, Action, Tab, TrackerView};
+
+/// How long to wait for a key press before redrawing.
+const INPUT_TIMEOUT: Duration = Duration::from_millis(250);
+
+struct TuiApp {
+    tracker: Tracker,
+    active_tab: Tab,
+    last_poll: Instant,
+}
+
+impl TuiApp {
+    fn new() -> Self {
+        let mut app = Self {
+            tracker: Tracker::new(),
+            active_tab: Tab::FlameElementium,
+            last_poll: Instant::now(),
+        };
+        app.tracker.poll();
+        app
+    }
+
+    /// Display values for the current frame.
+    fn view(&self) -> TrackerView {
+        TrackerView::from(
+            self.tracker.current_map.as_deref(),
+            self.tracker.session.as_ref(),
+            self.tracker.loot.as_ref(),
+            &self.tracker.settings,
+            Utc::now(),
+        )
+    }
+
+    /// Apply an action; returns false when the app should exit.
+    fn apply(&mut self, action: Action) -> bool {
+        match action {
+            Action::ToggleSession => self.tracker.toggle_session(),
+            Action::SelectTab(tab) => self.active_tab = tab,
+            Action::NextTab => self.active_tab = self.active_tab.next(),
+            Action::Quit => return false,
+        }
+        true
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            if self.last_poll.elapsed() >= self.tracker.settings.poll_interval() {
+                self.tracker.poll();
+                self.last_poll = Instant::now();
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if event::poll(INPUT_TIMEOUT)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let ch = match key.code {
+                        KeyCode::Char(c) => c,
+                        KeyCode::Tab => '\t',
+                        KeyCode::Esc => 'q',
+                        _ => continue,
+                    };
+                    if let Some(action) = viewmodel::action_for_key(ch) {
+                        if !self.apply(action) {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, tabs, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_session_bar(frame, header);
+
+        let titles = Tab::ALL.iter().map(|t| t.label());
+        frame.render_widget(
+            Tabs::new(titles)
+                .select(self.active_tab.index())
+                .highlight_style(Style::new().fg(Color::White).add_modifier(Modifier::BOLD))
+                .style(Style::new().fg(Color::DarkGray)),
+            tabs,
+        );
+
+        match self.active_tab {
+            Tab::FlameElementium => self.draw_fe_tab(frame, body),
+            Tab::Items => self.draw_loot_tab(frame, body),
+            Tab::Inventory => self.draw_inventory_tab(frame, body),
+            Tab::Runs => self.draw_runs_tab(frame, body),
+            Tab::Settings => self.draw_settings_tab(frame, body),
+        }
+
+        frame.render_widget(
+            Paragraph::new(format!(
+                "s start/stop  1-5/Tab switch  q quit  │  {}",
+                self.tracker.log_status
+            ))
+            .style(Style::new().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn draw_session_bar(&self, frame: &mut Frame, area: Rect) {
+        let view = self.view();
+        let mut spans = Vec::new();
+        let settings = &self.tracker.settings;
+        for stat in view.stats(
+            &settings.stat_boxes,
+            &settings.number_locale(),
+            settings.abbreviate_numbers,
+        ) {
+            spans.push(Span::styled(
+                format!("{} ", stat.label),
+                Style::new().fg(Color::DarkGray),
+            ));
+            spans.push(Span::styled(
+                format!("{}   ", stat.value),
+                Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            ));
+        }
+        let title = if self.tracker.session.is_some() {
+            " TLI Tracker ● session "
+        } else {
+            " TLI Tracker "
+        };
+        let mut block = Block::default().borders(Borders::ALL).title(title);
+        if let Some(alert) = view.pace_alert {
+            block = block
+                .border_style(Style::new().fg(Color::Red))
+                .title_bottom(Span::styled(
+                    format!(" ⚠ {} ", alert),
+                    Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+        }
+        if let Some(alert) = self.tracker.bag_alert() {
+            block = block.title_bottom(
+                Line::from(Span::styled(
+                    format!(" ⚠ {} ", alert),
+                    Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ))
+                .right_aligned(),
+            );
+        }
+        let anomalies = self
+            .tracker
+            .loot
+            .as_ref()
+            .and_then(|l| l.anomalies.warning());
+        if let Some(warning) = anomalies {
+            block = block.title_bottom(Line::from(Span::styled(
+                format!(" ⚠ {} ", warning),
+                Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+        if let Some(warning) = self.tracker.loot.as_ref().and_then(|l| l.item_db_warning()) {
+            block = block.title_bottom(Line::from(Span::styled(
+                format!(" ⚠ {} ", warning),
+                Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)).block(block), area);
+    }
+
+    fn draw_fe_tab(&self, frame: &mut Frame, area: Rect) {
+        let view = self.view();
+        let sep = &self.tracker.settings.number_locale();
+        let heading = view.currency_name.to_uppercase();
+        let mut lines = match (view.active, &self.tracker.loot) {
+            (true, _) => vec![
+                Line::from(heading),
+                Line::from(Span::styled(
+                    viewmodel::format_count(view.primary, sep),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(format!(
+                    "{} {} / hour",
+                    viewmodel::format_number(view.primary_per_hour, view.rate_precision, sep),
+                    view.currency
+                )),
+            ],
+            (false, Some(_)) => vec![
+                Line::from(heading),
+                Line::from(Span::styled(
+                    viewmodel::format_count(view.primary, sep),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(format!(
+                    "from log (press s to start a session and track {}/hour)",
+                    view.currency
+                )),
+            ],
+            (false, None) => vec![Line::from(format!(
+                "Start a session to track {}. Sort inventory in-game to sync baseline.",
+                view.currency_name
+            ))],
+        };
+        if let (false, Some(last)) = (view.active, &self.tracker.last_session) {
+            lines.push(Line::from(""));
+            lines.push(Line::from(viewmodel::last_session_line(
+                last,
+                &view.currency,
+                view.rate_precision,
+                sep,
+            )));
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL)),
+            area,
+        );
+    }
+
+    fn draw_loot_tab(&self, frame: &mut Frame, area: Rect) {
+        let rows = viewmodel::loot_rows(
+            self.tracker.session.as_ref(),
+            self.tracker.loot.as_ref(),
+            &self.tracker.settings,
+        );
+        // An empty or unsorted log explains itself better than an empty table.
+        let hint = self.tracker.loot.as_ref().and_then(|l| l.hint());
+        if let Some(hint) = hint.filter(|_| rows.is_empty()) {
+            frame.render_widget(
+                Paragraph::new(hint).block(Block::default().borders(Borders::ALL)),
+                area,
+            );
+            return;
+        }
+        // In a session the last two columns are this run and the session
+        // total; otherwise the log delta and current stack.
+        let display = self.tracker.settings.item_display;
+        let mut headers = display.cells("Item", "ID");
+        headers.extend(if self.tracker.session.is_some() {
+            ["This Run", "Session"]
+        } else {
+            ["Delta", "Current"]
+        });
+        let sep = &self.tracker.settings.number_locale();
+        let table_rows = rows.iter().map(|r| {
+            let (third, fourth) = match r.run {
+                Some(run) => (
+                    viewmodel::format_delta(run, sep),
+                    viewmodel::format_delta(r.delta, sep),
+                ),
+                None => (
+                    viewmodel::format_delta(r.delta, sep),
+                    r.current
+                        .map(|c| viewmodel::format_count(c.into(), sep))
+                        .unwrap_or_default(),
+                ),
+            };
+            let aliases = &self.tracker.settings.item_aliases;
+            let shown = aliases.get(&r.id).unwrap_or(&r.name);
+            let mut cells: Vec<Cell> = display
+                .cells(shown, &r.id)
+                .into_iter()
+                .map(|c| Cell::from(c.to_string()))
+                .collect();
+            cells.extend([Cell::from(third), Cell::from(fourth)]);
+            Row::new(cells)
+        });
+        let item_widths = display.cells(Constraint::Percentage(50), Constraint::Length(10));
+        let table = Table::new(
+            table_rows,
+            item_widths
+                .into_iter()
+                .chain([Constraint::Length(9), Constraint::Length(8)]),
+        )
+        .header(header_row(&headers))
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_inventory_tab(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.tracker.inventory.iter().map(|item| {
+            Row::new(vec![
+                Cell::from(log_parser::display_name(
+                    &item.config_base_id,
+                    &self.tracker.settings.item_aliases,
+                )),
+                Cell::from(item.page_id.to_string()),
+                Cell::from(item.slot_id.to_string()),
+                Cell::from(viewmodel::format_count(
+                    item.num.into(),
+                    &self.tracker.settings.number_locale(),
+                )),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(50),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header_row(&["Item", "Page", "Slot", "Qty"]))
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_runs_tab(&self, frame: &mut Frame, area: Rect) {
+        let Some(ref session) = self.tracker.session else {
+            frame.render_widget(
+                Paragraph::new("Start a session to track map runs.")
+                    .block(Block::default().borders(Borders::ALL)),
+                area,
+            );
+            return;
+        };
+        let rows = viewmodel::run_rows(session, &self.tracker.settings)
+            .into_iter()
+            .map(|run| {
+                Row::new(vec![
+                    Cell::from(run.started),
+                    Cell::from(run.map),
+                    Cell::from(run.duration),
+                    Cell::from(viewmodel::format_count(
+                        run.items,
+                        &self.tracker.settings.number_locale(),
+                    )),
+                ])
+            });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Percentage(50),
+                Constraint::Length(10),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header_row(&["Started", "Map", "Duration", "Items"]))
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(table, area);
+    }
+
+    /// Read-only: settings are edited in the GUI or with `tli-tracker settings`.
+    fn draw_settings_tab(&self, frame: &mut Frame, area: Rect) {
+        let rows = self
+            .tracker
+            .settings
+            .entries()
+            .into_iter()
+            .map(|(name, value)| Row::new(vec![Cell::from(name), Cell::from(value)]));
+        let table = Table::new(rows, [Constraint::Length(22), Constraint::Min(0)])
+            .header(header_row(&["Setting", "Value"]))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Edit with `tli-tracker settings` or in the GUI "),
+            );
+        frame.render_widget(table, area);
+    }
+}
+
+fn header_row(titles: &[&'static str]) -> Row<'static> {
+    Row::new(titles.iter().copied()).style(Style::new().fg(Color::DarkGray))
+}
+
+/// Launch the terminal UI; restores the terminal on exit.
+pub fn run() -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = TuiApp::new().run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,2145 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::log_parser::{self, ItemDelta, LootSummary};
+use crate::scoring::ScoreMode;
+use crate::settings::Settings;
+use crate::tracker::{FinalizedSession, MapRun, TrackerSession};
+
+// ── Tabs and shortcuts ────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tab {
+    #[default]
+    FlameElementium,
+    Items,
+    Inventory,
+    Runs,
+    Settings,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 5] = [
+        Tab::FlameElementium,
+        Tab::Items,
+        Tab::Inventory,
+        Tab::Runs,
+        Tab::Settings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tab::FlameElementium => "Flame Elementium",
+            Tab::Items => "Items",
+            Tab::Inventory => "Inventory",
+            Tab::Runs => "Runs",
+            Tab::Settings => "Settings",
+        }
+    }
+
+    /// Stable name the tab is saved under.
+    pub fn key(self) -> &'static str {
+        match self {
+            Tab::FlameElementium => "flame_elementium",
+            Tab::Items => "items",
+            Tab::Inventory => "inventory",
+            Tab::Runs => "runs",
+            Tab::Settings => "settings",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        Tab::ALL.iter().position(|&t| t == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+}
+
+impl Serialize for Tab {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+/// A name this version doesn't know (a tab from a newer one, say) reads as
+/// the default tab rather than failing the whole settings file.
+impl<'de> Deserialize<'de> for Tab {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Ok(Tab::ALL
+            .into_iter()
+            .find(|tab| tab.key() == key)
+            .unwrap_or_default())
+    }
+}
+
+/// A keyboard action understood by every frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ToggleSession,
+    SelectTab(Tab),
+    NextTab,
+    Quit,
+}
+
+/// Map a key press to its action: `s` start/stop, `1`-`5` tabs, Tab cycles,
+/// `q` quits (TUI only).
+pub fn action_for_key(key: char) -> Option<Action> {
+    match key {
+        's' | 'S' => Some(Action::ToggleSession),
+        '1' => Some(Action::SelectTab(Tab::FlameElementium)),
+        '2' => Some(Action::SelectTab(Tab::Items)),
+        '3' => Some(Action::SelectTab(Tab::Inventory)),
+        '4' => Some(Action::SelectTab(Tab::Runs)),
+        '5' => Some(Action::SelectTab(Tab::Settings)),
+        '\t' => Some(Action::NextTab),
+        'q' | 'Q' => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+// ── Session bar ───────────────────────────────────────────────────────
+
+/// A stat the session bar can show; users pick which and in what order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatKind {
+    Map,
+    Time,
+    /// Primary currency gained.
+    Primary,
+    PrimaryPerHour,
+    RecentPace,
+    Items,
+    Runs,
+}
+
+impl StatKind {
+    /// Every stat, in the default order.
+    pub const ALL: [StatKind; 7] = [
+        StatKind::Map,
+        StatKind::Time,
+        StatKind::Primary,
+        StatKind::PrimaryPerHour,
+        StatKind::RecentPace,
+        StatKind::Items,
+        StatKind::Runs,
+    ];
+
+    /// Name used in `settings.json` and on the command line.
+    pub fn key(self) -> &'static str {
+        match self {
+            StatKind::Map => "map",
+            StatKind::Time => "time",
+            StatKind::Primary => "primary",
+            StatKind::PrimaryPerHour => "primary_per_hour",
+            StatKind::RecentPace => "recent_pace",
+            StatKind::Items => "items",
+            StatKind::Runs => "runs",
+        }
+    }
+
+    /// Description for the settings screen.
+    pub fn title(self) -> &'static str {
+        match self {
+            StatKind::Map => "Current map",
+            StatKind::Time => "Session time",
+            StatKind::Primary => "Primary currency gained",
+            StatKind::PrimaryPerHour => "Primary currency per hour",
+            StatKind::RecentPace => "Recent pace",
+            StatKind::Items => "Items",
+            StatKind::Runs => "Completed runs",
+        }
+    }
+
+    /// This stat's box for `view`; session stats show `-` without a session.
+    ///
+    /// With `abbreviate`, numbers from 1,000 up are shortened (`123.5k`);
+    /// [`Stat::full`] keeps them whole.
+    pub fn resolve(self, view: &TrackerView, locale: &NumberLocale, abbreviate: bool) -> Stat {
+        let currency = view.currency.to_uppercase();
+        let label = match self {
+            StatKind::Map => "MAP".to_string(),
+            StatKind::Time => "TIME".to_string(),
+            StatKind::Primary => currency,
+            StatKind::PrimaryPerHour => format!("{}/HR", currency),
+            StatKind::RecentPace => format!("LAST {}", view.recent_runs),
+            StatKind::Items => "ITEMS".to_string(),
+            StatKind::Runs => "RUNS".to_string(),
+        };
+        let number = match self {
+            _ if !view.active => None,
+            StatKind::Primary => Some(view.primary as f64),
+            StatKind::PrimaryPerHour => Some(view.primary_per_hour),
+            StatKind::RecentPace => view.recent_per_hour,
+            StatKind::Items => Some(view.items as f64),
+            StatKind::Map | StatKind::Time | StatKind::Runs => None,
+        };
+        let full = match self {
+            StatKind::Map => view.map.clone().unwrap_or_else(|| "-".to_string()),
+            StatKind::Time => view.time.clone(),
+            _ if !view.active => "-".to_string(),
+            StatKind::Primary => format_count(view.primary, locale),
+            StatKind::PrimaryPerHour => {
+                format_number(view.primary_per_hour, view.rate_precision, locale)
+            }
+            StatKind::RecentPace => view
+                .recent_per_hour
+                .map(|r| format_number(r, view.rate_precision, locale))
+                .unwrap_or_else(|| "-".to_string()),
+            StatKind::Items => format_count(view.items, locale),
+            StatKind::Runs => view.runs.to_string(),
+        };
+        let value = match number {
+            Some(n) if abbreviate && n.round().abs() >= 1000.0 => {
+                format_abbrev(n.round() as i64, locale)
+            }
+            _ => full.clone(),
+        };
+        let target = match self {
+            StatKind::PrimaryPerHour => view.target,
+            _ => None,
+        };
+        Stat {
+            label,
+            value,
+            full,
+            target,
+        }
+    }
+}
+
+impl std::str::FromStr for StatKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StatKind::ALL
+            .into_iter()
+            .find(|kind| kind.key() == s.trim())
+            .ok_or_else(|| {
+                let keys: Vec<_> = StatKind::ALL.iter().map(|k| k.key()).collect();
+                format!("unknown stat {:?} (expected one of {})", s, keys.join(", "))
+            })
+    }
+}
+
+/// One labelled box in the session bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stat {
+    pub label: String,
+    /// The value as shown in the box, abbreviated if so configured.
+    pub value: String,
+    /// The value in full, for tooltips.
+    pub full: String,
+    /// How the value compares with the user's target, if it has one.
+    pub target: Option<TargetProgress>,
+}
+
+/// Below this share of the target a rate is behind rather than close.
+const TARGET_CLOSE: f64 = 0.9;
+
+/// Colour bucket for a rate measured against a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetStatus {
+    /// Under 90% of the target (red).
+    Behind,
+    /// Within 10% under the target (amber).
+    Close,
+    /// At or above the target (green).
+    OnTarget,
+}
+
+/// A rate's standing against the target.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TargetProgress {
+    pub status: TargetStatus,
+    /// Percent above (positive) or below (negative) the target.
+    pub percent: f64,
+}
+
+impl TargetProgress {
+    /// The percent as a signed whole number, e.g. `+12%`.
+    pub fn delta_label(&self) -> String {
+        format!("{:+.0}%", self.percent)
+    }
+}
+
+/// Compare `rate` with `target`; no target (or a non-positive one) means
+/// nothing to compare with.
+pub fn classify_rate(rate: f64, target: Option<f64>) -> Option<TargetProgress> {
+    let target = target.filter(|t| *t > 0.0)?;
+    let ratio = rate / target;
+    let status = if ratio >= 1.0 {
+        TargetStatus::OnTarget
+    } else if ratio >= TARGET_CLOSE {
+        TargetStatus::Close
+    } else {
+        TargetStatus::Behind
+    };
+    Some(TargetProgress {
+        status,
+        percent: (ratio - 1.0) * 100.0,
+    })
+}
+
+/// Format seconds as `MM:SS` (minutes keep counting past an hour).
+pub fn format_clock(secs: f64) -> String {
+    let mins = (secs / 60.0).floor() as u64;
+    let s = (secs % 60.0).floor() as u64;
+    format!("{:02}:{:02}", mins, s)
+}
+
+/// A loot total under the configured score mode, e.g. `Value: 26.00`;
+/// item counts have no decimals.
+pub fn score_label(score: f64, settings: &Settings) -> String {
+    let precision = match settings.score_mode {
+        ScoreMode::Count => 0,
+        ScoreMode::Priced | ScoreMode::Weighted => settings.value_precision,
+    };
+    format!(
+        "{}: {}",
+        settings.score_mode.label(),
+        format_number(score, precision, &settings.number_locale())
+    )
+}
+
+/// One line on the last stopped session, from its frozen totals.
+pub fn last_session_line(
+    last: &FinalizedSession,
+    currency: &str,
+    precision: usize,
+    locale: &NumberLocale,
+) -> String {
+    format!(
+        "Last session: {} {} in {} ({} {} / hour, {} runs)",
+        format_count(last.primary, locale),
+        currency,
+        format_clock(last.elapsed_secs),
+        format_number(last.primary_per_hour(), precision, locale),
+        currency,
+        last.runs
+    )
+}
+
+/// Characters of a session id shown where space is short, as the web UI
+/// shortens them.
+pub const SHORT_ID_LEN: usize = 8;
+
+/// A session id for display: the whole id, or its first [`SHORT_ID_LEN`]
+/// characters.
+pub fn session_id_label(id: &str, full: bool) -> &str {
+    if full {
+        return id;
+    }
+    match id.char_indices().nth(SHORT_ID_LEN) {
+        Some((end, _)) => &id[..end],
+        None => id,
+    }
+}
+
+/// How numbers are written: the digit-group separator, if digits are
+/// grouped, and the decimal mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    pub group: Option<char>,
+    pub decimal: char,
+}
+
+impl NumberLocale {
+    /// `1234567.5`
+    pub const PLAIN: NumberLocale = NumberLocale {
+        group: None,
+        decimal: '.',
+    };
+    /// `1,234,567.5`
+    pub const US: NumberLocale = NumberLocale {
+        group: Some(','),
+        decimal: '.',
+    };
+    /// `1.234.567,5`
+    pub const EU: NumberLocale = NumberLocale {
+        group: Some('.'),
+        decimal: ',',
+    };
+
+    /// Named styles selectable in settings.
+    pub const PRESETS: [(&'static str, NumberLocale); 4] = [
+        ("us", NumberLocale::US),
+        ("eu", NumberLocale::EU),
+        (
+            "fr",
+            NumberLocale {
+                group: Some(' '),
+                decimal: ',',
+            },
+        ),
+        (
+            "ch",
+            NumberLocale {
+                group: Some('\''),
+                decimal: '.',
+            },
+        ),
+    ];
+
+    /// The preset called `name` (case-insensitive).
+    pub fn preset(name: &str) -> Option<NumberLocale> {
+        NumberLocale::PRESETS
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.trim()))
+            .map(|&(_, locale)| locale)
+    }
+
+    /// This locale without digit grouping, for compact columns.
+    pub fn ungrouped(self) -> NumberLocale {
+        NumberLocale {
+            group: None,
+            ..self
+        }
+    }
+}
+
+/// Format a count with the locale's separator between groups of three
+/// digits (`1,234,567`), or ungrouped if it has none.
+pub fn format_count(n: i64, locale: &NumberLocale) -> String {
+    let grouped = group_digits(&n.unsigned_abs().to_string(), locale.group);
+    if n < 0 {
+        format!("-{}", grouped)
+    } else {
+        grouped
+    }
+}
+
+/// `digits` with `separator` between groups of three, counted from the right.
+fn group_digits(digits: &str, separator: Option<char>) -> String {
+    let Some(sep) = separator else {
+        return digits.to_string();
+    };
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(sep);
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Format a rate or money value with `precision` decimals, grouping the
+/// whole part like [`format_count`] and using the locale's decimal mark
+/// (`1,234.5`, or `1.234,5` for [`NumberLocale::EU`]).
+pub fn format_number(value: f64, precision: usize, locale: &NumberLocale) -> String {
+    if precision == 0 && value.is_finite() {
+        return format_count(value.round() as i64, locale);
+    }
+    let text = format!("{:.*}", precision, value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let mut out = String::new();
+    if value < 0.0 && text.chars().any(|c| ('1'..='9').contains(&c)) {
+        out.push('-');
+    }
+    out.push_str(&group_digits(whole, locale.group));
+    if !fraction.is_empty() {
+        out.push(locale.decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// A count shortened for tight spaces: `999`, `1k`, `123.5k`, `1.5m`,
+/// with one decimal (dropped when zero) in the locale's decimal mark.
+pub fn format_abbrev(n: i64, locale: &NumberLocale) -> String {
+    let abs = n.unsigned_abs() as f64;
+    if abs < 1000.0 {
+        return format_count(n, locale);
+    }
+    // Round to tenths before picking the unit, so 999,950 reads 1m, not 1000k.
+    let (tenths, suffix) = match (abs / 100.0).round() {
+        tenths if tenths < 10_000.0 => (tenths, "k"),
+        _ => ((abs / 100_000.0).round(), "m"),
+    };
+    let text = if tenths % 10.0 == 0.0 {
+        format_count((tenths / 10.0) as i64, &locale.ungrouped())
+    } else {
+        format_number(tenths / 10.0, 1, &locale.ungrouped())
+    };
+    format!("{}{}{}", if n < 0 { "-" } else { "" }, text, suffix)
+}
+
+/// Signed delta for display (`+5`, `-3`, `0`), grouped like [`format_count`].
+pub fn format_delta(delta: i64, locale: &NumberLocale) -> String {
+    let count = format_count(delta, locale);
+    if delta > 0 {
+        format!("+{}", count)
+    } else {
+        count
+    }
+}
+
+/// Every headline value a frontend displays, computed in one place.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackerView {
+    /// Current map from the log, if any map event was seen.
+    pub map: Option<String>,
+    /// Whether a tracker session is running.
+    pub active: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub elapsed_secs: f64,
+    /// Elapsed time as `MM:SS`, or `--:--` without a session.
+    pub time: String,
+    /// Short label of the primary currency, e.g. "FE".
+    pub currency: String,
+    /// Full item name of the primary currency, e.g. "Flame Elementium".
+    pub currency_name: String,
+    /// Primary currency gained this session while active, otherwise its
+    /// delta parsed from the log.
+    pub primary: i64,
+    pub primary_per_hour: f64,
+    /// Primary currency per hour over the last `recent_runs` completed
+    /// runs; `None` until a run has completed.
+    pub recent_per_hour: Option<f64>,
+    /// How many completed runs `recent_per_hour` covers at most.
+    pub recent_runs: usize,
+    /// Warning shown while the recent pace stays below the configured floor.
+    pub pace_alert: Option<String>,
+    /// Primary currency per hour against `target_per_hour`, while a session
+    /// runs and a target is set.
+    pub target: Option<TargetProgress>,
+    /// Decimals to show rates with.
+    #[serde(skip)]
+    pub rate_precision: usize,
+    pub items: i64,
+    pub runs: usize,
+    /// Loot events counted in the latest log parse.
+    pub total_events: usize,
+}
+
+impl TrackerView {
+    /// `settings` supplies the headline currency and the recent-pace window.
+    pub fn from(
+        map: Option<&str>,
+        session: Option<&TrackerSession>,
+        summary: Option<&LootSummary>,
+        settings: &Settings,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let primary = settings.primary_currency.as_str();
+        let recent_runs = settings.recent_runs;
+        let total_events = summary.map(|s| s.total_events).unwrap_or(0);
+        let currency = log_parser::currency_label(primary);
+        let currency_name = log_parser::item_name(primary);
+        match session {
+            Some(session) => {
+                let elapsed_secs = session.elapsed_secs(now);
+                let gained = session.currency(primary);
+                let per_hour = if elapsed_secs < 1.0 {
+                    0.0
+                } else {
+                    gained as f64 / elapsed_secs * 3600.0
+                };
+                let pace_alert = pace_alert(session, settings, &currency);
+                Self {
+                    map: map.map(str::to_string),
+                    active: true,
+                    started_at: Some(session.start_wall),
+                    elapsed_secs,
+                    time: format_clock(elapsed_secs),
+                    currency,
+                    currency_name,
+                    primary: gained,
+                    primary_per_hour: per_hour,
+                    recent_per_hour: recent_pace(session, primary, recent_runs),
+                    recent_runs,
+                    pace_alert,
+                    target: classify_rate(per_hour, settings.target_per_hour),
+                    rate_precision: settings.rate_precision,
+                    items: item_count(&session.cumulative_loot, settings),
+                    runs: session.runs.len(),
+                    total_events,
+                }
+            }
+            None => Self {
+                map: map.map(str::to_string),
+                active: false,
+                started_at: None,
+                elapsed_secs: 0.0,
+                time: "--:--".to_string(),
+                currency,
+                currency_name,
+                primary: summary.map(|s| s.primary_delta(primary)).unwrap_or(0),
+                primary_per_hour: 0.0,
+                recent_per_hour: None,
+                recent_runs,
+                pace_alert: None,
+                target: None,
+                rate_precision: settings.rate_precision,
+                items: 0,
+                runs: 0,
+                total_events,
+            },
+        }
+    }
+
+    /// The session bar's `kinds` of stats, in that order, with numbers
+    /// written in `locale` and shortened if `abbreviate` is set.
+    pub fn stats(&self, kinds: &[StatKind], locale: &NumberLocale, abbreviate: bool) -> Vec<Stat> {
+        kinds
+            .iter()
+            .map(|kind| kind.resolve(self, locale, abbreviate))
+            .collect()
+    }
+}
+
+// ── Tables ────────────────────────────────────────────────────────────
+
+/// How loot tables identify items: by name, by ConfigBaseId, or both in
+/// separate columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemDisplay {
+    Name,
+    Id,
+    #[default]
+    NameAndId,
+}
+
+impl ItemDisplay {
+    pub const ALL: [ItemDisplay; 3] = [ItemDisplay::Name, ItemDisplay::Id, ItemDisplay::NameAndId];
+
+    /// Name used in `settings.json` and on the command line.
+    pub fn key(self) -> &'static str {
+        match self {
+            ItemDisplay::Name => "name",
+            ItemDisplay::Id => "id",
+            ItemDisplay::NameAndId => "name_and_id",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemDisplay::Name => "Name",
+            ItemDisplay::Id => "ID",
+            ItemDisplay::NameAndId => "Name and ID",
+        }
+    }
+
+    /// The item columns of a table row: `[name]`, `[id]` or `[name, id]`.
+    /// Called with `"Item", "ID"` it gives the matching headers, and with
+    /// the two column widths the matching widths.
+    pub fn cells<T>(self, name: T, id: T) -> Vec<T> {
+        match self {
+            ItemDisplay::Name => vec![name],
+            ItemDisplay::Id => vec![id],
+            ItemDisplay::NameAndId => vec![name, id],
+        }
+    }
+}
+
+impl std::str::FromStr for ItemDisplay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ItemDisplay::ALL
+            .into_iter()
+            .find(|mode| mode.key() == s.trim())
+            .ok_or_else(|| {
+                let keys: Vec<_> = ItemDisplay::ALL.iter().map(|m| m.key()).collect();
+                format!(
+                    "unknown item display {:?} (expected one of {})",
+                    s,
+                    keys.join(", ")
+                )
+            })
+    }
+}
+
+/// One row of the loot table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LootRow {
+    pub name: String,
+    pub id: String,
+    /// Session total while a session runs, otherwise the log delta.
+    pub delta: i64,
+    /// Gained in the open run (only known while a session runs).
+    pub run: Option<i64>,
+    /// Current stack total (only known for log loot, not session totals).
+    pub current: Option<u32>,
+    /// The map whose runs gained the most of the item this session, and how
+    /// much (only known while a session runs).
+    pub top_map: Option<(String, i64)>,
+}
+
+/// Net items in `loot`, leaving out ignored items unless `settings` counts them.
+pub fn item_count(loot: &HashMap<String, i64>, settings: &Settings) -> i64 {
+    loot.iter()
+        .filter(|(id, _)| settings.counts_item(id))
+        .map(|(_, &n)| n)
+        .sum()
+}
+
+/// Move rows whose id is in `pinned` to the front, keeping the existing
+/// order within the pinned and unpinned groups.
+pub fn pinned_first<T>(rows: &mut [T], pinned: &BTreeSet<String>, id: impl Fn(&T) -> &str) {
+    rows.sort_by_key(|row| !pinned.contains(id(row)));
+}
+
+/// Log loot items that are not on the ignore list, pinned items first.
+pub fn visible_items<'a>(loot: &'a LootSummary, settings: &Settings) -> Vec<&'a ItemDelta> {
+    let mut items: Vec<&ItemDelta> = loot
+        .items
+        .iter()
+        .filter(|i| !settings.is_ignored(&i.config_base_id))
+        .collect();
+    pinned_first(&mut items, &settings.pinned_items, |i| &i.config_base_id);
+    items
+}
+
+/// Loot rows to show: session and this-run totals while a session runs,
+/// otherwise the latest deltas parsed from the log. Ignored items are left
+/// out and pinned items come first.
+pub fn loot_rows(
+    session: Option<&TrackerSession>,
+    loot: Option<&LootSummary>,
+    settings: &Settings,
+) -> Vec<LootRow> {
+    let mut rows = match (session, loot) {
+        (Some(session), _) => {
+            let contributions = map_contributions(&session.runs);
+            let mut rows = combined_loot_rows(
+                &session.cumulative_loot,
+                session.open_run().map(|r| &r.loot_gained),
+            );
+            for row in &mut rows {
+                row.top_map = contributions
+                    .get(&row.id)
+                    .and_then(top_map)
+                    .map(|(map, n)| (map.to_string(), n));
+            }
+            rows
+        }
+        (None, Some(loot)) => loot
+            .items
+            .iter()
+            .map(|i| LootRow {
+                name: i.item_name.clone(),
+                id: i.config_base_id.clone(),
+                delta: i.delta,
+                run: None,
+                current: Some(i.current),
+                top_map: None,
+            })
+            .collect(),
+        (None, None) => Vec::new(),
+    };
+    rows.retain(|row| !settings.is_ignored(&row.id));
+    pinned_first(&mut rows, &settings.pinned_items, |row| &row.id);
+    rows
+}
+
+/// One row per item seen this session, pairing the session total with the
+/// open run's gain (zero when nothing dropped this run, or with no open run).
+///
+/// Sorted by absolute session delta, then absolute run delta, largest first.
+pub fn combined_loot_rows(
+    session: &HashMap<String, i64>,
+    open_run: Option<&HashMap<String, i64>>,
+) -> Vec<LootRow> {
+    let run_delta = |id: &str| open_run.and_then(|r| r.get(id)).copied().unwrap_or(0);
+    let mut rows: Vec<LootRow> = session
+        .iter()
+        .map(|(id, &delta)| (id, delta))
+        .chain(
+            // A run's loot is always part of the session; keep stray ids anyway.
+            open_run
+                .into_iter()
+                .flatten()
+                .filter(|(id, _)| !session.contains_key(*id))
+                .map(|(id, _)| (id, 0)),
+        )
+        .map(|(id, delta)| LootRow {
+            name: log_parser::item_name(id),
+            id: id.clone(),
+            delta,
+            run: Some(run_delta(id)),
+            current: None,
+            top_map: None,
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.delta
+            .abs()
+            .cmp(&a.delta.abs())
+            .then(b.run.map(i64::abs).cmp(&a.run.map(i64::abs)))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    rows
+}
+
+/// Net gain of each item per map over `runs`: item id → map → amount.
+pub fn map_contributions(runs: &[MapRun]) -> HashMap<String, BTreeMap<String, i64>> {
+    let mut contributions: HashMap<String, BTreeMap<String, i64>> = HashMap::new();
+    for run in runs {
+        for (id, &n) in &run.loot_gained {
+            *contributions
+                .entry(id.clone())
+                .or_default()
+                .entry(run.map_name.clone())
+                .or_insert(0) += n;
+        }
+    }
+    contributions
+}
+
+/// The map that gained the most of one item, if any gained it at all; a tie
+/// goes to the map first by name.
+pub fn top_map(by_map: &BTreeMap<String, i64>) -> Option<(&str, i64)> {
+    by_map
+        .iter()
+        .filter(|(_, &n)| n > 0)
+        .fold(None, |best: Option<(&str, i64)>, (map, &n)| match best {
+            Some((_, best_n)) if best_n >= n => best,
+            _ => Some((map, n)),
+        })
+}
+
+/// One row of the runs table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRow {
+    /// Local wall-clock start, `HH:MM:SS`.
+    pub started: String,
+    pub map: String,
+    pub duration: String,
+    pub items: i64,
+}
+
+/// Runs of the session, most recent first; item counts follow `settings`.
+pub fn run_rows(session: &TrackerSession, settings: &Settings) -> Vec<RunRow> {
+    session
+        .runs
+        .iter()
+        .rev()
+        .map(|run| {
+            let secs = run.duration_secs();
+            RunRow {
+                started: run
+                    .start
+                    .with_timezone(&Local)
+                    .format("%H:%M:%S")
+                    .to_string(),
+                map: run.map_name.clone(),
+                duration: format!(
+                    "{}:{:02}",
+                    (secs / 60.0).floor() as u64,
+                    (secs % 60.0).floor() as u64
+                ),
+                items: item_count(&run.loot_gained, settings),
+            }
+        })
+        .collect()
+}
+
+/// Primary currency per hour of a single run; zero for runs under a second.
+fn run_rate(run: &MapRun, primary: &str) -> f64 {
+    let secs = run.duration_secs();
+    if secs < 1.0 {
+        0.0
+    } else {
+        run.currency(primary) as f64 / secs * 3600.0
+    }
+}
+
+/// Mean of `rates`, or `None` when empty.
+fn mean(rates: &[f64]) -> Option<f64> {
+    if rates.is_empty() {
+        None
+    } else {
+        Some(rates.iter().sum::<f64>() / rates.len() as f64)
+    }
+}
+
+/// Average `primary` currency per hour over the last `n` completed runs
+/// (fewer if fewer have completed); the open run is ignored.
+pub fn recent_pace(session: &TrackerSession, primary: &str, n: usize) -> Option<f64> {
+    let rates: Vec<f64> = session
+        .runs
+        .iter()
+        .filter(|r| r.end.is_some())
+        .rev()
+        .take(n)
+        .map(|r| run_rate(r, primary))
+        .collect();
+    mean(&rates)
+}
+
+/// The recent pace as it stood after each completed run, oldest first.
+pub fn pace_series(session: &TrackerSession, primary: &str, n: usize) -> Vec<f64> {
+    let rates: Vec<f64> = session
+        .runs
+        .iter()
+        .filter(|r| r.end.is_some())
+        .map(|r| run_rate(r, primary))
+        .collect();
+    (1..=rates.len())
+        .filter_map(|end| mean(&rates[end.saturating_sub(n)..end]))
+        .collect()
+}
+
+/// Warning text when the recent pace has stayed below the configured floor
+/// for the last `pace_alert_runs` completed runs.
+fn pace_alert(session: &TrackerSession, settings: &Settings, currency: &str) -> Option<String> {
+    let floor = settings.pace_floor?;
+    let series = pace_series(session, &settings.primary_currency, settings.recent_runs);
+    let window = settings.pace_alert_runs;
+    sustained_below(&series, floor, window).then(|| {
+        format!(
+            "{}/hour has stayed below {} for the last {} runs",
+            currency,
+            format_count(floor.round() as i64, &settings.number_locale()),
+            window
+        )
+    })
+}
+
+/// Whether the last `window` samples are all below `floor`.
+///
+/// Requiring the whole window keeps a single slow run (or a single fast
+/// one) from toggling the alert. A window of zero never alerts.
+pub fn sustained_below(samples: &[f64], floor: f64, window: usize) -> bool {
+    window > 0
+        && samples.len() >= window
+        && samples[samples.len() - window..].iter().all(|&s| s < floor)
+}
+
+/// Seconds of session time the live rate looks back over.
+pub const LIVE_RATE_WINDOW_SECS: f64 = 60.0;
+
+/// Per-hour rate over the last `window` seconds, from `samples` of
+/// (session seconds, running total) taken oldest first.
+///
+/// The total at `now` is the newest sample. It is compared with the newest
+/// sample at least `window` old, or with the oldest one while the history
+/// is still shorter than the window, so the rate then covers less time.
+/// `None` without samples or without time elapsed since the baseline.
+pub fn window_rate(samples: &[(f64, i64)], now: f64, window: f64) -> Option<f64> {
+    let &(_, latest) = samples.last()?;
+    let cutoff = now - window;
+    let &(start, baseline) = samples
+        .iter()
+        .rev()
+        .find(|(t, _)| *t <= cutoff)
+        .unwrap_or(&samples[0]);
+    let span = now - start;
+    (span > 0.0).then(|| (latest - baseline) as f64 / span * 3600.0)
+}
+
+/// Recent samples of a tracker session's primary currency, for the live
+/// "last 60s" rate next to the session average.
+#[derive(Debug, Clone, Default)]
+pub struct LiveRate {
+    /// Start of the session the samples belong to.
+    session: Option<DateTime<Utc>>,
+    samples: VecDeque<(f64, i64)>,
+}
+
+impl LiveRate {
+    /// Sample `session` as of `now`; a new or stopped session starts over.
+    pub fn record(&mut self, session: Option<&TrackerSession>, primary: &str, now: DateTime<Utc>) {
+        let Some(session) = session else {
+            *self = LiveRate::default();
+            return;
+        };
+        if self.session != Some(session.start_wall) {
+            *self = LiveRate {
+                session: Some(session.start_wall),
+                samples: VecDeque::new(),
+            };
+        }
+        let secs = session.elapsed_secs(now);
+        self.samples.push_back((secs, session.currency(primary)));
+        // Keep one sample older than the window as the baseline.
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|&(t, _)| t <= secs - LIVE_RATE_WINDOW_SECS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Per hour over the last [`LIVE_RATE_WINDOW_SECS`] of `session`.
+    pub fn per_hour(&self, session: &TrackerSession, now: DateTime<Utc>) -> Option<f64> {
+        if self.session != Some(session.start_wall) {
+            return None;
+        }
+        let samples: Vec<(f64, i64)> = self.samples.iter().copied().collect();
+        window_rate(&samples, session.elapsed_secs(now), LIVE_RATE_WINDOW_SECS)
+    }
+}
+
+/// Whether to remind the user to sort their inventory: never sorted
+/// (`None`), or the last sort is at least `max_age` old. A `max_age` of
+/// zero turns the reminder off.
+pub fn sort_reminder_due(
+    last_sort: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    max_age: chrono::Duration,
+) -> bool {
+    if max_age <= chrono::Duration::zero() {
+        return false;
+    }
+    match last_sort {
+        None => true,
+        Some(at) => now - at >= max_age,
+    }
+}
+
+/// Reminder text when the loot baseline needs a fresh inventory sort.
+///
+/// Logs without bag events are covered by `LootSummary::hint`, and a sort
+/// whose log line has no timestamp is taken as recent.
+pub fn sort_reminder(
+    loot: Option<&LootSummary>,
+    settings: &Settings,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let loot = loot.filter(|l| l.events_seen)?;
+    if loot.baseline_found && loot.last_sort_at.is_none() {
+        return None;
+    }
+    let max_age = i64::try_from(settings.sort_reminder_mins)
+        .ok()
+        .and_then(chrono::Duration::try_minutes)
+        .unwrap_or(chrono::Duration::MAX);
+    sort_reminder_due(loot.last_sort_at, now, max_age).then(|| match loot.last_sort_at {
+        None => "No inventory sort in the log — sort your inventory in-game so loot is counted from a fresh baseline.".to_string(),
+        Some(at) => format!(
+            "Last inventory sort was {} min ago — sort again to keep the loot baseline in sync.",
+            (now - at).num_minutes()
+        ),
+    })
+}
+
+/// Average primary-currency rate per hour for one map across the session's runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapRate {
+    pub map: String,
+    pub runs: usize,
+    pub per_hour: f64,
+}
+
+/// Per-map average of each run's `primary` currency per hour, in order of
+/// first visit.
+///
+/// Runs shorter than a second count with a rate of zero.
+pub fn map_rates(session: &TrackerSession, primary: &str) -> Vec<MapRate> {
+    let mut rates: Vec<MapRate> = Vec::new();
+    for run in &session.runs {
+        let rate = run_rate(run, primary);
+        match rates.iter_mut().find(|r| r.map == run.map_name) {
+            Some(entry) => {
+                // Running mean keeps every run equally weighted.
+                entry.runs += 1;
+                entry.per_hour += (rate - entry.per_hour) / entry.runs as f64;
+            }
+            None => rates.push(MapRate {
+                map: run.map_name.clone(),
+                runs: 1,
+                per_hour: rate,
+            }),
+        }
+    }
+    rates
+}
+
+// ── Row actions ───────────────────────────────────────────────────────
+
+/// Something done to an item from the context menu of a loot or inventory row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemAction {
+    CopyId,
+    CopyName,
+    TogglePin,
+    Ignore,
+    /// Set (`Some`) or clear (`None`) the item's hand-set price.
+    SetPrice(Option<f64>),
+}
+
+/// What the frontend has left to do after `apply_item_action`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionEffect {
+    /// Put this text on the clipboard.
+    Copy(String),
+    /// Settings changed and should be saved.
+    SettingsChanged,
+    /// Nothing changed (e.g. an invalid price).
+    None,
+}
+
+/// Apply `action` to the item `id` (displayed as `name`).
+pub fn apply_item_action(
+    settings: &mut Settings,
+    id: &str,
+    name: &str,
+    action: ItemAction,
+) -> ActionEffect {
+    match action {
+        ItemAction::CopyId => ActionEffect::Copy(id.to_string()),
+        ItemAction::CopyName => ActionEffect::Copy(name.to_string()),
+        ItemAction::TogglePin => {
+            if !settings.pinned_items.remove(id) {
+                settings.pinned_items.insert(id.to_string());
+            }
+            ActionEffect::SettingsChanged
+        }
+        ItemAction::Ignore => {
+            if settings.ignored_items.insert(id.to_string()) {
+                ActionEffect::SettingsChanged
+            } else {
+                ActionEffect::None
+            }
+        }
+        ItemAction::SetPrice(Some(price)) if !price.is_finite() || price < 0.0 => {
+            ActionEffect::None
+        }
+        ItemAction::SetPrice(Some(price)) => {
+            settings.item_prices.insert(id.to_string(), price);
+            ActionEffect::SettingsChanged
+        }
+        ItemAction::SetPrice(None) => {
+            if settings.item_prices.remove(id).is_some() {
+                ActionEffect::SettingsChanged
+            } else {
+                ActionEffect::None
+            }
+        }
+    }
+}
+
+/// Something done to every selected row at once, from the bulk-action bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    Ignore,
+    Pin,
+    /// Copy the selected items as `item_name,config_base_id` CSV.
+    Export,
+}
+
+/// Apply `action` to every item in `selection` (id → displayed name).
+pub fn apply_bulk_action(
+    settings: &mut Settings,
+    selection: &BTreeMap<String, String>,
+    action: BulkAction,
+) -> ActionEffect {
+    let changed = match action {
+        BulkAction::Ignore => selection.keys().fold(false, |changed, id| {
+            settings.ignored_items.insert(id.clone()) | changed
+        }),
+        BulkAction::Pin => selection.keys().fold(false, |changed, id| {
+            settings.pinned_items.insert(id.clone()) | changed
+        }),
+        BulkAction::Export => {
+            let mut csv = String::from("item_name,config_base_id\n");
+            for (id, name) in selection {
+                csv.push_str(&format!(
+                    "{},{}\n",
+                    crate::inventory::csv_field(name),
+                    crate::inventory::csv_field(id)
+                ));
+            }
+            return ActionEffect::Copy(csv);
+        }
+    };
+    if changed {
+        ActionEffect::SettingsChanged
+    } else {
+        ActionEffect::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::FLAME_ELEMENTIUM_ID;
+    use crate::tracker::MapRun;
+    use std::collections::HashMap;
+
+    fn session_with_loot() -> TrackerSession {
+        let mut session = TrackerSession::new();
+        session
+            .cumulative_loot
+            .insert(FLAME_ELEMENTIUM_ID.to_string(), 120);
+        session.cumulative_loot.insert("200100".to_string(), -3);
+        session.cumulative_loot.insert("6002".to_string(), 7);
+        session
+    }
+
+    fn summary(fe_delta: i64, total_events: usize) -> LootSummary {
+        LootSummary {
+            items: vec![ItemDelta {
+                config_base_id: FLAME_ELEMENTIUM_ID.to_string(),
+                item_name: "Flame Elementium".to_string(),
+                delta: fe_delta,
+                current: 600,
+            }],
+            total_events,
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 0,
+            anomalies: Default::default(),
+            game_start: None,
+            events_seen: true,
+        }
+    }
+
+    #[test]
+    fn test_tab_round_trips_and_unknown_falls_back() {
+        for tab in Tab::ALL {
+            let json = serde_json::to_string(&tab).unwrap();
+            assert_eq!(serde_json::from_str::<Tab>(&json).unwrap(), tab);
+        }
+        assert_eq!(serde_json::to_string(&Tab::Runs).unwrap(), "\"runs\"");
+        assert_eq!(
+            serde_json::from_str::<Tab>("\"Charts\"").unwrap(),
+            Tab::FlameElementium
+        );
+    }
+
+    #[test]
+    fn test_view_without_session_or_log() {
+        let view = TrackerView::from(None, None, None, &Settings::default(), Utc::now());
+        assert!(!view.active);
+        assert_eq!(view.map, None);
+        assert_eq!(view.time, "--:--");
+        assert_eq!(view.primary, 0);
+        assert_eq!(view.primary_per_hour, 0.0);
+        assert_eq!(view.total_events, 0);
+
+        let stats = view.stats(&StatKind::ALL, &NumberLocale::US, false);
+        let labels: Vec<_> = stats.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["MAP", "TIME", "FE", "FE/HR", "LAST 3", "ITEMS", "RUNS"]
+        );
+        assert_eq!(stats[0].value, "-");
+        assert_eq!(stats[1].value, "--:--");
+        assert!(stats[2..].iter().all(|s| s.value == "-"));
+    }
+
+    #[test]
+    fn test_view_without_session_shows_log_fe() {
+        let loot = summary(42, 3);
+        let view = TrackerView::from(
+            Some("Abyss"),
+            None,
+            Some(&loot),
+            &Settings::default(),
+            Utc::now(),
+        );
+        assert!(!view.active);
+        assert_eq!(view.map.as_deref(), Some("Abyss"));
+        assert_eq!(view.primary, 42);
+        assert_eq!(view.total_events, 3);
+        assert_eq!(
+            view.stats(&StatKind::ALL, &NumberLocale::US, false)[2].value,
+            "-"
+        );
+    }
+
+    #[test]
+    fn test_view_with_session_uses_now_for_rates() {
+        let mut session = session_with_loot();
+        let start = Utc::now();
+        session.runs.push(MapRun {
+            map_name: "Abyss".to_string(),
+            start,
+            end: Some(start + chrono::Duration::seconds(90)),
+            loot_gained: HashMap::new(),
+            paused_secs: 0.0,
+            tier: None,
+        });
+        let now = session.start_wall + chrono::Duration::minutes(30);
+        let loot = summary(999, 7);
+
+        let view = TrackerView::from(
+            Some("Abyss"),
+            Some(&session),
+            Some(&loot),
+            &Settings::default(),
+            now,
+        );
+        assert!(view.active);
+        assert_eq!(view.started_at, Some(session.start_wall));
+        assert_eq!(view.elapsed_secs, 1800.0);
+        assert_eq!(view.time, "30:00");
+        // Session totals win over the raw log delta.
+        assert_eq!(view.primary, 120);
+        assert_eq!(view.primary_per_hour, 240.0);
+        assert_eq!(view.items, 124);
+        assert_eq!(view.runs, 1);
+        assert_eq!(view.total_events, 7);
+
+        let values: Vec<_> = view
+            .stats(&StatKind::ALL, &NumberLocale::US, false)
+            .into_iter()
+            .map(|s| s.value)
+            .collect();
+        assert_eq!(values, ["Abyss", "30:00", "120", "240", "0", "124", "1"]);
+    }
+
+    #[test]
+    fn test_resolve_each_stat_kind() {
+        let session = session_with_loot();
+        let now = session.start_wall + chrono::Duration::minutes(30);
+        let loot = summary(999, 7);
+        let settings = Settings::default();
+        let view = TrackerView::from(Some("Abyss"), Some(&session), Some(&loot), &settings, now);
+        let resolve = |kind: StatKind| {
+            let stat = kind.resolve(&view, &NumberLocale::US, false);
+            (stat.label, stat.value)
+        };
+        let pair = |label: &str, value: &str| (label.to_string(), value.to_string());
+        assert_eq!(resolve(StatKind::Map), pair("MAP", "Abyss"));
+        assert_eq!(resolve(StatKind::Time), pair("TIME", "30:00"));
+        assert_eq!(resolve(StatKind::Primary), pair("FE", "120"));
+        assert_eq!(resolve(StatKind::PrimaryPerHour), pair("FE/HR", "240"));
+        assert_eq!(resolve(StatKind::RecentPace), pair("LAST 3", "-"));
+        assert_eq!(resolve(StatKind::Items), pair("ITEMS", "124"));
+        assert_eq!(resolve(StatKind::Runs), pair("RUNS", "0"));
+
+        // Configured order and selection.
+        let labels: Vec<_> = view
+            .stats(
+                &[StatKind::Runs, StatKind::Primary],
+                &NumberLocale::PLAIN,
+                false,
+            )
+            .into_iter()
+            .map(|s| s.label)
+            .collect();
+        assert_eq!(labels, ["RUNS", "FE"]);
+
+        // Abbreviated boxes keep the whole number for tooltips.
+        let mut big = view.clone();
+        big.primary = 123_456;
+        big.primary_per_hour = 1_500_000.0;
+        let kinds = [StatKind::Primary, StatKind::PrimaryPerHour, StatKind::Items];
+        let stats = big.stats(&kinds, &NumberLocale::US, true);
+        assert_eq!(
+            (stats[0].value.as_str(), stats[0].full.as_str()),
+            ("123.5k", "123,456")
+        );
+        assert_eq!(stats[1].value, "1.5m");
+        assert_eq!(stats[2].value, stats[2].full);
+    }
+
+    #[test]
+    fn test_classify_rate() {
+        let status = |rate, target| classify_rate(rate, target).map(|p| p.status);
+        assert_eq!(status(500.0, Some(1000.0)), Some(TargetStatus::Behind));
+        assert_eq!(status(899.0, Some(1000.0)), Some(TargetStatus::Behind));
+        assert_eq!(status(900.0, Some(1000.0)), Some(TargetStatus::Close));
+        assert_eq!(status(1000.0, Some(1000.0)), Some(TargetStatus::OnTarget));
+        assert_eq!(status(1500.0, Some(1000.0)), Some(TargetStatus::OnTarget));
+        assert_eq!(status(1500.0, None), None);
+        assert_eq!(status(1500.0, Some(0.0)), None);
+
+        let delta = |rate| classify_rate(rate, Some(1000.0)).unwrap().delta_label();
+        assert_eq!(delta(1120.0), "+12%");
+        assert_eq!(delta(1000.0), "+0%");
+        assert_eq!(delta(750.0), "-25%");
+    }
+
+    #[test]
+    fn test_rate_stat_carries_target() {
+        let session = session_with_loot();
+        let now = session.start_wall + chrono::Duration::minutes(30);
+        let settings = Settings {
+            target_per_hour: Some(200.0),
+            ..Settings::default()
+        };
+        let view = TrackerView::from(None, Some(&session), None, &settings, now);
+        let target = StatKind::PrimaryPerHour
+            .resolve(&view, &NumberLocale::PLAIN, false)
+            .target
+            .unwrap();
+        assert_eq!(target.status, TargetStatus::OnTarget);
+        assert_eq!(target.delta_label(), "+20%");
+        assert_eq!(
+            StatKind::Primary
+                .resolve(&view, &NumberLocale::PLAIN, false)
+                .target,
+            None
+        );
+
+        let idle = TrackerView::from(None, None, None, &settings, now);
+        assert_eq!(idle.target, None);
+    }
+
+    #[test]
+    fn test_stat_kind_keys() {
+        for kind in StatKind::ALL {
+            assert_eq!(kind.key().parse::<StatKind>(), Ok(kind));
+            assert_eq!(
+                serde_json::to_string(&kind).unwrap(),
+                format!("\"{}\"", kind.key())
+            );
+        }
+        assert!("fe".parse::<StatKind>().is_err());
+    }
+
+    #[test]
+    fn test_item_display_cells() {
+        let cells = |mode: ItemDisplay| mode.cells("Flame Elementium", "100300");
+        assert_eq!(cells(ItemDisplay::Name), ["Flame Elementium"]);
+        assert_eq!(cells(ItemDisplay::Id), ["100300"]);
+        assert_eq!(
+            cells(ItemDisplay::NameAndId),
+            ["Flame Elementium", "100300"]
+        );
+        assert_eq!(ItemDisplay::default().cells("Item", "ID"), ["Item", "ID"]);
+        for mode in ItemDisplay::ALL {
+            assert_eq!(mode.key().parse::<ItemDisplay>(), Ok(mode));
+            assert_eq!(
+                serde_json::to_string(&mode).unwrap(),
+                format!("\"{}\"", mode.key())
+            );
+        }
+        assert!("both".parse::<ItemDisplay>().is_err());
+    }
+
+    fn currency_6002() -> Settings {
+        Settings {
+            primary_currency: "6002".to_string(),
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn test_view_with_non_fe_primary_currency() {
+        let session = session_with_loot();
+        let now = session.start_wall + chrono::Duration::hours(1);
+        let loot = summary(50, 1);
+
+        let view = TrackerView::from(None, Some(&session), Some(&loot), &currency_6002(), now);
+        assert_eq!(view.primary, 7);
+        assert_eq!(view.primary_per_hour, 7.0);
+        assert_eq!(view.currency_name, log_parser::item_name("6002"));
+
+        let labels: Vec<_> = view
+            .stats(&StatKind::ALL, &NumberLocale::US, false)
+            .into_iter()
+            .map(|s| s.label)
+            .collect();
+        let currency = view.currency.to_uppercase();
+        assert_eq!(labels[2], currency);
+        assert_eq!(labels[3], format!("{}/HR", currency));
+
+        // Without a session the log delta of that currency is shown (none here).
+        let idle = TrackerView::from(None, None, Some(&loot), &currency_6002(), now);
+        assert_eq!(idle.primary, 0);
+    }
+
+    #[test]
+    fn test_map_rates_use_primary_currency() {
+        let mut session = TrackerSession::new();
+        let mut abyss = run("Abyss", 3600, 100);
+        abyss.loot_gained.insert("6002".to_string(), 12);
+        session.runs = vec![abyss];
+        assert_eq!(map_rates(&session, "6002")[0].per_hour, 12.0);
+    }
+
+    #[test]
+    fn test_view_rate_is_zero_in_first_second() {
+        let session = session_with_loot();
+        let view = TrackerView::from(
+            None,
+            Some(&session),
+            None,
+            &Settings::default(),
+            session.start_wall,
+        );
+        assert_eq!(view.elapsed_secs, 0.0);
+        assert_eq!(view.primary_per_hour, 0.0);
+    }
+
+    #[test]
+    fn test_view_clamps_clock_skew() {
+        let session = session_with_loot();
+        let before_start = session.start_wall - chrono::Duration::seconds(5);
+        let view = TrackerView::from(
+            None,
+            Some(&session),
+            None,
+            &Settings::default(),
+            before_start,
+        );
+        assert_eq!(view.elapsed_secs, 0.0);
+        assert_eq!(view.time, "00:00");
+    }
+
+    #[test]
+    fn test_view_serializes_display_fields() {
+        let session = session_with_loot();
+        let now = session.start_wall + chrono::Duration::hours(2);
+        let value = serde_json::to_value(TrackerView::from(
+            Some("Abyss"),
+            Some(&session),
+            None,
+            &Settings::default(),
+            now,
+        ))
+        .unwrap();
+        for key in [
+            "map",
+            "active",
+            "started_at",
+            "elapsed_secs",
+            "time",
+            "currency",
+            "currency_name",
+            "primary",
+            "primary_per_hour",
+            "recent_per_hour",
+            "pace_alert",
+            "items",
+            "runs",
+            "total_events",
+        ] {
+            assert!(value.get(key).is_some(), "missing key {key}");
+        }
+        assert_eq!(value["primary_per_hour"], 60.0);
+    }
+
+    #[test]
+    fn test_loot_rows_prefer_session_sorted_by_magnitude() {
+        let session = session_with_loot();
+        let rows = loot_rows(Some(&session), None, &Settings::default());
+        let ids: Vec<_> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, [FLAME_ELEMENTIUM_ID, "6002", "200100"]);
+        assert!(rows.iter().all(|r| r.current.is_none()));
+    }
+
+    #[test]
+    fn test_loot_rows_pair_session_with_open_run() {
+        let mut session = session_with_loot();
+        let start = Utc::now();
+        session.runs.push(MapRun {
+            map_name: "Done".to_string(),
+            start,
+            end: Some(start),
+            loot_gained: HashMap::from([(FLAME_ELEMENTIUM_ID.to_string(), 100)]),
+            paused_secs: 0.0,
+            tier: None,
+        });
+        session.runs.push(MapRun {
+            map_name: "Open".to_string(),
+            start,
+            end: None,
+            loot_gained: HashMap::from([
+                (FLAME_ELEMENTIUM_ID.to_string(), 20),
+                ("6002".to_string(), 7),
+            ]),
+            paused_secs: 0.0,
+            tier: None,
+        });
+        let rows = loot_rows(Some(&session), None, &Settings::default());
+        let pairs: Vec<_> = rows
+            .iter()
+            .map(|r| (r.id.as_str(), r.run, r.delta))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                (FLAME_ELEMENTIUM_ID, Some(20), 120),
+                ("6002", Some(7), 7),
+                ("200100", Some(0), -3),
+            ]
+        );
+        let top: Vec<_> = rows.iter().map(|r| r.top_map.clone()).collect();
+        assert_eq!(
+            top,
+            [
+                Some(("Done".to_string(), 100)),
+                Some(("Open".to_string(), 7)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_contributions_across_maps() {
+        let mut abyss = run("Abyss", 600, 30);
+        abyss.loot_gained.insert("6002".to_string(), 2);
+        let mut rift = run("Rift", 600, 50);
+        rift.loot_gained.insert("6003".to_string(), 1);
+        let runs = [abyss.clone(), rift, abyss];
+        let contributions = map_contributions(&runs);
+
+        let fe = &contributions[FLAME_ELEMENTIUM_ID];
+        assert_eq!(
+            fe,
+            &BTreeMap::from([("Abyss".to_string(), 60), ("Rift".to_string(), 50)])
+        );
+        assert_eq!(top_map(fe), Some(("Abyss", 60)));
+        // Items from one map only.
+        assert_eq!(top_map(&contributions["6002"]), Some(("Abyss", 4)));
+        assert_eq!(top_map(&contributions["6003"]), Some(("Rift", 1)));
+        assert!(!contributions.contains_key("200100"));
+
+        // Ties go to the first map by name; losses never win.
+        let tied = BTreeMap::from([("Rift".to_string(), 5), ("Abyss".to_string(), 5)]);
+        assert_eq!(top_map(&tied), Some(("Abyss", 5)));
+        let spent = BTreeMap::from([("Abyss".to_string(), -3)]);
+        assert_eq!(top_map(&spent), None);
+    }
+
+    #[test]
+    fn test_combined_loot_rows() {
+        assert!(combined_loot_rows(&HashMap::new(), None).is_empty());
+
+        let session = HashMap::from([("6002".to_string(), 4), ("6003".to_string(), 4)]);
+        // No open run: every run column is zero, ties broken by id.
+        let rows = combined_loot_rows(&session, None);
+        assert_eq!(rows[0].id, "6002");
+        assert!(rows.iter().all(|r| r.run == Some(0)));
+
+        // Equal session totals sort by this run's gain.
+        let run = HashMap::from([("6003".to_string(), 2), ("6999".to_string(), 1)]);
+        let rows = combined_loot_rows(&session, Some(&run));
+        let pairs: Vec<_> = rows
+            .iter()
+            .map(|r| (r.id.as_str(), r.run, r.delta))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("6003", Some(2), 4),
+                ("6002", Some(0), 4),
+                ("6999", Some(1), 0),
+            ]
+        );
+        assert_eq!(rows[0].name, log_parser::item_name("6003"));
+    }
+
+    #[test]
+    fn test_loot_rows_from_log_without_session() {
+        let loot = LootSummary {
+            items: vec![ItemDelta {
+                config_base_id: FLAME_ELEMENTIUM_ID.to_string(),
+                item_name: "Flame Elementium".to_string(),
+                delta: 5,
+                current: 605,
+            }],
+            total_events: 1,
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 0,
+            anomalies: Default::default(),
+            game_start: None,
+            events_seen: true,
+        };
+        let rows = loot_rows(None, Some(&loot), &Settings::default());
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].current, Some(605));
+        assert_eq!(rows[0].run, None);
+        assert!(loot_rows(None, None, &Settings::default()).is_empty());
+    }
+
+    #[test]
+    fn test_ignored_items_hidden_and_not_counted() {
+        let session = session_with_loot();
+        let mut settings = Settings::default();
+        settings.ignored_items.insert("6002".to_string());
+
+        let rows = loot_rows(Some(&session), None, &settings);
+        let ids: Vec<_> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, [FLAME_ELEMENTIUM_ID, "200100"]);
+        assert_eq!(item_count(&session.cumulative_loot, &settings), 117);
+        let view = TrackerView::from(None, Some(&session), None, &settings, Utc::now());
+        assert_eq!(view.items, 117);
+
+        // Counting them back in leaves them hidden.
+        settings.count_ignored = true;
+        assert_eq!(item_count(&session.cumulative_loot, &settings), 124);
+        assert_eq!(loot_rows(Some(&session), None, &settings).len(), 2);
+
+        let mut loot = summary(5, 1);
+        loot.items.push(ItemDelta {
+            config_base_id: "6002".to_string(),
+            item_name: log_parser::item_name("6002"),
+            delta: 3,
+            current: 3,
+        });
+        let visible = visible_items(&loot, &settings);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].config_base_id, FLAME_ELEMENTIUM_ID);
+        assert_eq!(loot_rows(None, Some(&loot), &settings).len(), 1);
+    }
+
+    #[test]
+    fn test_pinned_first_keeps_order_within_groups() {
+        let mut rows = vec!["a", "b", "c", "d", "e"];
+        let pinned = BTreeSet::from(["d".to_string(), "b".to_string()]);
+        pinned_first(&mut rows, &pinned, |r| r);
+        assert_eq!(rows, ["b", "d", "a", "c", "e"]);
+
+        pinned_first(&mut rows, &BTreeSet::new(), |r| r);
+        assert_eq!(rows, ["b", "d", "a", "c", "e"]);
+    }
+
+    #[test]
+    fn test_loot_rows_pinned_items_first() {
+        let session = session_with_loot();
+        let mut settings = Settings::default();
+        settings.pinned_items.insert("200100".to_string());
+        let rows = loot_rows(Some(&session), None, &settings);
+        let ids: Vec<_> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["200100", FLAME_ELEMENTIUM_ID, "6002"]);
+    }
+
+    #[test]
+    fn test_item_actions() {
+        let mut settings = Settings::default();
+        let mut act = |action| apply_item_action(&mut settings, "6002", "Ember", action);
+        assert_eq!(
+            act(ItemAction::CopyId),
+            ActionEffect::Copy("6002".to_string())
+        );
+        assert_eq!(
+            act(ItemAction::CopyName),
+            ActionEffect::Copy("Ember".to_string())
+        );
+        assert_eq!(act(ItemAction::TogglePin), ActionEffect::SettingsChanged);
+        assert_eq!(act(ItemAction::Ignore), ActionEffect::SettingsChanged);
+        assert_eq!(act(ItemAction::Ignore), ActionEffect::None);
+        assert_eq!(
+            act(ItemAction::SetPrice(Some(2.5))),
+            ActionEffect::SettingsChanged
+        );
+        assert_eq!(act(ItemAction::SetPrice(Some(-1.0))), ActionEffect::None);
+        assert_eq!(
+            act(ItemAction::SetPrice(Some(f64::NAN))),
+            ActionEffect::None
+        );
+        assert!(settings.pinned_items.contains("6002"));
+        assert!(settings.is_ignored("6002"));
+        assert_eq!(settings.item_prices.get("6002"), Some(&2.5));
+
+        let mut act = |action| apply_item_action(&mut settings, "6002", "Ember", action);
+        assert_eq!(act(ItemAction::TogglePin), ActionEffect::SettingsChanged);
+        assert_eq!(
+            act(ItemAction::SetPrice(None)),
+            ActionEffect::SettingsChanged
+        );
+        assert_eq!(act(ItemAction::SetPrice(None)), ActionEffect::None);
+        assert!(settings.pinned_items.is_empty());
+        assert!(settings.item_prices.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_ignore_pin_and_export() {
+        let mut settings = Settings::default();
+        settings.ignored_items.insert("6002".to_string());
+        let selection = BTreeMap::from([
+            ("5028".to_string(), "Netherrealm resonance".to_string()),
+            ("6002".to_string(), "Glacial, key".to_string()),
+        ]);
+
+        assert_eq!(
+            apply_bulk_action(&mut settings, &selection, BulkAction::Ignore),
+            ActionEffect::SettingsChanged
+        );
+        assert!(settings.is_ignored("5028"));
+        assert!(settings.is_ignored("6002"));
+        assert_eq!(settings.ignored_items.len(), 2);
+        // Everything already ignored: nothing to save.
+        assert_eq!(
+            apply_bulk_action(&mut settings, &selection, BulkAction::Ignore),
+            ActionEffect::None
+        );
+
+        assert_eq!(
+            apply_bulk_action(&mut settings, &selection, BulkAction::Pin),
+            ActionEffect::SettingsChanged
+        );
+        assert_eq!(settings.pinned_items.len(), 2);
+
+        assert_eq!(
+            apply_bulk_action(&mut settings, &selection, BulkAction::Export),
+            ActionEffect::Copy(
+                "item_name,config_base_id\n\
+                 Netherrealm resonance,5028\n\
+                 \"Glacial, key\",6002\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            apply_bulk_action(&mut settings, &BTreeMap::new(), BulkAction::Pin),
+            ActionEffect::None
+        );
+    }
+
+    #[test]
+    fn test_sort_reminder_due() {
+        let now: DateTime<Utc> = "2026-10-16T12:00:00Z".parse().unwrap();
+        let hour = chrono::Duration::hours(1);
+        assert!(sort_reminder_due(None, now, hour));
+        assert!(!sort_reminder_due(
+            Some(now - chrono::Duration::minutes(59)),
+            now,
+            hour
+        ));
+        assert!(sort_reminder_due(Some(now - hour), now, hour));
+        // Turned off.
+        assert!(!sort_reminder_due(None, now, chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn test_sort_reminder_text() {
+        let now: DateTime<Utc> = "2026-10-16T12:00:00Z".parse().unwrap();
+        let settings = Settings::default();
+        let mut loot = summary(0, 0);
+        // Baseline from a sort without a timestamp: nothing to go on.
+        assert_eq!(sort_reminder(Some(&loot), &settings, now), None);
+
+        loot.last_sort_at = Some(now - chrono::Duration::minutes(90));
+        let text = sort_reminder(Some(&loot), &settings, now).unwrap();
+        assert!(text.contains("90 min ago"), "{text}");
+
+        // Intervals too long to represent never come due.
+        for mins in [u64::MAX, 1 << 62] {
+            let settings = Settings {
+                sort_reminder_mins: mins,
+                ..Settings::default()
+            };
+            assert_eq!(sort_reminder(Some(&loot), &settings, now), None);
+        }
+
+        loot.baseline_found = false;
+        loot.last_sort_at = None;
+        assert!(sort_reminder(Some(&loot), &settings, now)
+            .unwrap()
+            .starts_with("No inventory sort"));
+
+        loot.events_seen = false;
+        assert_eq!(sort_reminder(Some(&loot), &settings, now), None);
+        assert_eq!(sort_reminder(None, &settings, now), None);
+    }
+
+    #[test]
+    fn test_run_rows_newest_first() {
+        let mut session = TrackerSession::new();
+        let start = Utc::now();
+        for (name, secs) in [("First", 65), ("Second", 130)] {
+            session.runs.push(MapRun {
+                map_name: name.to_string(),
+                start,
+                end: Some(start + chrono::Duration::seconds(secs)),
+                loot_gained: HashMap::from([("6002".to_string(), 2)]),
+                paused_secs: 0.0,
+                tier: None,
+            });
+        }
+        let rows = run_rows(&session, &Settings::default());
+        let started = start.with_timezone(&Local).format("%H:%M:%S").to_string();
+        assert_eq!(rows[0].started, started);
+        assert_eq!(rows[0].map, "Second");
+        assert_eq!(rows[0].duration, "2:10");
+        assert_eq!(rows[1].duration, "1:05");
+        assert_eq!(rows[1].items, 2);
+    }
+
+    #[test]
+    fn test_format_abbrev() {
+        let us = &NumberLocale::US;
+        assert_eq!(format_abbrev(0, us), "0");
+        assert_eq!(format_abbrev(999, us), "999");
+        assert_eq!(format_abbrev(1000, us), "1k");
+        assert_eq!(format_abbrev(123_456, us), "123.5k");
+        assert_eq!(format_abbrev(999_949, us), "999.9k");
+        assert_eq!(format_abbrev(999_950, us), "1m");
+        assert_eq!(format_abbrev(1_500_000, us), "1.5m");
+        assert_eq!(format_abbrev(2_345_678_901, us), "2345.7m");
+        assert_eq!(format_abbrev(-999, us), "-999");
+        assert_eq!(format_abbrev(-1_500, us), "-1.5k");
+        assert_eq!(format_abbrev(-1_500_000, &NumberLocale::EU), "-1,5m");
+        assert_eq!(format_abbrev(i64::MIN, us), "-9223372036854.8m");
+    }
+
+    #[test]
+    fn test_formatters() {
+        assert_eq!(format_clock(0.0), "00:00");
+        assert_eq!(format_clock(3725.0), "62:05");
+        assert_eq!(format_delta(5, &NumberLocale::PLAIN), "+5");
+        assert_eq!(format_delta(-3, &NumberLocale::PLAIN), "-3");
+        assert_eq!(format_delta(0, &NumberLocale::PLAIN), "0");
+        assert_eq!(format_delta(12345, &NumberLocale::US), "+12,345");
+        assert_eq!(format_delta(-12345, &NumberLocale::US), "-12,345");
+        assert_eq!(format_delta(-12345, &NumberLocale::EU), "-12.345");
+    }
+
+    #[test]
+    fn test_session_id_label() {
+        let id = "0b6f5c1e-1111-4222-8333-444455556666";
+        assert_eq!(session_id_label(id, false), "0b6f5c1e");
+        assert_eq!(session_id_label(id, true), id);
+        assert_eq!(session_id_label("abc", false), "abc");
+        assert_eq!(session_id_label("ééééééééé", false), "éééééééé");
+    }
+
+    #[test]
+    fn test_format_number() {
+        let (us, eu, plain) = (NumberLocale::US, NumberLocale::EU, NumberLocale::PLAIN);
+        assert_eq!(format_number(1234.5, 0, &us), "1,235");
+        assert_eq!(format_number(1234.56, 1, &us), "1,234.6");
+        assert_eq!(format_number(1234.5, 2, &plain), "1234.50");
+        let fr = NumberLocale::preset("FR").unwrap();
+        assert_eq!(format_number(1234567.891, 3, &fr), "1 234 567,891");
+        assert_eq!(format_number(1234.56, 2, &eu), "1.234,56");
+        assert_eq!(format_number(1234.5, 0, &eu), "1.235");
+        assert_eq!(format_number(-1234.5, 1, &us), "-1,234.5");
+        assert_eq!(format_number(-1234.56, 2, &eu), "-1.234,56");
+        assert_eq!(format_number(-1234.56, 2, &eu.ungrouped()), "-1234,56");
+        assert_eq!(format_number(-0.04, 1, &plain), "0.0");
+        assert_eq!(format_number(-0.04, 1, &eu), "0,0");
+        assert_eq!(format_number(0.0, 0, &plain), "0");
+        assert_eq!(format_number(99.95, 2, &plain), "99.95");
+        assert_eq!(NumberLocale::preset("xx"), None);
+    }
+
+    #[test]
+    fn test_score_label() {
+        let mut settings = Settings::default();
+        assert_eq!(score_label(1234.0, &settings), "Items: 1,234");
+        settings.score_mode = ScoreMode::Priced;
+        assert_eq!(score_label(1234.0, &settings), "Value: 1,234.00");
+        settings.score_mode = ScoreMode::Weighted;
+        settings.value_precision = 1;
+        assert_eq!(score_label(-2.25, &settings), "Score: -2.2");
+    }
+
+    #[test]
+    fn test_last_session_line() {
+        let last = FinalizedSession {
+            ended_at: Utc::now(),
+            elapsed_secs: 1800.0,
+            primary: 1500,
+            runs: 3,
+        };
+        assert_eq!(
+            last_session_line(&last, "FE", 0, &NumberLocale::US),
+            "Last session: 1,500 FE in 30:00 (3,000 FE / hour, 3 runs)"
+        );
+    }
+
+    #[test]
+    fn test_format_count() {
+        let sep = &NumberLocale::US;
+        assert_eq!(format_count(0, sep), "0");
+        assert_eq!(format_count(999, sep), "999");
+        assert_eq!(format_count(1000, sep), "1,000");
+        assert_eq!(format_count(123456, sep), "123,456");
+        assert_eq!(format_count(1234567, sep), "1,234,567");
+        assert_eq!(format_count(-1234, sep), "-1,234");
+        assert_eq!(format_count(-999, sep), "-999");
+        assert_eq!(format_count(i64::MIN, sep), "-9,223,372,036,854,775,808");
+        assert_eq!(format_count(1234567, &NumberLocale::EU), "1.234.567");
+        assert_eq!(format_count(-1234567, &NumberLocale::EU), "-1.234.567");
+        assert_eq!(format_count(1234567, &NumberLocale::PLAIN), "1234567");
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        assert_eq!(action_for_key('s'), Some(Action::ToggleSession));
+        assert_eq!(action_for_key('3'), Some(Action::SelectTab(Tab::Inventory)));
+        assert_eq!(action_for_key('x'), None);
+        assert_eq!(Tab::Runs.next(), Tab::Settings);
+        assert_eq!(Tab::Settings.next(), Tab::FlameElementium);
+    }
+
+    fn run(map: &str, secs: i64, fe: i64) -> MapRun {
+        let start = Utc::now();
+        MapRun {
+            map_name: map.to_string(),
+            start,
+            end: Some(start + chrono::Duration::seconds(secs)),
+            loot_gained: HashMap::from([(FLAME_ELEMENTIUM_ID.to_string(), fe)]),
+            paused_secs: 0.0,
+            tier: None,
+        }
+    }
+
+    #[test]
+    fn test_map_rates_average_runs_per_map() {
+        let mut session = TrackerSession::new();
+        session.runs = vec![
+            run("Abyss", 600, 100),
+            run("Rift", 1800, 300),
+            run("Abyss", 1200, 100),
+        ];
+        let rates = map_rates(&session, FLAME_ELEMENTIUM_ID);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].map, "Abyss");
+        assert_eq!(rates[0].runs, 2);
+        // (600 + 300) / 2
+        assert!((rates[0].per_hour - 450.0).abs() < 1e-9);
+        assert_eq!(rates[1].map, "Rift");
+        assert_eq!(rates[1].runs, 1);
+        assert!((rates[1].per_hour - 600.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recent_pace_averages_last_completed_runs() {
+        let mut session = TrackerSession::new();
+        assert_eq!(recent_pace(&session, FLAME_ELEMENTIUM_ID, 3), None);
+
+        // 100/h, 200/h, 300/h, 400/h, then an open run that is ignored.
+        session.runs = vec![
+            run("A", 3600, 100),
+            run("B", 3600, 200),
+            run("C", 1800, 150),
+            run("D", 900, 100),
+        ];
+        let mut open = run("E", 60, 9999);
+        open.end = None;
+        session.runs.push(open);
+
+        let pace = recent_pace(&session, FLAME_ELEMENTIUM_ID, 3).unwrap();
+        assert!((pace - 300.0).abs() < 1e-9);
+        let pace = recent_pace(&session, FLAME_ELEMENTIUM_ID, 1).unwrap();
+        assert!((pace - 400.0).abs() < 1e-9);
+        assert_eq!(recent_pace(&session, FLAME_ELEMENTIUM_ID, 0), None);
+    }
+
+    #[test]
+    fn test_recent_pace_with_fewer_runs_than_window() {
+        let mut session = TrackerSession::new();
+        session.runs = vec![run("A", 3600, 100), run("B", 3600, 300)];
+        let pace = recent_pace(&session, FLAME_ELEMENTIUM_ID, 5).unwrap();
+        assert!((pace - 200.0).abs() < 1e-9);
+
+        let view = TrackerView::from(
+            None,
+            Some(&session),
+            None,
+            &Settings {
+                recent_runs: 5,
+                ..Settings::default()
+            },
+            session.start_wall + chrono::Duration::hours(2),
+        );
+        let recent = &view.stats(&StatKind::ALL, &NumberLocale::PLAIN, false)[4];
+        assert_eq!(recent.label, "LAST 5");
+        assert_eq!(recent.value, "200");
+    }
+
+    #[test]
+    fn test_sustained_below() {
+        assert!(!sustained_below(&[], 100.0, 2));
+        assert!(!sustained_below(&[50.0], 100.0, 2));
+        assert!(sustained_below(&[50.0, 60.0], 100.0, 2));
+        // A recovery at the end clears the alert.
+        assert!(!sustained_below(&[50.0, 60.0, 150.0], 100.0, 2));
+        // Only the trailing window matters.
+        assert!(sustained_below(&[150.0, 50.0, 60.0], 100.0, 2));
+        // Alternating samples never sustain.
+        assert!(!sustained_below(&[50.0, 150.0, 50.0, 150.0], 100.0, 2));
+        // The floor itself is not below.
+        assert!(!sustained_below(&[100.0, 100.0], 100.0, 2));
+        assert!(!sustained_below(&[1.0, 1.0], 100.0, 0));
+    }
+
+    #[test]
+    fn test_window_rate() {
+        assert_eq!(window_rate(&[], 60.0, 60.0), None);
+        // 100 gained in the last minute: the sample at 30s is the baseline.
+        let samples = [(0.0, 0), (30.0, 50), (60.0, 100), (90.0, 150)];
+        assert_eq!(window_rate(&samples, 90.0, 60.0), Some(6000.0));
+        // Nothing new since the baseline.
+        assert_eq!(window_rate(&samples, 200.0, 60.0), Some(0.0));
+        // Only 20s of history: the rate covers those 20s.
+        assert_eq!(
+            window_rate(&[(0.0, 0), (20.0, 10)], 20.0, 60.0),
+            Some(1800.0)
+        );
+        assert_eq!(window_rate(&[(5.0, 10)], 5.0, 60.0), None);
+    }
+
+    #[test]
+    fn test_live_rate_follows_session() {
+        let mut session = TrackerSession::new();
+        let start = session.start_wall;
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        let mut live = LiveRate::default();
+        for (secs, fe) in [(0, 0), (30, 100), (90, 400), (120, 400)] {
+            session
+                .cumulative_loot
+                .insert(FLAME_ELEMENTIUM_ID.to_string(), fe);
+            live.record(Some(&session), FLAME_ELEMENTIUM_ID, at(secs));
+        }
+        // Old samples are dropped, bar the baseline at 30s.
+        assert_eq!(live.samples.len(), 3);
+        assert_eq!(
+            live.per_hour(&session, at(120)),
+            Some(300.0 / 90.0 * 3600.0)
+        );
+
+        let restarted = TrackerSession {
+            start_wall: at(200),
+            ..TrackerSession::new()
+        };
+        assert_eq!(live.per_hour(&restarted, at(210)), None);
+        live.record(None, FLAME_ELEMENTIUM_ID, at(200));
+        assert!(live.samples.is_empty());
+    }
+
+    #[test]
+    fn test_pace_series_and_alert() {
+        let mut session = TrackerSession::new();
+        // 600/h, then two slow runs at 100/h.
+        session.runs = vec![
+            run("A", 3600, 600),
+            run("B", 3600, 100),
+            run("C", 3600, 100),
+        ];
+        let series = pace_series(&session, FLAME_ELEMENTIUM_ID, 2);
+        assert_eq!(series, [600.0, 350.0, 100.0]);
+
+        let now = session.start_wall + chrono::Duration::hours(3);
+        let mut settings = Settings {
+            recent_runs: 2,
+            pace_floor: Some(400.0),
+            ..Settings::default()
+        };
+        let view = TrackerView::from(None, Some(&session), None, &settings, now);
+        assert_eq!(
+            view.pace_alert.as_deref(),
+            Some("FE/hour has stayed below 400 for the last 2 runs")
+        );
+
+        settings.pace_alert_runs = 3;
+        let view = TrackerView::from(None, Some(&session), None, &settings, now);
+        assert_eq!(view.pace_alert, None);
+
+        settings.pace_floor = None;
+        let view = TrackerView::from(None, Some(&session), None, &settings, now);
+        assert_eq!(view.pace_alert, None);
+    }
+
+    #[test]
+    fn test_map_rates_single_and_empty() {
+        let mut session = TrackerSession::new();
+        assert!(map_rates(&session, FLAME_ELEMENTIUM_ID).is_empty());
+
+        session.runs = vec![run("Abyss", 0, 50)];
+        let rates = map_rates(&session, FLAME_ELEMENTIUM_ID);
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].runs, 1);
+        assert_eq!(rates[0].per_hour, 0.0);
+    }
+}
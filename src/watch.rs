@@ -0,0 +1,196 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::{debug_span, warn};
+
+use crate::log_parser::{self, LootSummary};
+use crate::models::Session;
+use crate::settings::{self, Settings};
+use crate::storage;
+use crate::viewmodel;
+
+/// ANSI sequence: clear the screen and move the cursor home.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Render one frame of the watch view.
+///
+/// `loot` is the latest parse of the game log, if one was found; `settings`
+/// names the currency to report from it and how to format values.
+pub fn render(
+    session: Option<&Session>,
+    loot: Option<&LootSummary>,
+    settings: &Settings,
+    now: DateTime<Utc>,
+) -> String {
+    let primary = settings.primary_currency.as_str();
+    let locale = settings.number_locale();
+    let value = |v: f64| viewmodel::format_number(v, settings.value_precision, &locale);
+    let mut out = String::new();
+    out.push_str("TLI Tracker – watch (Ctrl-C to quit)\n\n");
+
+    match session {
+        Some(session) => {
+            let end = session.end_time.unwrap_or(now);
+            let elapsed = (end - session.start_time).num_seconds().max(0);
+            out.push_str(&format!("Session: {}\n", session.id));
+            out.push_str(&format!("Map: {}\n", session.map));
+            out.push_str(&format!(
+                "Status: {}\n",
+                if session.is_active() {
+                    "active"
+                } else {
+                    "ended"
+                }
+            ));
+            out.push_str(&format!(
+                "Elapsed: {:02}:{:02}:{:02}\n",
+                elapsed / 3600,
+                (elapsed % 3600) / 60,
+                elapsed % 60
+            ));
+            out.push_str(&format!("Drops: {}\n", session.drops.len()));
+            out.push_str(&format!("Total value: {}\n", value(session.total_value())));
+            if elapsed > 0 {
+                let per_min = session.total_value() / (elapsed as f64 / 60.0);
+                out.push_str(&format!("Profit/min: {}\n", value(per_min)));
+            }
+        }
+        None => out.push_str("Waiting for an active session...\n"),
+    }
+
+    out.push('\n');
+    match loot {
+        Some(loot) => {
+            out.push_str(&format!(
+                "{} (log): {}\n",
+                log_parser::display_name(primary, &settings.item_aliases),
+                viewmodel::format_delta(loot.primary_delta(primary), &locale)
+            ));
+            out.push_str(&format!("Loot events: {}\n", loot.total_events));
+        }
+        None => out.push_str("Game log: not found\n"),
+    }
+    out
+}
+
+/// Pick the session to show: the requested id, else the active session.
+fn pick_session(sessions: &[Session], requested: Option<&str>) -> Option<Session> {
+    match requested {
+        Some(id) => sessions.iter().find(|s| s.id == id).cloned(),
+        None => sessions.iter().find(|s| s.is_active()).cloned(),
+    }
+}
+
+/// Redraw the summary every `interval` until the process is interrupted.
+pub fn run(data_file: &Path, session: Option<String>, interval: Duration) -> io::Result<()> {
+    let stdout = io::stdout();
+    let settings = settings::load(&settings::settings_path(data_file));
+    loop {
+        let frame = {
+            let _span = debug_span!("watch_poll").entered();
+            let sessions = storage::load_sessions(data_file)?;
+            let current = pick_session(&sessions, session.as_deref());
+            let loot = storage::detect_game_log().and_then(|path| {
+                log_parser::parse_loot_from_log(&path, &settings.loot_contexts)
+                    .map_err(|e| warn!(error = %e, "failed to parse loot from log"))
+                    .ok()
+            });
+            render(current.as_ref(), loot.as_ref(), &settings, Utc::now())
+        };
+
+        let mut handle = stdout.lock();
+        write!(handle, "{}{}", CLEAR_SCREEN, frame)?;
+        handle.flush()?;
+        drop(handle);
+
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log_parser::{ItemDelta, FLAME_ELEMENTIUM_ID};
+    use crate::models::DropItem;
+
+    fn session(start: DateTime<Utc>) -> Session {
+        Session {
+            id: "abc".to_string(),
+            map: "Netherrealm".to_string(),
+            notes: None,
+            start_time: start,
+            end_time: None,
+            drops: vec![DropItem {
+                name: "Flame Core".to_string(),
+                quantity: 2,
+                value: 15.0,
+                added_at: None,
+            }],
+            runs: Vec::new(),
+            version: 0,
+            start_inventory: None,
+            end_inventory: None,
+        }
+    }
+
+    #[test]
+    fn test_render_session_with_loot() {
+        let now = Utc::now();
+        let session = session(now - chrono::Duration::minutes(10));
+        let loot = LootSummary {
+            items: vec![ItemDelta {
+                config_base_id: FLAME_ELEMENTIUM_ID.to_string(),
+                item_name: "Flame Elementium".to_string(),
+                delta: 120,
+                current: 700,
+            }],
+            total_events: 4,
+            baseline_found: true,
+            last_sort_at: None,
+            sorts_seen: 0,
+            anomalies: Default::default(),
+            game_start: None,
+            events_seen: true,
+        };
+
+        let frame = render(Some(&session), Some(&loot), &Settings::default(), now);
+        assert!(frame.contains("Session: abc"));
+        assert!(frame.contains("Map: Netherrealm"));
+        assert!(frame.contains("Elapsed: 00:10:00"));
+        assert!(frame.contains("Total value: 30.00"));
+        assert!(frame.contains("Profit/min: 3.00"));
+        assert!(frame.contains("Flame Elementium (log): +120"));
+        assert!(frame.contains("Loot events: 4"));
+
+        let mut settings = Settings::default();
+        settings
+            .item_aliases
+            .insert(FLAME_ELEMENTIUM_ID.to_string(), "FE".to_string());
+        let frame = render(Some(&session), Some(&loot), &settings, now);
+        assert!(frame.contains("FE (log): +120"), "{frame}");
+    }
+
+    #[test]
+    fn test_render_waiting_without_session_or_log() {
+        let frame = render(None, None, &Settings::default(), Utc::now());
+        assert!(frame.contains("Waiting for an active session"));
+        assert!(frame.contains("Game log: not found"));
+    }
+
+    #[test]
+    fn test_pick_session_prefers_requested_then_active() {
+        let now = Utc::now();
+        let mut ended = session(now);
+        ended.id = "ended".to_string();
+        ended.end_time = Some(now);
+        let active = session(now);
+
+        let sessions = vec![ended, active];
+        assert_eq!(pick_session(&sessions, None).unwrap().id, "abc");
+        assert_eq!(pick_session(&sessions, Some("ended")).unwrap().id, "ended");
+        assert!(pick_session(&sessions, Some("missing")).is_none());
+    }
+}